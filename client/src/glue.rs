@@ -3,6 +3,13 @@
 //! Don't forget to update public/glue.js with the actual functions.
 
 use wasm_bindgen::prelude::*;
+use wrangler_common::{
+  prelude::{
+    AllWhat,
+    WranglerErrorKind::{self, *},
+  },
+  tools::json,
+};
 
 #[wasm_bindgen(module = "/public/glue.js")]
 extern "C" {
@@ -19,3 +26,20 @@ extern "C" {
   #[wasm_bindgen(js_namespace = console)]
   pub fn log(s: &str);
 }
+
+/// Read the error tree the server rejected a call with
+pub fn decode_error(value: &JsValue) -> AllWhat<WranglerErrorKind> {
+  let text = js_sys::JSON::stringify(value)
+    .ok()
+    .and_then(|text| text.as_string());
+
+  match text.map(|text| json::from_str(&text)) {
+    Some(Ok(err)) => err,
+    _ => {
+      let err: AllWhat<WranglerErrorKind> = UnrecognizedError.into();
+      err
+        .set_context("The server failed without saying why")
+        .set_dev_context(&format!("Rejected with:\n{:?}", value))
+    }
+  }
+}
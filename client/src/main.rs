@@ -2,11 +2,17 @@ use std::rc::Rc;
 
 use tracing::info;
 use wasm_bindgen_futures::spawn_local;
+use wrangler_common::{
+  calls::{self, data, Request},
+  model::ModelNode,
+  prelude::{AllWhat, WranglerErrorKind},
+  tools::json,
+};
 use yew::prelude::*;
 
 pub(crate) mod glue;
 mod views;
-pub use views::organization::*;
+pub use views::{error::*, organization::*};
 
 /// Messages that can be sent to the server for processing
 #[derive(Debug, Clone)]
@@ -15,6 +21,24 @@ enum Call {
   QueryAll,
 }
 
+impl Call {
+  /// Convert the button press into a request for the server
+  fn request(&self) -> Request {
+    let call = match self {
+      Call::LoadCSV => calls::Call::Import {
+        node: ModelNode::Organization,
+        path: "data/organizations.tsv".to_string(),
+      },
+      Call::QueryAll => calls::Call::Data(data::Query::Retrieve {
+        node: ModelNode::Organization,
+        filter: data::Filter::All,
+        page: data::Page::default(),
+      }),
+    };
+    Request::new(call)
+  }
+}
+
 /// Root pages to be displayed in the body of the page
 #[derive(Debug, Clone)]
 enum PageView {
@@ -31,6 +55,9 @@ enum AppAction {
   /// A message that an asynchronous call has been completed and the result should be processed
   Thunk(String),
 
+  /// The server rejected a call
+  Failed(AllWhat<WranglerErrorKind>),
+
   /// Switch the page
   ChangePage(PageView),
 }
@@ -46,33 +73,33 @@ struct AppState {
 
   /// The active page displayed in the body
   current_page: PageView,
+
+  /// Why the last call failed
+  error: Option<AllWhat<WranglerErrorKind>>,
 }
 
 impl AppState {
   /// Perform an update on the state based on the info contained in the call
   pub fn call(self, call: Call) -> Self {
-    let new_state = match call {
+    match call {
       Call::LoadCSV => AppState {
         data_graph: "Loading CSV ...".to_string(),
+        error: None,
         ..self
       },
       Call::QueryAll => AppState {
         data_graph: "Querying All ...".to_string(),
+        error: None,
         ..self
       },
-    };
+    }
+  }
 
-    spawn_local(async move {
-      let call_str = format!("{:?}", call);
-      info!("Spawned the call with a thunk: {:?}", call_str);
-      let result = match glue::call_server(call_str).await {
-        Ok(result) => AppAction::Thunk(format!("Received a call result: {:?}", result)),
-        Err(err) => AppAction::Thunk(format!("Call failed in the end with error: {:?}", err)),
-      };
-      info!("Completed call. Sending thunk: {:?}", result);
-    });
-
-    new_state
+  pub fn fail(self, err: AllWhat<WranglerErrorKind>) -> Self {
+    AppState {
+      error: Some(err),
+      ..self
+    }
   }
 
   pub fn change_page(self, page: PageView) -> Self {
@@ -89,6 +116,7 @@ impl Default for AppState {
       // settings: "No Settings Yet".to_string(),
       data_graph: "Initialized".to_string(),
       current_page: PageView::Organizations,
+      error: None,
     }
   }
 }
@@ -107,11 +135,30 @@ impl Reducible for AppState {
         info!("Processing an async result: {:?}", msg);
         current_state
       }
+      AppAction::Failed(err) => current_state.fail(err),
     };
     new_state.into()
   }
 }
 
+/// Send the call to the server, dispatching the outcome once it completes
+fn send(call: Call, dispatcher: UseReducerDispatcher<AppState>) {
+  spawn_local(async move {
+    let request = call.request();
+    let call_str = match json::to_string(&request) {
+      Ok(call_str) => call_str,
+      Err(err) => return dispatcher.dispatch(AppAction::Failed(err)),
+    };
+    info!("Spawned the call with a thunk: {:?}", call_str);
+    let result = match glue::call_server(call_str).await {
+      Ok(result) => AppAction::Thunk(format!("Received a call result: {:?}", result)),
+      Err(err) => AppAction::Failed(glue::decode_error(&err)),
+    };
+    info!("Completed call. Sending thunk: {:?}", result);
+    dispatcher.dispatch(result);
+  });
+}
+
 /// Enable tracing to dump to the console
 mod logger {
   use tracing_subscriber::{
@@ -151,7 +198,8 @@ fn app() -> Html {
     Callback::from(move |_e: MouseEvent| {
       let call = call.clone();
       info!("Clicked button for {:?}", call);
-      state.dispatch(AppAction::CallServer(call));
+      state.dispatch(AppAction::CallServer(call.clone()));
+      send(call, state.dispatcher());
     })
   };
 
@@ -160,6 +208,11 @@ fn app() -> Html {
     PageView::Organizations => html! {<OrgGrid></OrgGrid>},
   };
 
+  let error = match &state.error {
+    Some(err) => html! {<ErrorTree error={err.clone()} />},
+    None => html! {},
+  };
+
   info!("Rendering the App");
   html! {
     <div style="width: 100%;">
@@ -175,6 +228,7 @@ fn app() -> Html {
           <button onclick={clicked(Call::QueryAll)}>{"Query All"}</button>
         </div>
       </div>
+      {error}
       <div>
         <table style="border: 2px; border-color: white;">
           <tr>
@@ -0,0 +1,39 @@
+//! Render an error and everything that caused it
+
+use yew::prelude::*;
+
+use super::prelude::*;
+use errors::ErrorKind;
+
+#[derive(Properties, PartialEq)]
+pub struct ErrorTreeProps {
+  pub error: AllWhat<WranglerErrorKind>,
+}
+
+#[function_component(ErrorTree)]
+pub fn error_tree(props: &ErrorTreeProps) -> Html {
+  let err = &props.error;
+  let kind = err.get_kind();
+  let message = err.user_message();
+
+  // Only sent by debug builds of the server
+  let dev_context = match err.get_dev_context() {
+    Some(dev) => html! { <pre class={"text-sm text-gray-500"}>{dev}</pre> },
+    None => html! {},
+  };
+
+  let children: Vec<Html> = err
+    .get_inner()
+    .iter()
+    .map(|child| html! { <ErrorTree error={child.clone()} /> })
+    .collect();
+
+  html! {
+    <div class={"pl-4 border-l-2 border-red-500"}>
+      <div class={"font-bold"}>{message}</div>
+      <div class={"text-xs text-gray-500"}>{kind.reference()}{" "}{kind.name()}</div>
+      {dev_context}
+      {children}
+    </div>
+  }
+}
@@ -2,6 +2,9 @@
 
 pub mod organization;
 
+// Failed calls
+pub mod error;
+
 mod prelude {
   pub use wrangler_common::prelude::*;
 }
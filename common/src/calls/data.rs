@@ -2,12 +2,68 @@
 //!
 //! This currently does not follow edges for updates and must be handled by the user manually
 
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::model::{ModelNode, ModelValue};
+
 /// Your basic CRUD management of the database
-#[derive(Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
 pub enum Query {
-  Create,
-  Delete,
-  Retrieve,
-  Update,
-  Upsert,
+  /// Add new values, failing if any of them already exist
+  Create { value: ModelValue },
+
+  /// Remove the nodes matching the filter along with all of their edges
+  Delete { node: ModelNode, filter: Filter },
+
+  /// Read a page of the nodes matching the filter
+  Retrieve {
+    node: ModelNode,
+    filter: Filter,
+    page: Page,
+  },
+
+  /// Overwrite existing values, failing if any of them do not exist
+  Update { value: ModelValue },
+
+  /// Create or overwrite the values
+  Upsert { value: ModelValue },
+}
+
+/// Narrow down the nodes a query is applied to
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum Filter {
+  /// Every node of the requested type
+  #[default]
+  All,
+
+  /// Only the nodes with the given guids
+  Guids(Vec<Uuid>),
+
+  /// Nodes where the named property is equal to the value
+  Equals { field: String, value: String },
+
+  /// Nodes which match every one of the inner filters
+  And(Vec<Filter>),
+}
+
+/// The subset of the results to return
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Page {
+  /// The number of results to skip
+  pub offset: usize,
+
+  /// The maximum number of results to return. None returns everything after the offset.
+  pub limit: Option<usize>,
+}
+
+impl Default for Page {
+  fn default() -> Page {
+    Page {
+      offset: 0,
+      limit: Some(100),
+    }
+  }
 }
@@ -1,13 +1,106 @@
 //! A rust implementation of the Wrangler server API spec
+//!
+//! Every call is wrapped in a [Request] holding a unique id. The server answers each one with a
+//! [Reply] carrying the same id, so replies that arrive asynchronously (such as via the Tauri `rs2js`
+//! event) can be matched back up to the call that caused them.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+  local::*,
+  model::{ModelNode, ModelValue},
+};
 
 // Database operations
 pub mod data;
 
+/// The identifier used to pair a reply with its request
+pub type RequestId = Uuid;
+
 /// Top level routing data for the system
 ///
 /// In essence, each of these is the equivalent of an API endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value")]
 pub enum Call {
   Settings,
   Data(data::Query),
+
+  /// Load a file of the given node type into the database
+  Import { node: ModelNode, path: String },
+
+  Heartbeat,
+}
+
+/// A call to be sent to the server
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Request {
+  pub id: RequestId,
+  pub call: Call,
+}
+
+impl Request {
+  /// Wrap the call with a new unique id
+  pub fn new(call: Call) -> Request {
+    Request {
+      id: Uuid::new_v4(),
+      call,
+    }
+  }
+
+  /// Create the reply to this request, leaving out anything the client shouldn't see
+  pub fn reply(&self, response: Response) -> Reply {
+    let response = match response {
+      Response::Error(err) => Response::Error(err.for_client()),
+      response => response,
+    };
+    Reply {
+      id: self.id,
+      response,
+    }
+  }
+}
+
+/// The outcome of a call
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum Response {
+  /// The call succeeded but there is no value to return
+  Done,
+
+  /// The values returned by the call
+  Data(ModelValue),
+
+  /// The server is alive and processing calls
   Heartbeat,
+
+  /// The call could not be completed
+  Error(AllWhat<WranglerErrorKind>),
+}
+
+impl From<Result<ModelValue>> for Response {
+  fn from(result: Result<ModelValue>) -> Response {
+    match result {
+      Ok(value) => Response::Data(value),
+      Err(err) => Response::Error(err),
+    }
+  }
+}
+
+impl From<Result<()>> for Response {
+  fn from(result: Result<()>) -> Response {
+    match result {
+      Ok(()) => Response::Done,
+      Err(err) => Response::Error(err),
+    }
+  }
+}
+
+/// The server's answer to a [Request]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Reply {
+  /// The id of the request being answered
+  pub id: RequestId,
+  pub response: Response,
 }
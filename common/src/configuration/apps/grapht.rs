@@ -0,0 +1,65 @@
+//! Settings for the in-memory graph database
+
+use std::{
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex, PoisonError, RwLock},
+};
+
+use crate::{grapht::Graph, local::*};
+
+/// An in-memory graph which needs no external services to run
+///
+/// The graph is created by the first connection and shared with every later one, including those
+/// made from clones of the config. Otherwise each connection would see only its own writes.
+#[derive(Clone, Default)]
+pub struct GraphtConfig {
+  /// A file to load the graph from on startup and save it to afterwards. Without one the graph only
+  /// lives as long as the process.
+  snapshot: Option<PathBuf>,
+
+  /// The graph handed out to each connection, once one has been made
+  graph: Arc<Mutex<Option<Arc<RwLock<Graph>>>>>,
+}
+
+impl GraphtConfig {
+  pub fn new() -> GraphtConfig {
+    GraphtConfig::default()
+  }
+
+  /// Persist the graph to the given file
+  pub fn with_snapshot(self, path: impl Into<PathBuf>) -> GraphtConfig {
+    GraphtConfig {
+      snapshot: Some(path.into()),
+      graph: Default::default(),
+    }
+  }
+
+  pub fn get_snapshot(&self) -> Option<&Path> {
+    self.snapshot.as_deref()
+  }
+
+  /// The graph shared by every connection, loading it from the snapshot the first time
+  pub fn get_graph(&self) -> Result<Arc<RwLock<Graph>>> {
+    // The lock only guards the handle, which is never left half set
+    let mut shared = self.graph.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(graph) = shared.as_ref() {
+      return Ok(graph.clone());
+    }
+
+    let graph = match self.get_snapshot() {
+      Some(path) if path.exists() => Graph::load(path)?,
+      _ => Graph::new(),
+    };
+    let graph = Arc::new(RwLock::new(graph));
+    *shared = Some(graph.clone());
+    Ok(graph)
+  }
+}
+
+impl Debug for GraphtConfig {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("GraphtConfig")
+      .field("snapshot", &self.snapshot)
+      .finish_non_exhaustive()
+  }
+}
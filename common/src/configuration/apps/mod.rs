@@ -1,7 +1,10 @@
 //! Application initialization configurations
 
-// Re-export the primitives for use by the apps
-pub use super::primitives;
+// Re-export the primitives and their shared traits for use by the apps
+pub use super::{primitives, traits};
 
 // Graph database
 pub mod neo4j;
+
+// In-memory graph database
+pub mod grapht;
@@ -2,23 +2,31 @@
 
 use crate::local::*;
 
-use super::primitives::{password::*, uri::*};
+use super::{
+  primitives::{password::*, uri::*},
+  traits::Configuration,
+};
 
 #[derive(Debug, Clone)]
 pub struct Neo4jConfig {
   bolt_uri: UriConfig,
-  user: &'static str,
+  user: String,
   password: Password,
 }
 
 impl Neo4jConfig {
-  pub fn new(uri: &'static str, user: &'static str, password: &'static str) -> Result<Neo4jConfig> {
-    let uri_config = UriConfig::new(uri).constrain(vec![UriConstraint::IsUrl])?;
+  pub fn new(uri: &str, user: &str, password: Password) -> Result<Neo4jConfig> {
+    let uri_config = UriConfig::parse(uri)?.constrain(vec![
+      UriConstraint::IsUrl,
+      UriConstraint::schemes(&NEO4J_SCHEMES),
+      UriConstraint::HasHost,
+      UriConstraint::HasPort,
+    ])?;
 
     Ok(Neo4jConfig {
       bolt_uri: uri_config,
-      user,
-      password: Password::new(password),
+      user: user.to_string(),
+      password,
     })
   }
 
@@ -27,17 +35,17 @@ impl Neo4jConfig {
   }
 
   pub fn get_username(&self) -> String {
-    self.user.into()
+    self.user.clone()
   }
 
-  pub fn get_password(&self) -> Password {
-    self.password.clone()
+  pub fn get_password(&self) -> &Password {
+    &self.password
   }
 }
 
 // Set the default for localhos
 impl Default for Neo4jConfig {
   fn default() -> Neo4jConfig {
-    Neo4jConfig::new("127.0.0.1:7687", "neo4j", "neo4j").unwrap()
+    Neo4jConfig::new("bolt://127.0.0.1:7687", "neo4j", Password::new("neo4j")).unwrap()
   }
 }
@@ -7,4 +7,4 @@ pub mod primitives;
 pub mod apps;
 
 // Ways of grouping apps that can be substituted for one another
-// pub mod traits;
+pub mod traits;
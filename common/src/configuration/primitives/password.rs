@@ -1,29 +1,211 @@
 //! A simple string wrapper which does not automatically get printed to screen
+//!
+//! The value is wiped from memory when dropped, and printing or serializing it only ever shows
+//! [REDACTED] or where the password was read from.
 
 use core::fmt::{Debug, Display};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs;
+use zeroize::Zeroizing;
+
+use crate::local::*;
+
+use super::super::traits::{Configuration, Test};
+
+/// Shown in place of a password
+pub const REDACTED: &str = "*******";
+
+/// Where a password can be read from instead of being written into a configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordSource {
+  /// The name of an environment variable
+  Env(String),
+  /// A file holding only the password
+  File(String),
+  /// The name of an entry in the workspace's encrypted secret store
+  Store(String),
+}
+
+impl Display for PasswordSource {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PasswordSource::Env(var) => write!(f, "environment variable '{}'", var),
+      PasswordSource::File(path) => write!(f, "file '{}'", path),
+      PasswordSource::Store(name) => write!(f, "secret '{}'", name),
+    }
+  }
+}
+
+/// Failures describe the rule that was broken, never the password itself
+#[derive(Debug, Clone)]
+pub enum PasswordConstraint {
+  Not(Box<PasswordConstraint>),
+  /// At least this many characters long
+  MinLength(usize),
+}
+
+impl PasswordConstraint {
+  /// What the constraint requires of a password, phrased to follow "must"
+  pub fn describe(&self) -> String {
+    match self {
+      PasswordConstraint::Not(inner) => format!("not {}", inner.describe()),
+      PasswordConstraint::MinLength(length) => format!("be at least {} characters long", length),
+    }
+  }
+
+  fn holds(&self, value: &str) -> bool {
+    match self {
+      PasswordConstraint::Not(inner) => !inner.holds(value),
+      PasswordConstraint::MinLength(length) => value.chars().count() >= *length,
+    }
+  }
+}
+
+impl Test for PasswordConstraint {
+  type Value = str;
+
+  fn test(&self, value: &str) -> Result<()> {
+    match self.holds(value) {
+      true => Ok(()),
+      false => {
+        let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+        Err(err.set_context(&format!("The password must {}", self.describe())))
+      }
+    }
+  }
+}
 
 /// A value that should never be printed out to a log or screen
 #[derive(Clone)]
-pub struct Password(&'static str);
+pub struct Password {
+  value: Zeroizing<String>,
+  /// Where the value was read from, if it wasn't given directly
+  source: Option<PasswordSource>,
+  constraints: Vec<PasswordConstraint>,
+}
 
 impl Password {
-  pub fn new(password: &'static str) -> Password {
-    Password(password)
+  pub fn new(password: &str) -> Password {
+    Password {
+      value: Zeroizing::new(password.to_string()),
+      source: None,
+      constraints: Vec::new(),
+    }
+  }
+
+  /// Read the password from an environment variable
+  pub fn from_env(var: &str) -> Result<Password> {
+    match std::env::var(var) {
+      Ok(value) => {
+        Ok(Password::new(&Zeroizing::new(value)).set_source(PasswordSource::Env(var.to_string())))
+      }
+      Err(err) => {
+        let result: AllWhat<WranglerErrorKind> = ConfigError.into();
+        Err(
+          result
+            .set_context(&format!(
+              "The environment variable '{}' does not hold a password",
+              var
+            ))
+            .set_dev_context(&format!("From <std::env>:\n{:#?}", err)),
+        )
+      }
+    }
+  }
+
+  /// Read the password from a file, ignoring a trailing line break
+  pub fn from_file(path: &str) -> Result<Password> {
+    let text = Zeroizing::new(fs::read_to_string(path).map_err(|err| {
+      let result: AllWhat<WranglerErrorKind> = err.into();
+      result.set_context(&format!("Could not read the password file '{}'", path))
+    })?);
+
+    Ok(
+      Password::new(text.trim_end_matches(['\r', '\n']))
+        .set_source(PasswordSource::File(path.to_string())),
+    )
+  }
+
+  /// Record where the password was read from, so it can be written out in place of the value
+  pub fn set_source(self, source: PasswordSource) -> Password {
+    Password {
+      source: Some(source),
+      ..self
+    }
+  }
+
+  pub fn get_source(&self) -> Option<&PasswordSource> {
+    self.source.as_ref()
+  }
+
+  /// True if this was read back from a file that had the password redacted
+  pub fn is_redacted(&self) -> bool {
+    self.value.as_str() == REDACTED
+  }
+}
+
+impl Configuration for Password {
+  type Constraint = PasswordConstraint;
+
+  fn value(&self) -> &str {
+    self.value.as_str()
   }
 
-  pub fn value(&self) -> &'static str {
-    self.0
+  fn constraints(&self) -> &[PasswordConstraint] {
+    &self.constraints
+  }
+
+  fn set_constraints(self, constraints: Vec<PasswordConstraint>) -> Self {
+    Password {
+      constraints,
+      ..self
+    }
+  }
+
+  fn label(&self) -> String {
+    "Password".to_string()
   }
 }
 
 impl Display for Password {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "*******")
+    write!(f, "{}", REDACTED)
   }
 }
 
 impl Debug for Password {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "*******")
+    write!(f, "{}", REDACTED)
+  }
+}
+
+/// Written as its source when known, so a saved configuration can find it again
+impl Serialize for Password {
+  fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+    match &self.source {
+      Some(source) => source.serialize(serializer),
+      None => serializer.serialize_str(REDACTED),
+    }
+  }
+}
+
+/// The two forms a password is written in
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Written {
+  Source(PasswordSource),
+  Value(String),
+}
+
+/// Reads back either form written above. A source is only recorded, not read, so loading a
+/// configuration doesn't touch the environment or other files. The password stays redacted until
+/// it is read from that source.
+impl<'de> Deserialize<'de> for Password {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+    match Written::deserialize(deserializer)? {
+      Written::Source(source) => Ok(Password::new(REDACTED).set_source(source)),
+      Written::Value(value) => Ok(Password::new(&Zeroizing::new(value))),
+    }
   }
 }
@@ -2,63 +2,115 @@
 //!
 //! This functionality changes based on whether it is used in a browser sandbox
 
+use std::{fs, path::Path};
+
 use crate::local::*;
 
+use super::super::traits::{Configuration, Test};
+
 #[derive(Debug, Clone)]
 pub enum PathConstraint {
   Not(Box<PathConstraint>),
   IsFile,
   IsDirectory,
+  Exists,
+  /// The path can be written to, or created inside its nearest existing ancestor
+  Writable,
 }
 
 impl PathConstraint {
-  pub fn test(&self, _value: &'static str) -> Result<()> {
-    Ok(())
+  /// What the constraint requires of a path, phrased to follow "must"
+  pub fn describe(&self) -> String {
+    match self {
+      PathConstraint::Not(inner) => format!("not {}", inner.describe()),
+      PathConstraint::IsFile => "be a file".to_string(),
+      PathConstraint::IsDirectory => "be a directory".to_string(),
+      PathConstraint::Exists => "exist".to_string(),
+      PathConstraint::Writable => "be writable".to_string(),
+    }
+  }
+
+  fn holds(&self, path: &Path) -> bool {
+    match self {
+      PathConstraint::Not(inner) => !inner.holds(path),
+      PathConstraint::IsFile => path.is_file(),
+      PathConstraint::IsDirectory => path.is_dir(),
+      PathConstraint::Exists => path.exists(),
+      PathConstraint::Writable => is_writable(path),
+    }
+  }
+}
+
+impl Test for PathConstraint {
+  type Value = str;
+
+  fn test(&self, value: &str) -> Result<()> {
+    match self.holds(Path::new(value)) {
+      true => Ok(()),
+      false => {
+        let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+        Err(err.set_context(&format!("'{}' must {}", value, self.describe())))
+      }
+    }
+  }
+}
+
+/// Only the read-only flag is checked, so this can't see ACLs or the uid running the process
+fn is_writable(path: &Path) -> bool {
+  match fs::metadata(path) {
+    Ok(meta) => !meta.permissions().readonly(),
+    Err(_) => path
+      .ancestors()
+      .skip(1)
+      .map(|ancestor| match ancestor.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => ancestor,
+      })
+      .find(|ancestor| ancestor.exists())
+      .map(|ancestor| ancestor.is_dir() && is_writable(ancestor))
+      .unwrap_or(false),
   }
 }
 
 #[derive(Debug, Clone)]
 pub struct PathConfig {
-  path: &'static str,
+  path: String,
   constraints: Vec<PathConstraint>,
 }
 
 impl PathConfig {
-  pub fn new(path: &'static str) -> PathConfig {
+  pub fn new(path: &str) -> PathConfig {
     // let result = Path::try_from(Path);
     PathConfig {
-      path,
+      path: path.to_string(),
       constraints: Vec::new(),
     }
   }
 
-  pub fn is_valid(&self) -> bool {
-    self
-      .constraints
-      .iter()
-      .find(|constraint: &&PathConstraint| constraint.test(&self.path).is_err())
-      .is_none()
+  pub fn get_path(&self) -> &str {
+    &self.path
   }
+}
 
-  pub fn validate(self) -> Result<Self> {
-    // Test the value against the new constraints
-    let results: Vec<Result<()>> = self
-      .constraints
-      .iter()
-      .map(|constraint: &PathConstraint| constraint.test(&self.path))
-      .collect();
+impl Configuration for PathConfig {
+  type Constraint = PathConstraint;
 
-    let _ = AllWhat::flatten(ValidationError, results)
-      .set_context(&format!("Path '{}' failed validation", self.path))?;
+  fn value(&self) -> &str {
+    &self.path
+  }
 
-    Ok(self)
+  fn constraints(&self) -> &[PathConstraint] {
+    &self.constraints
   }
 
-  pub fn constrain(self, constraints: Vec<PathConstraint>) -> Result<Self> {
+  fn set_constraints(self, constraints: Vec<PathConstraint>) -> Self {
     PathConfig {
       constraints,
       ..self
     }
-    .validate()
+  }
+
+  fn label(&self) -> String {
+    format!("Path '{}'", self.path)
   }
 }
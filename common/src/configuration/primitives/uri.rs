@@ -4,15 +4,73 @@ use http::uri::Uri;
 
 use crate::local::*;
 
+use super::super::traits::{Configuration, Test};
+
+/// The schemes the bolt driver knows how to connect with
+pub const NEO4J_SCHEMES: [&str; 6] = [
+  "bolt",
+  "bolt+s",
+  "bolt+ssc",
+  "neo4j",
+  "neo4j+s",
+  "neo4j+ssc",
+];
+
+pub const HTTP_SCHEMES: [&str; 2] = ["http", "https"];
+
 #[derive(Debug, Clone)]
 pub enum UriConstraint {
   Not(Box<UriConstraint>),
+  /// Has both a scheme and a host, so "127.0.0.1:7687" is not a url
   IsUrl,
+  /// The scheme is one of the listed names
+  Scheme(Vec<String>),
+  HasHost,
+  HasPort,
 }
 
 impl UriConstraint {
-  pub fn test(&self, _value: &Uri) -> Result<()> {
-    Ok(())
+  /// Allow only the given schemes
+  pub fn schemes(names: &[&str]) -> UriConstraint {
+    UriConstraint::Scheme(names.iter().map(|name| name.to_string()).collect())
+  }
+
+  /// What the constraint requires of a uri, phrased to follow "must"
+  pub fn describe(&self) -> String {
+    match self {
+      UriConstraint::Not(inner) => format!("not {}", inner.describe()),
+      UriConstraint::IsUrl => "be a url with a scheme and host".to_string(),
+      UriConstraint::Scheme(names) => format!("use one of the schemes: {}", names.join(", ")),
+      UriConstraint::HasHost => "include a host".to_string(),
+      UriConstraint::HasPort => "include a port".to_string(),
+    }
+  }
+
+  fn holds(&self, value: &Uri) -> bool {
+    match self {
+      UriConstraint::Not(inner) => !inner.holds(value),
+      UriConstraint::IsUrl => value.scheme().is_some() && value.host().is_some(),
+      UriConstraint::Scheme(names) => match value.scheme_str() {
+        Some(scheme) => names.iter().any(|name| name.eq_ignore_ascii_case(scheme)),
+        None => false,
+      },
+      UriConstraint::HasHost => value.host().map(|host| !host.is_empty()).unwrap_or(false),
+      UriConstraint::HasPort => value.port_u16().is_some(),
+    }
+  }
+}
+
+impl Test for UriConstraint {
+  type Value = Uri;
+
+  fn test(&self, value: &Uri) -> Result<()> {
+    match self.holds(value) {
+      true => Ok(()),
+      false => {
+        let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+        Err(err.set_context(&format!("'{}' must {}", value, self.describe())))
+      }
+    }
   }
 }
 
@@ -23,6 +81,24 @@ pub struct UriConfig {
 }
 
 impl UriConfig {
+  /// Read a uri, failing if it cannot be parsed
+  pub fn parse(uri: &str) -> Result<UriConfig> {
+    match Uri::try_from(uri) {
+      Ok(value) => Ok(UriConfig {
+        uri: value,
+        constraints: Vec::new(),
+      }),
+      Err(err) => {
+        let result: AllWhat<WranglerErrorKind> = ValidationError.into();
+        Err(
+          result
+            .set_context(&format!("'{}' is not a valid uri", uri))
+            .set_dev_context(&format!("From <http::uri>:\n{:#?}", err)),
+        )
+      }
+    }
+  }
+
   pub fn new(uri: &str) -> UriConfig {
     let result = Uri::try_from(uri);
     match result {
@@ -33,35 +109,28 @@ impl UriConfig {
       Err(err) => panic!("Invalid uri given: {:?}", err),
     }
   }
+}
 
-  pub fn is_valid(&self) -> bool {
-    self
-      .constraints
-      .iter()
-      .find(|constraint: &&UriConstraint| constraint.test(&self.uri).is_err())
-      .is_none()
-  }
-
-  pub fn validate(self) -> Result<Self> {
-    // Test the value against the new constraints
-    let results: Vec<Result<()>> = self
-      .constraints
-      .iter()
-      .map(|constraint: &UriConstraint| constraint.test(&self.uri))
-      .collect();
+impl Configuration for UriConfig {
+  type Constraint = UriConstraint;
 
-    let _ = AllWhat::flatten(ValidationError, results)
-      .set_context(&format!("Uri '{}' failed validation", self.uri))?;
+  fn value(&self) -> &Uri {
+    &self.uri
+  }
 
-    Ok(self)
+  fn constraints(&self) -> &[UriConstraint] {
+    &self.constraints
   }
 
-  pub fn constrain(self, constraints: Vec<UriConstraint>) -> Result<Self> {
+  fn set_constraints(self, constraints: Vec<UriConstraint>) -> Self {
     UriConfig {
       constraints,
       ..self
     }
-    .validate()
+  }
+
+  fn label(&self) -> String {
+    format!("Uri '{}'", self.uri)
   }
 }
 
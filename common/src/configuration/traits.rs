@@ -2,32 +2,51 @@
 
 use crate::local::*;
 
-pub trait Configuration {
-  type Constraint;
+/// A single check a configuration value has to pass
+pub trait Test {
+  /// What the check is run against
+  type Value: ?Sized;
+
+  fn test(&self, value: &Self::Value) -> Result<()>;
+}
+
+/// A value wrapped with the constraints it must satisfy
+pub trait Configuration: Sized {
+  type Constraint: Test;
+
+  /// The value each of the constraints is tested against
+  fn value(&self) -> &<Self::Constraint as Test>::Value;
+
+  fn constraints(&self) -> &[Self::Constraint];
+
+  /// Replace the constraints without checking them
+  fn set_constraints(self, constraints: Vec<Self::Constraint>) -> Self;
+
+  /// Names the value in a failed validation, eg. "Path './local/logs'"
+  fn label(&self) -> String;
 
   fn is_valid(&self) -> bool {
     self
-      .constraints
+      .constraints()
       .iter()
-      .find(|constraint: &&Self::Constraint| constraint.test(&self.uri).is_err())
-      .is_none()
+      .all(|constraint: &Self::Constraint| constraint.test(self.value()).is_ok())
   }
 
-  fn validate(&self, constraints: &Vec<Self::Constraint>) -> Result<Self> {
+  fn validate(self) -> Result<Self> {
     // Test the value against the new constraints
-    let results: Vec<Result<()>> = constraints
+    let results: Vec<Result<()>> = self
+      .constraints()
       .iter()
-      .map(|constraint: &Self::Constraint| constraint.test(&self.uri))
+      .map(|constraint: &Self::Constraint| constraint.test(self.value()))
       .collect();
 
-    let _ = AllWhat::flatten(
-      ValidationError(format!("Uri '{}' failed validation", self.uri)),
-      results,
-    )?;
+    let _ = AllWhat::flatten(ValidationError, results)
+      .set_context(&format!("{} failed validation", self.label()))?;
+
+    Ok(self)
+  }
 
-    Ok(UriConfig {
-      constraints,
-      ..self
-    })
+  fn constrain(self, constraints: Vec<Self::Constraint>) -> Result<Self> {
+    self.set_constraints(constraints).validate()
   }
 }
@@ -1,27 +1,98 @@
 //! A set of tools for working with a pre-defined error
 
 use core::fmt::{Debug, Display};
+use serde::{Deserialize, Serialize};
+use std::{
+  backtrace::{Backtrace, BacktraceStatus},
+  sync::Arc,
+};
 
 pub trait ErrorKind: Debug + Clone {
   fn name(&self) -> &str;
 
   fn description(&self) -> &str;
+
+  /// A stable number identifying the kind. Never renumber or reuse one that has been released
+  fn code(&self) -> u32;
+
+  /// How the code is quoted to users and in support notes
+  fn reference(&self) -> String {
+    format!("E{}", self.code())
+  }
+
+  /// A message for an end user, where `{context}` is replaced by the context of the error
+  fn message(&self) -> &str;
 }
 
-#[derive(Clone)]
+/// Where in the source an error was created
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+  pub file: String,
+  pub line: u32,
+  pub column: u32,
+}
+
+impl Location {
+  /// The location of the code calling the current function
+  #[track_caller]
+  pub fn caller() -> Location {
+    let location = std::panic::Location::caller();
+    Location {
+      file: location.file().to_string(),
+      line: location.line(),
+      column: location.column(),
+    }
+  }
+}
+
+impl Display for Location {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}:{}:{}", self.file, self.line, self.column)
+  }
+}
+
+/// Serialized as a tree so a client can render the error and everything that caused it
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AllWhat<Kind: ErrorKind> {
   /// A domain specific error
   kind: Kind,
 
   /// Optional grouping to capture handled errors that caused this error
+  #[serde(skip_serializing_if = "Option::is_none")]
   inner: Option<Vec<AllWhat<Kind>>>,
   /// A human readable message added at the spot where the message was generated
+  #[serde(skip_serializing_if = "Option::is_none")]
   context: Option<String>,
   /// A separate message that contains information only useful to a developer
+  #[serde(skip_serializing_if = "Option::is_none")]
   dev_context: Option<String>,
+
+  /// Where the error was created, found using `#[track_caller]`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  location: Option<Box<Location>>,
+  /// Only captured when enabled with RUST_BACKTRACE or RUST_LIB_BACKTRACE, and never sent anywhere.
+  /// The frames are only resolved to symbols when it is printed.
+  #[serde(skip)]
+  backtrace: Option<Arc<Backtrace>>,
 }
 
 impl<KIND: ErrorKind> AllWhat<KIND> {
+  #[track_caller]
+  fn new(kind: KIND, inner: Option<Vec<AllWhat<KIND>>>) -> Self {
+    let backtrace = Backtrace::capture();
+    AllWhat {
+      kind,
+      inner,
+      context: None,
+      dev_context: None,
+      location: Some(Box::new(Location::caller())),
+      backtrace: match backtrace.status() {
+        BacktraceStatus::Captured => Some(Arc::new(backtrace)),
+        _ => None,
+      },
+    }
+  }
+
   pub fn set_context(self, ctx: &str) -> Self {
     AllWhat {
       context: Some(ctx.to_string()),
@@ -36,6 +107,64 @@ impl<KIND: ErrorKind> AllWhat<KIND> {
     }
   }
 
+  /// Remove the developer context, location and backtrace from this error and everything that
+  /// caused it
+  pub fn strip_dev_context(self) -> Self {
+    AllWhat {
+      dev_context: None,
+      location: None,
+      backtrace: None,
+      inner: self
+        .inner
+        .map(|inner| inner.into_iter().map(AllWhat::strip_dev_context).collect()),
+      ..self
+    }
+  }
+
+  /// The error as it should be sent to a client, which only sees the developer context in debug builds
+  pub fn for_client(self) -> Self {
+    match cfg!(debug_assertions) {
+      true => self,
+      false => self.strip_dev_context(),
+    }
+  }
+
+  pub fn get_kind(&self) -> &KIND {
+    &self.kind
+  }
+
+  pub fn get_context(&self) -> Option<&str> {
+    self.context.as_deref()
+  }
+
+  pub fn get_dev_context(&self) -> Option<&str> {
+    self.dev_context.as_deref()
+  }
+
+  pub fn get_location(&self) -> Option<&Location> {
+    self.location.as_deref()
+  }
+
+  pub fn get_backtrace(&self) -> Option<&Backtrace> {
+    self.backtrace.as_deref()
+  }
+
+  /// The handled errors that caused this one, if any
+  pub fn get_inner(&self) -> &[AllWhat<KIND>] {
+    self.inner.as_deref().unwrap_or(&[])
+  }
+
+  /// The kind's message with the context filled in, safe to show an end user
+  pub fn user_message(&self) -> String {
+    let ctx = self.context.as_deref().unwrap_or("");
+    self
+      .kind
+      .message()
+      .replace("{context}", ctx)
+      .trim()
+      .to_string()
+  }
+
   pub fn render_context(&self) -> String {
     match (&self.context, &self.dev_context) {
       (Some(ctx), Some(dev)) => format!("{}. {}", ctx, dev),
@@ -47,6 +176,7 @@ impl<KIND: ErrorKind> AllWhat<KIND> {
   }
 
   /// Take a list of results and aggregate
+  #[track_caller]
   pub fn flatten<VALUE: Clone + Debug>(
     aggregate_kind: KIND,
     children: Vec<Result<VALUE, AllWhat<KIND>>>,
@@ -64,32 +194,42 @@ impl<KIND: ErrorKind> AllWhat<KIND> {
           .map(|value| value.clone().unwrap())
           .collect(),
       ),
-      false => Err(AllWhat {
-        kind: aggregate_kind,
-        inner: Some(
+      false => Err(AllWhat::new(
+        aggregate_kind,
+        Some(
           failed
             .iter()
             .map(|error| error.clone().unwrap_err())
             .collect(),
         ),
-        context: None,
-        dev_context: None,
-      }),
+      )),
     }
   }
 }
 
 impl<Kind: ErrorKind> Debug for AllWhat<Kind> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let location = match &self.location {
+      Some(location) => location.to_string(),
+      None => "unknown".to_string(),
+    };
+
     // If it has children, make it a struct, otherwise, use a one-liner
     match self.inner.clone() {
       Some(inner) => f
         .debug_struct(self.kind.name())
         .field("Context", &self.render_context())
+        .field("Location", &location)
         .field("Children", &inner)
         .finish(),
 
-      None => write!(f, "{}: {}", self.kind.name(), &self.render_context()),
+      None => write!(
+        f,
+        "{}: {} (at {})",
+        self.kind.name(),
+        &self.render_context(),
+        location
+      ),
     }
   }
 }
@@ -115,14 +255,31 @@ impl<Kind: ErrorKind> Display for AllWhat<Kind> {
   }
 }
 
+/// Errors are equal if they describe the same problem, regardless of where they were raised or how
+/// the stack looked
+impl<Kind: ErrorKind + PartialEq> PartialEq for AllWhat<Kind> {
+  fn eq(&self, other: &Self) -> bool {
+    self.kind == other.kind
+      && self.inner == other.inner
+      && self.context == other.context
+      && self.dev_context == other.dev_context
+  }
+}
+
+/// The source is the first of the errors that caused this one
+impl<Kind: ErrorKind + 'static> std::error::Error for AllWhat<Kind> {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    self
+      .get_inner()
+      .first()
+      .map(|inner| inner as &(dyn std::error::Error + 'static))
+  }
+}
+
 impl<K: ErrorKind> From<K> for AllWhat<K> {
+  #[track_caller]
   fn from(kind: K) -> Self {
-    AllWhat {
-      kind,
-      inner: None,
-      context: None,
-      dev_context: None,
-    }
+    AllWhat::new(kind, None)
   }
 }
 
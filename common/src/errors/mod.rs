@@ -1,9 +1,22 @@
 //! An enumeration of all the possible errors that the server can generate
+//!
+//! Every kind has a stable code that support notes can refer to, such as `WR-1002` for `NotFound`.
+//! Codes are grouped by where the problem lies:
+//!
+//! | Range | Area                                            |
+//! |-------|-------------------------------------------------|
+//! | 1xxx  | The request or the data it refers to            |
+//! | 2xxx  | The application, its configuration or services  |
+//! | 3xxx  | The submission data being loaded or billed      |
+//!
+//! A code is never renumbered or reused once released, even if its kind is removed.
+
+use serde::{Deserialize, Serialize};
 
 pub mod allwhat;
 pub use allwhat::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WranglerErrorKind {
   /// Errors that were raised by internal code and are not explicitly handled
   UnrecognizedError,
@@ -11,6 +24,18 @@ pub enum WranglerErrorKind {
   /// A generic error when checking correctness of incoming data
   ValidationError,
 
+  /// The requested item does not exist
+  NotFound,
+
+  /// The change would clash with something that already exists
+  Conflict,
+
+  /// The user is not allowed to do what they asked
+  PermissionDenied,
+
+  /// The application settings are missing or invalid
+  ConfigError,
+
   /// Problems concerning database connectivity.
   GraphDbError,
 
@@ -19,6 +44,38 @@ pub enum WranglerErrorKind {
 
   /// Thread error raised by Tokio
   TokioError,
+
+  /// A value could not be converted to or from its wire format
+  SerializationError,
+
+  /// A single row of an import file could not be loaded
+  ImportRowError,
+
+  /// Invoices, payments or line items that don't add up
+  BillingError,
+}
+
+impl WranglerErrorKind {
+  /// Every kind, in code order
+  pub const ALL: &'static [WranglerErrorKind] = &[
+    Self::UnrecognizedError,
+    Self::ValidationError,
+    Self::NotFound,
+    Self::Conflict,
+    Self::PermissionDenied,
+    Self::ConfigError,
+    Self::IOError,
+    Self::SerializationError,
+    Self::GraphDbError,
+    Self::TokioError,
+    Self::ImportRowError,
+    Self::BillingError,
+  ];
+
+  /// Look up a kind from the number in a support reference
+  pub fn from_code(code: u32) -> Option<WranglerErrorKind> {
+    Self::ALL.iter().find(|kind| kind.code() == code).cloned()
+  }
 }
 
 impl core::fmt::Display for WranglerErrorKind {
@@ -32,9 +89,16 @@ impl allwhat::ErrorKind for WranglerErrorKind {
     match self {
       Self::UnrecognizedError => "UnrecognizedError",
       Self::ValidationError => "ValidationError",
+      Self::NotFound => "NotFound",
+      Self::Conflict => "Conflict",
+      Self::PermissionDenied => "PermissionDenied",
+      Self::ConfigError => "ConfigError",
       Self::GraphDbError => "GraphDbError",
       Self::IOError => "IOError",
       Self::TokioError => "TokioError",
+      Self::SerializationError => "SerializationError",
+      Self::ImportRowError => "ImportRowError",
+      Self::BillingError => "BillingError",
     }
   }
 
@@ -42,25 +106,116 @@ impl allwhat::ErrorKind for WranglerErrorKind {
     match self {
       Self::UnrecognizedError => "An error that was not captured and converted automatically",
       Self::ValidationError => "ValidationError",
+      Self::NotFound => "A lookup did not match anything",
+      Self::Conflict => "A write clashed with existing data",
+      Self::PermissionDenied => "The caller lacks the rights for the operation",
+      Self::ConfigError => "The configuration could not be loaded or failed validation",
       Self::GraphDbError => "Graph Database Connectivity Error",
       Self::IOError => "An IO Error",
       Self::TokioError => "An issue caused managing threads via Tokio",
+      Self::SerializationError => "Failed to serialize or deserialize a value",
+      Self::ImportRowError => "A row of an import file was rejected",
+      Self::BillingError => "The billing records are inconsistent",
+    }
+  }
+
+  fn code(&self) -> u32 {
+    match self {
+      Self::UnrecognizedError => 1000,
+      Self::ValidationError => 1001,
+      Self::NotFound => 1002,
+      Self::Conflict => 1003,
+      Self::PermissionDenied => 1004,
+      Self::ConfigError => 2000,
+      Self::IOError => 2001,
+      Self::SerializationError => 2002,
+      Self::GraphDbError => 2003,
+      Self::TokioError => 2004,
+      Self::ImportRowError => 3000,
+      Self::BillingError => 3001,
+    }
+  }
+
+  fn reference(&self) -> String {
+    format!("WR-{}", self.code())
+  }
+
+  fn message(&self) -> &str {
+    match self {
+      Self::UnrecognizedError => "Something unexpected went wrong. {context}",
+      Self::ValidationError => "Some of the information given is not valid. {context}",
+      Self::NotFound => "We could not find what was asked for. {context}",
+      Self::Conflict => "This clashes with something that already exists. {context}",
+      Self::PermissionDenied => "You do not have permission to do that. {context}",
+      Self::ConfigError => "The application is not set up correctly. {context}",
+      Self::GraphDbError => "There was a problem talking to the database. {context}",
+      Self::IOError => "A file could not be read or written. {context}",
+      Self::TokioError => "A background task stopped unexpectedly. {context}",
+      Self::SerializationError => "Some data was not in the expected format. {context}",
+      Self::ImportRowError => "A row in the import file could not be loaded. {context}",
+      Self::BillingError => "The billing records do not add up. {context}",
     }
   }
 }
 
 // --------------------   Conversions   -----------------------
 impl From<std::io::Error> for AllWhat<WranglerErrorKind> {
+  #[track_caller]
   fn from(err: std::io::Error) -> Self {
     let result: AllWhat<WranglerErrorKind> = WranglerErrorKind::IOError.into();
     result.set_dev_context(&format!("From <std::io::Error>:\n{:#?}", err))
   }
 }
 
-// Cannot import this as it doesn't seem to work for the client side
-// impl From<neo4rs::Error> for AllWhat<WranglerErrorKind> {
-//   fn from(err: neo4rs::Error) -> Self {
-//     let result: AllWhat<WranglerErrorKind> = WranglerErrorKind::GraphDbError.into();
-//     result.set_dev_context(&format!("From <neo4rs>:\n{:#?}", err))
-//   }
-// }
+impl From<serde_json::Error> for AllWhat<WranglerErrorKind> {
+  #[track_caller]
+  fn from(err: serde_json::Error) -> Self {
+    let result: AllWhat<WranglerErrorKind> = WranglerErrorKind::SerializationError.into();
+    result.set_dev_context(&format!("From <serde_json::Error>:\n{:#?}", err))
+  }
+}
+
+// The optional conversions can't be built for the browser client, so they are behind features
+#[cfg(feature = "neo4rs")]
+impl From<neo4rs::Error> for AllWhat<WranglerErrorKind> {
+  #[track_caller]
+  fn from(err: neo4rs::Error) -> Self {
+    let result: AllWhat<WranglerErrorKind> = WranglerErrorKind::GraphDbError.into();
+    result.set_dev_context(&format!("From <neo4rs>:\n{:#?}", err))
+  }
+}
+
+#[cfg(feature = "neo4rs")]
+impl From<neo4rs::DeError> for AllWhat<WranglerErrorKind> {
+  #[track_caller]
+  fn from(err: neo4rs::DeError) -> Self {
+    let result: AllWhat<WranglerErrorKind> = WranglerErrorKind::GraphDbError.into();
+    result.set_dev_context(&format!("From <neo4rs::DeError>:\n{:#?}", err))
+  }
+}
+
+#[cfg(feature = "csv")]
+impl From<csv::Error> for AllWhat<WranglerErrorKind> {
+  #[track_caller]
+  fn from(err: csv::Error) -> Self {
+    let kind = match err.is_io_error() {
+      true => WranglerErrorKind::IOError,
+      false => WranglerErrorKind::SerializationError,
+    };
+    let result: AllWhat<WranglerErrorKind> = kind.into();
+    result.set_dev_context(&format!("From <csv>:\n{:#?}", err))
+  }
+}
+
+#[cfg(feature = "tokio")]
+impl From<tokio::task::JoinError> for AllWhat<WranglerErrorKind> {
+  #[track_caller]
+  fn from(err: tokio::task::JoinError) -> Self {
+    let result: AllWhat<WranglerErrorKind> = WranglerErrorKind::TokioError.into();
+    let result = match err.is_cancelled() {
+      true => result.set_context("A background task was cancelled"),
+      false => result.set_context("A background task panicked"),
+    };
+    result.set_dev_context(&format!("From <tokio::task::JoinError>:\n{:#?}", err))
+  }
+}
@@ -0,0 +1,223 @@
+//! The parsed form of a query
+
+use super::super::{Direction, Value};
+
+#[derive(Clone, Debug)]
+pub struct Query {
+  pub clauses: Vec<Clause>,
+}
+
+impl Query {
+  /// Whether running the query could change the graph
+  pub fn is_read_only(&self) -> bool {
+    self.clauses.iter().all(|clause| {
+      matches!(
+        clause,
+        Clause::Match { .. } | Clause::Unwind { .. } | Clause::With(_) | Clause::Return(_)
+      )
+    })
+  }
+}
+
+#[derive(Clone, Debug)]
+pub enum Clause {
+  Match {
+    optional: bool,
+    patterns: Vec<Pattern>,
+    filter: Option<Expr>,
+  },
+  Unwind {
+    list: Expr,
+    alias: String,
+  },
+  Create(Vec<Pattern>),
+  Merge {
+    pattern: Pattern,
+    on_create: Vec<SetItem>,
+    on_match: Vec<SetItem>,
+  },
+  Set(Vec<SetItem>),
+  Delete {
+    detach: bool,
+    targets: Vec<Expr>,
+  },
+  With(Projection),
+  Return(Projection),
+}
+
+/// The body of a WITH or RETURN clause
+#[derive(Clone, Debug, Default)]
+pub struct Projection {
+  pub distinct: bool,
+
+  /// Project every variable in scope, as with `RETURN *`
+  pub star: bool,
+
+  pub items: Vec<ProjectionItem>,
+  pub order: Vec<SortItem>,
+  pub skip: Option<Expr>,
+  pub limit: Option<Expr>,
+
+  /// The WHERE of a WITH clause, applied to the projected rows
+  pub filter: Option<Expr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProjectionItem {
+  pub expr: Expr,
+
+  /// The alias, or the text of the expression when there isn't one
+  pub name: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct SortItem {
+  pub expr: Expr,
+
+  /// The text of the expression, so it can refer to an unaliased column
+  pub name: String,
+  pub descending: bool,
+}
+
+/// A chain of nodes joined by relationships: `(a)-[:R]->(b)<-[:S]-(c)`
+#[derive(Clone, Debug)]
+pub struct Pattern {
+  pub start: NodePattern,
+  pub steps: Vec<(RelPattern, NodePattern)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NodePattern {
+  pub var: Option<String>,
+  pub labels: Vec<String>,
+  pub properties: Vec<(String, Expr)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RelPattern {
+  pub var: Option<String>,
+
+  /// Any one of these labels matches. Empty matches every relationship.
+  pub types: Vec<String>,
+  pub direction: Direction,
+  pub properties: Vec<(String, Expr)>,
+
+  /// The minimum and maximum number of hops for a variable length relationship: `*1..3`
+  pub length: Option<(usize, Option<usize>)>,
+}
+
+#[derive(Clone, Debug)]
+pub enum SetItem {
+  /// `n.key = value`
+  Property {
+    var: String,
+    key: String,
+    value: Expr,
+  },
+
+  /// `n = {map}`, replacing every property
+  Replace { var: String, value: Expr },
+
+  /// `n += {map}`, keeping properties that aren't mentioned
+  Merge { var: String, value: Expr },
+
+  /// `n:Label`
+  Labels { var: String, labels: Vec<String> },
+}
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+  Literal(Value),
+  Param(String),
+  Variable(String),
+  Property(Box<Expr>, String),
+  Index(Box<Expr>, Box<Expr>),
+  List(Vec<Expr>),
+  Map(Vec<(String, Expr)>),
+  HasLabels(Box<Expr>, Vec<String>),
+  Not(Box<Expr>),
+  Negate(Box<Expr>),
+  Binary(BinaryOp, Box<Expr>, Box<Expr>),
+  IsNull {
+    expr: Box<Expr>,
+    negated: bool,
+  },
+  Function {
+    /// Lower cased, as function names are case insensitive
+    name: String,
+    distinct: bool,
+    args: Vec<Expr>,
+  },
+  CountStar,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+  Or,
+  Xor,
+  And,
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  In,
+  StartsWith,
+  EndsWith,
+  Contains,
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Mod,
+}
+
+/// Functions that combine many rows into a single value
+pub const AGGREGATES: [&str; 6] = ["count", "collect", "sum", "avg", "min", "max"];
+
+impl Expr {
+  /// Whether the expression is an aggregate, which groups the rows of a projection
+  pub fn is_aggregate(&self) -> bool {
+    match self {
+      Expr::CountStar => true,
+      Expr::Function { name, .. } => AGGREGATES.contains(&name.as_str()),
+      _ => false,
+    }
+  }
+
+  /// Whether the expression or anything inside it is an aggregate, like `count(n) + 1`
+  pub fn contains_aggregate(&self) -> bool {
+    self.is_aggregate() || self.children().into_iter().any(Expr::contains_aggregate)
+  }
+
+  /// The expressions directly inside this one
+  pub fn children(&self) -> Vec<&Expr> {
+    match self {
+      Expr::Literal(_) | Expr::Param(_) | Expr::Variable(_) | Expr::CountStar => vec![],
+      Expr::Property(inner, _)
+      | Expr::HasLabels(inner, _)
+      | Expr::Not(inner)
+      | Expr::Negate(inner)
+      | Expr::IsNull { expr: inner, .. } => vec![inner],
+      Expr::Index(left, right) | Expr::Binary(_, left, right) => vec![left, right],
+      Expr::List(items) | Expr::Function { args: items, .. } => items.iter().collect(),
+      Expr::Map(entries) => entries.iter().map(|(_, value)| value).collect(),
+    }
+  }
+
+  /// The expressions directly inside this one, to be rewritten
+  pub fn children_mut(&mut self) -> Vec<&mut Expr> {
+    match self {
+      Expr::Literal(_) | Expr::Param(_) | Expr::Variable(_) | Expr::CountStar => vec![],
+      Expr::Property(inner, _)
+      | Expr::HasLabels(inner, _)
+      | Expr::Not(inner)
+      | Expr::Negate(inner)
+      | Expr::IsNull { expr: inner, .. } => vec![inner],
+      Expr::Index(left, right) | Expr::Binary(_, left, right) => vec![left, right],
+      Expr::List(items) | Expr::Function { args: items, .. } => items.iter_mut().collect(),
+      Expr::Map(entries) => entries.iter_mut().map(|(_, value)| value).collect(),
+    }
+  }
+}
@@ -0,0 +1,733 @@
+//! Evaluate expressions against a row of bound variables
+
+use crate::local::*;
+
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use super::{
+  super::{
+    store::{EdgeId, NodeId},
+    Graph, Properties, Value,
+  },
+  ast::*,
+};
+
+/// A value while a query is running. Unlike [Value], nodes and edges are kept by id so they can be
+/// matched and updated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Val {
+  Null,
+  Bool(bool),
+  Int(i64),
+  Float(f64),
+  Str(String),
+  List(Vec<Val>),
+  Map(BTreeMap<String, Val>),
+  Node(NodeId),
+  Edge(EdgeId),
+}
+
+/// The variables bound while matching a single result
+pub type Record = BTreeMap<String, Val>;
+
+#[track_caller]
+pub fn query_error(ctx: &str) -> AllWhat<WranglerErrorKind> {
+  let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+  err.set_context(ctx)
+}
+
+impl From<&Value> for Val {
+  fn from(value: &Value) -> Val {
+    match value {
+      Value::Null => Val::Null,
+      Value::Bool(inner) => Val::Bool(*inner),
+      Value::Integer(inner) => Val::Int(*inner),
+      Value::Float(inner) => Val::Float(*inner),
+      Value::String(inner) => Val::Str(inner.clone()),
+      Value::List(items) => Val::List(items.iter().map(Val::from).collect()),
+      // Snapshots passed in as parameters no longer point at anything in the graph
+      Value::Map(props)
+      | Value::Node(super::super::Node {
+        properties: props, ..
+      })
+      | Value::Edge(super::super::Edge {
+        properties: props, ..
+      }) => Val::Map(
+        props
+          .iter()
+          .map(|(key, value)| (key.clone(), value.into()))
+          .collect(),
+      ),
+    }
+  }
+}
+
+impl Val {
+  /// Convert into a value that can be returned from the query
+  pub fn to_value(&self, graph: &Graph) -> Value {
+    match self {
+      Val::Null => Value::Null,
+      Val::Bool(inner) => Value::Bool(*inner),
+      Val::Int(inner) => Value::Integer(*inner),
+      Val::Float(inner) => Value::Float(*inner),
+      Val::Str(inner) => Value::String(inner.clone()),
+      Val::List(items) => Value::List(items.iter().map(|item| item.to_value(graph)).collect()),
+      Val::Map(map) => Value::Map(
+        map
+          .iter()
+          .map(|(key, value)| (key.clone(), value.to_value(graph)))
+          .collect(),
+      ),
+      Val::Node(id) => graph
+        .node(*id)
+        .map(|node| Value::Node(node.snapshot()))
+        .unwrap_or(Value::Null),
+      Val::Edge(id) => graph
+        .edge(*id)
+        .map(|edge| Value::Edge(edge.snapshot()))
+        .unwrap_or(Value::Null),
+    }
+  }
+
+  /// Convert into a value that can be stored as a property
+  pub fn to_property(&self, graph: &Graph) -> Result<Value> {
+    match self {
+      Val::Node(_) | Val::Edge(_) => Err(query_error(
+        "Nodes and relationships cannot be stored as properties",
+      )),
+      Val::List(items) => Ok(Value::List(
+        items
+          .iter()
+          .map(|item| item.to_property(graph))
+          .collect::<Result<_>>()?,
+      )),
+      other => Ok(other.to_value(graph)),
+    }
+  }
+
+  /// Only true passes a WHERE clause. Null and false both filter the row out.
+  pub fn is_true(&self) -> bool {
+    matches!(self, Val::Bool(true))
+  }
+
+  fn type_name(&self) -> &'static str {
+    match self {
+      Val::Null => "null",
+      Val::Bool(_) => "a boolean",
+      Val::Int(_) => "an integer",
+      Val::Float(_) => "a float",
+      Val::Str(_) => "a string",
+      Val::List(_) => "a list",
+      Val::Map(_) => "a map",
+      Val::Node(_) => "a node",
+      Val::Edge(_) => "a relationship",
+    }
+  }
+
+  fn as_f64(&self) -> Option<f64> {
+    match self {
+      Val::Int(inner) => Some(*inner as f64),
+      Val::Float(inner) => Some(*inner),
+      _ => None,
+    }
+  }
+
+  /// Cypher equality, which is null if either side is null
+  pub fn equals(&self, other: &Val) -> Option<bool> {
+    match (self, other) {
+      (Val::Null, _) | (_, Val::Null) => None,
+      (Val::Int(a), Val::Int(b)) => Some(a == b),
+      (Val::Int(_) | Val::Float(_), Val::Int(_) | Val::Float(_)) => {
+        Some(self.as_f64() == other.as_f64())
+      }
+      (Val::List(a), Val::List(b)) => {
+        if a.len() != b.len() {
+          return Some(false);
+        }
+        let mut result = Some(true);
+        for (a, b) in a.iter().zip(b) {
+          match a.equals(b) {
+            Some(false) => return Some(false),
+            None => result = None,
+            Some(true) => (),
+          }
+        }
+        result
+      }
+      (a, b) => Some(a == b),
+    }
+  }
+
+  /// Compare two values of the same kind, which is null when they cannot be compared
+  pub fn compare(&self, other: &Val) -> Option<Ordering> {
+    match (self, other) {
+      (Val::Int(a), Val::Int(b)) => Some(a.cmp(b)),
+      (Val::Int(_) | Val::Float(_), Val::Int(_) | Val::Float(_)) => {
+        self.as_f64()?.partial_cmp(&other.as_f64()?)
+      }
+      (Val::Str(a), Val::Str(b)) => Some(a.cmp(b)),
+      (Val::Bool(a), Val::Bool(b)) => Some(a.cmp(b)),
+      _ => None,
+    }
+  }
+
+  /// A total order for ORDER BY, which sorts mixed types and puts nulls last
+  pub fn sort_order(&self, other: &Val) -> Ordering {
+    let rank = |val: &Val| match val {
+      Val::Map(_) => 0,
+      Val::Node(_) => 1,
+      Val::Edge(_) => 2,
+      Val::List(_) => 3,
+      Val::Str(_) => 4,
+      Val::Bool(_) => 5,
+      Val::Int(_) | Val::Float(_) => 6,
+      Val::Null => 7,
+    };
+
+    match (self, other) {
+      (Val::Node(a), Val::Node(b)) | (Val::Edge(a), Val::Edge(b)) => a.cmp(b),
+      (Val::List(a), Val::List(b)) => a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| a.sort_order(b))
+        .find(|order| order.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len())),
+      (Val::Map(a), Val::Map(b)) => a.len().cmp(&b.len()),
+      (a, b) => match rank(a).cmp(&rank(b)) {
+        Ordering::Equal => a.compare(b).unwrap_or(Ordering::Equal),
+        order => order,
+      },
+    }
+  }
+}
+
+/// Everything needed to evaluate an expression besides the row itself
+pub struct Eval<'a> {
+  pub graph: &'a Graph,
+  pub params: &'a Properties,
+}
+
+impl<'a> Eval<'a> {
+  pub fn eval(&self, row: &Record, expr: &Expr) -> Result<Val> {
+    match expr {
+      Expr::Literal(value) => Ok(value.into()),
+      Expr::Param(name) => self
+        .params
+        .get(name)
+        .map(Val::from)
+        .ok_or_else(|| query_error(&format!("Missing parameter ${}", name))),
+      Expr::Variable(name) => row
+        .get(name)
+        .cloned()
+        .ok_or_else(|| query_error(&format!("Variable `{}` is not defined", name))),
+      Expr::Property(target, key) => {
+        let target = self.eval(row, target)?;
+        self.property(&target, key)
+      }
+      Expr::Index(target, index) => match (self.eval(row, target)?, self.eval(row, index)?) {
+        (Val::Null, _) | (_, Val::Null) => Ok(Val::Null),
+        (Val::List(items), Val::Int(index)) => {
+          let index = match index < 0 {
+            true => items.len() as i64 + index,
+            false => index,
+          };
+          Ok(
+            usize::try_from(index)
+              .ok()
+              .and_then(|index| items.get(index).cloned())
+              .unwrap_or(Val::Null),
+          )
+        }
+        (target, Val::Str(key)) => self.property(&target, &key),
+        (target, index) => Err(query_error(&format!(
+          "Cannot index {} with {}",
+          target.type_name(),
+          index.type_name()
+        ))),
+      },
+      Expr::List(items) => Ok(Val::List(
+        items
+          .iter()
+          .map(|item| self.eval(row, item))
+          .collect::<Result<_>>()?,
+      )),
+      Expr::Map(entries) => Ok(Val::Map(self.eval_map(row, entries)?)),
+      Expr::HasLabels(target, labels) => match self.eval(row, target)? {
+        Val::Null => Ok(Val::Null),
+        Val::Node(id) => Ok(Val::Bool(
+          self
+            .graph
+            .node(id)
+            .map(|node| labels.iter().all(|label| node.labels.contains(label)))
+            .unwrap_or(false),
+        )),
+        other => Err(query_error(&format!(
+          "Cannot check the labels of {}",
+          other.type_name()
+        ))),
+      },
+      Expr::Not(inner) => match self.eval(row, inner)? {
+        Val::Null => Ok(Val::Null),
+        Val::Bool(value) => Ok(Val::Bool(!value)),
+        other => Err(query_error(&format!("Cannot negate {}", other.type_name()))),
+      },
+      Expr::Negate(inner) => match self.eval(row, inner)? {
+        Val::Null => Ok(Val::Null),
+        Val::Int(value) => value
+          .checked_neg()
+          .map(Val::Int)
+          .ok_or_else(|| query_error("Integer overflow")),
+        Val::Float(value) => Ok(Val::Float(-value)),
+        other => Err(query_error(&format!(
+          "Cannot make {} negative",
+          other.type_name()
+        ))),
+      },
+      Expr::Binary(op, left, right) => self.binary(row, *op, left, right),
+      Expr::IsNull { expr, negated } => {
+        let is_null = self.eval(row, expr)? == Val::Null;
+        Ok(Val::Bool(is_null != *negated))
+      }
+      Expr::Function {
+        name,
+        distinct: _,
+        args,
+      } => {
+        if expr.is_aggregate() {
+          return Err(query_error(&format!(
+            "The aggregate {}() can only be used as a column of WITH or RETURN",
+            name
+          )));
+        }
+        let args = args
+          .iter()
+          .map(|arg| self.eval(row, arg))
+          .collect::<Result<Vec<_>>>()?;
+        self.function(name, args)
+      }
+      Expr::CountStar => Err(query_error(
+        "count(*) can only be used as a column of WITH or RETURN",
+      )),
+    }
+  }
+
+  pub fn eval_map(
+    &self,
+    row: &Record,
+    entries: &[(String, Expr)],
+  ) -> Result<BTreeMap<String, Val>> {
+    entries
+      .iter()
+      .map(|(key, value)| Ok((key.clone(), self.eval(row, value)?)))
+      .collect()
+  }
+
+  /// The properties of a node, relationship, or map
+  pub fn properties(&self, value: &Val) -> Result<BTreeMap<String, Val>> {
+    let convert = |props: &Properties| {
+      props
+        .iter()
+        .map(|(key, value)| (key.clone(), value.into()))
+        .collect()
+    };
+
+    match value {
+      Val::Map(map) => Ok(map.clone()),
+      Val::Node(id) => Ok(
+        self
+          .graph
+          .node(*id)
+          .map(|node| convert(&node.properties))
+          .unwrap_or_default(),
+      ),
+      Val::Edge(id) => Ok(
+        self
+          .graph
+          .edge(*id)
+          .map(|edge| convert(&edge.properties))
+          .unwrap_or_default(),
+      ),
+      other => Err(query_error(&format!(
+        "Expected a map, node or relationship but found {}",
+        other.type_name()
+      ))),
+    }
+  }
+
+  fn property(&self, target: &Val, key: &str) -> Result<Val> {
+    let found = match target {
+      Val::Null => return Ok(Val::Null),
+      Val::Map(map) => return Ok(map.get(key).cloned().unwrap_or(Val::Null)),
+      Val::Node(id) => self
+        .graph
+        .node(*id)
+        .and_then(|node| node.properties.get(key)),
+      Val::Edge(id) => self
+        .graph
+        .edge(*id)
+        .and_then(|edge| edge.properties.get(key)),
+      other => {
+        return Err(query_error(&format!(
+          "Cannot read property '{}' of {}",
+          key,
+          other.type_name()
+        )))
+      }
+    };
+    Ok(found.map(Val::from).unwrap_or(Val::Null))
+  }
+
+  fn binary(&self, row: &Record, op: BinaryOp, left: &Expr, right: &Expr) -> Result<Val> {
+    let left = self.eval(row, left)?;
+
+    // Logic operators short circuit, and treat null as unknown
+    let logic = |value: &Val| match value {
+      Val::Null => Ok(None),
+      Val::Bool(inner) => Ok(Some(*inner)),
+      other => Err(query_error(&format!(
+        "Expected a boolean but found {}",
+        other.type_name()
+      ))),
+    };
+    let result = |value: Option<bool>| value.map(Val::Bool).unwrap_or(Val::Null);
+
+    match op {
+      BinaryOp::And => {
+        if logic(&left)? == Some(false) {
+          return Ok(Val::Bool(false));
+        }
+        let right = logic(&self.eval(row, right)?)?;
+        return Ok(match (logic(&left)?, right) {
+          (_, Some(false)) => Val::Bool(false),
+          (Some(true), Some(true)) => Val::Bool(true),
+          _ => Val::Null,
+        });
+      }
+      BinaryOp::Or => {
+        if logic(&left)? == Some(true) {
+          return Ok(Val::Bool(true));
+        }
+        let right = logic(&self.eval(row, right)?)?;
+        return Ok(match (logic(&left)?, right) {
+          (_, Some(true)) => Val::Bool(true),
+          (Some(false), Some(false)) => Val::Bool(false),
+          _ => Val::Null,
+        });
+      }
+      BinaryOp::Xor => {
+        let right = logic(&self.eval(row, right)?)?;
+        return Ok(result(
+          logic(&left)?.and_then(|left| right.map(|right| left != right)),
+        ));
+      }
+      _ => (),
+    }
+
+    let right = self.eval(row, right)?;
+    let ordered = |check: fn(Ordering) -> bool| result(left.compare(&right).map(check));
+
+    match op {
+      BinaryOp::Eq => Ok(result(left.equals(&right))),
+      BinaryOp::Ne => Ok(result(left.equals(&right).map(|equal| !equal))),
+      BinaryOp::Lt => Ok(ordered(Ordering::is_lt)),
+      BinaryOp::Le => Ok(ordered(Ordering::is_le)),
+      BinaryOp::Gt => Ok(ordered(Ordering::is_gt)),
+      BinaryOp::Ge => Ok(ordered(Ordering::is_ge)),
+      BinaryOp::In => match right {
+        Val::Null => Ok(Val::Null),
+        Val::List(items) => {
+          let mut found = Some(false);
+          for item in items.iter() {
+            match left.equals(item) {
+              Some(true) => return Ok(Val::Bool(true)),
+              None => found = None,
+              Some(false) => (),
+            }
+          }
+          Ok(result(found))
+        }
+        other => Err(query_error(&format!(
+          "IN expects a list but found {}",
+          other.type_name()
+        ))),
+      },
+      BinaryOp::StartsWith | BinaryOp::EndsWith | BinaryOp::Contains => match (&left, &right) {
+        (Val::Str(left), Val::Str(right)) => Ok(Val::Bool(match op {
+          BinaryOp::StartsWith => left.starts_with(right.as_str()),
+          BinaryOp::EndsWith => left.ends_with(right.as_str()),
+          _ => left.contains(right.as_str()),
+        })),
+        _ => Ok(Val::Null),
+      },
+      _ => arithmetic(op, left, right),
+    }
+  }
+
+  fn function(&self, name: &str, args: Vec<Val>) -> Result<Val> {
+    let arity = |count: usize| match args.len() == count {
+      true => Ok(()),
+      false => Err(query_error(&format!(
+        "{}() takes {} argument(s) but was given {}",
+        name,
+        count,
+        args.len()
+      ))),
+    };
+    let wrong_type = |value: &Val| {
+      query_error(&format!(
+        "{}() cannot be used on {}",
+        name,
+        value.type_name()
+      ))
+    };
+
+    // Every function besides coalesce returns null when given null
+    if name != "coalesce" && args.first() == Some(&Val::Null) {
+      return Ok(Val::Null);
+    }
+
+    match name {
+      "coalesce" => Ok(
+        args
+          .into_iter()
+          .find(|arg| *arg != Val::Null)
+          .unwrap_or(Val::Null),
+      ),
+      "id" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Node(id) | Val::Edge(id) => Ok(Val::Int(*id as i64)),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "labels" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Node(id) => Ok(Val::List(
+            self
+              .graph
+              .node(*id)
+              .map(|node| node.labels.iter().cloned().map(Val::Str).collect())
+              .unwrap_or_default(),
+          )),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "type" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Edge(id) => Ok(
+            self
+              .graph
+              .edge(*id)
+              .map(|edge| Val::Str(edge.label.clone()))
+              .unwrap_or(Val::Null),
+          ),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "startnode" | "endnode" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Edge(id) => Ok(
+            self
+              .graph
+              .edge(*id)
+              .map(|edge| match name {
+                "startnode" => Val::Node(edge.source),
+                _ => Val::Node(edge.target),
+              })
+              .unwrap_or(Val::Null),
+          ),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "properties" => {
+        arity(1)?;
+        Ok(Val::Map(self.properties(&args[0])?))
+      }
+      "keys" => {
+        arity(1)?;
+        Ok(Val::List(
+          self
+            .properties(&args[0])?
+            .into_keys()
+            .map(Val::Str)
+            .collect(),
+        ))
+      }
+      "size" | "length" => {
+        arity(1)?;
+        match &args[0] {
+          Val::List(items) => Ok(Val::Int(items.len() as i64)),
+          Val::Str(value) => Ok(Val::Int(value.chars().count() as i64)),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "tolower" | "toupper" | "trim" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Str(value) => Ok(Val::Str(match name {
+            "tolower" => value.to_lowercase(),
+            "toupper" => value.to_uppercase(),
+            _ => value.trim().to_string(),
+          })),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "tostring" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Str(value) => Ok(Val::Str(value.clone())),
+          Val::Int(value) => Ok(Val::Str(value.to_string())),
+          Val::Float(value) => Ok(Val::Str(value.to_string())),
+          Val::Bool(value) => Ok(Val::Str(value.to_string())),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "tointeger" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Int(value) => Ok(Val::Int(*value)),
+          Val::Float(value) => Ok(Val::Int(value.trunc() as i64)),
+          Val::Str(value) => Ok(value.trim().parse().map(Val::Int).unwrap_or(Val::Null)),
+          other => Err(wrong_type(other)),
+        }
+      }
+      "tofloat" => {
+        arity(1)?;
+        match &args[0] {
+          Val::Int(value) => Ok(Val::Float(*value as f64)),
+          Val::Float(value) => Ok(Val::Float(*value)),
+          Val::Str(value) => Ok(value.trim().parse().map(Val::Float).unwrap_or(Val::Null)),
+          other => Err(wrong_type(other)),
+        }
+      }
+      _ => Err(query_error(&format!("Unknown function {}()", name))),
+    }
+  }
+
+  /// Combine the rows of a group with an aggregate function
+  pub fn aggregate(&self, rows: &[&Record], expr: &Expr) -> Result<Val> {
+    let (name, distinct, arg) = match expr {
+      Expr::CountStar => return Ok(Val::Int(rows.len() as i64)),
+      Expr::Function {
+        name,
+        distinct,
+        args,
+      } if args.len() == 1 => (name.as_str(), *distinct, &args[0]),
+      Expr::Function { name, .. } => {
+        return Err(query_error(&format!("{}() takes 1 argument", name)))
+      }
+      _ => return Err(query_error("Expected an aggregate function")),
+    };
+
+    // Nulls are ignored by every aggregate
+    let mut values: Vec<Val> = Vec::new();
+    for row in rows {
+      let value = self.eval(row, arg)?;
+      if value != Val::Null && !(distinct && values.contains(&value)) {
+        values.push(value);
+      }
+    }
+
+    match name {
+      "count" => Ok(Val::Int(values.len() as i64)),
+      "collect" => Ok(Val::List(values)),
+      "sum" => values.into_iter().try_fold(Val::Int(0), |total, value| {
+        arithmetic(BinaryOp::Add, total, value)
+      }),
+      "avg" => match values.is_empty() {
+        true => Ok(Val::Null),
+        false => {
+          let count = values.len() as f64;
+          let total = values
+            .iter()
+            .map(|value| {
+              value.as_f64().ok_or_else(|| {
+                query_error(&format!("avg() cannot be used on {}", value.type_name()))
+              })
+            })
+            .sum::<Result<f64>>()?;
+          Ok(Val::Float(total / count))
+        }
+      },
+      "min" => Ok(
+        values
+          .into_iter()
+          .min_by(|a, b| a.sort_order(b))
+          .unwrap_or(Val::Null),
+      ),
+      "max" => Ok(
+        values
+          .into_iter()
+          .max_by(|a, b| a.sort_order(b))
+          .unwrap_or(Val::Null),
+      ),
+      _ => Err(query_error(&format!("Unknown aggregate {}()", name))),
+    }
+  }
+}
+
+fn arithmetic(op: BinaryOp, left: Val, right: Val) -> Result<Val> {
+  let overflow = || query_error("Integer overflow");
+
+  match (op, left, right) {
+    (_, Val::Null, _) | (_, _, Val::Null) => Ok(Val::Null),
+
+    // Concatenation
+    (BinaryOp::Add, Val::Str(a), Val::Str(b)) => Ok(Val::Str(a + &b)),
+    (BinaryOp::Add, Val::Str(a), b @ (Val::Int(_) | Val::Float(_) | Val::Bool(_))) => {
+      Ok(Val::Str(format!("{}{}", a, display(&b))))
+    }
+    (BinaryOp::Add, a @ (Val::Int(_) | Val::Float(_) | Val::Bool(_)), Val::Str(b)) => {
+      Ok(Val::Str(format!("{}{}", display(&a), b)))
+    }
+    (BinaryOp::Add, Val::List(mut a), Val::List(b)) => {
+      a.extend(b);
+      Ok(Val::List(a))
+    }
+    (BinaryOp::Add, Val::List(mut a), b) => {
+      a.push(b);
+      Ok(Val::List(a))
+    }
+
+    (op, Val::Int(a), Val::Int(b)) => match op {
+      BinaryOp::Add => a.checked_add(b).map(Val::Int).ok_or_else(overflow),
+      BinaryOp::Sub => a.checked_sub(b).map(Val::Int).ok_or_else(overflow),
+      BinaryOp::Mul => a.checked_mul(b).map(Val::Int).ok_or_else(overflow),
+      BinaryOp::Div | BinaryOp::Mod if b == 0 => Err(query_error("Division by zero")),
+      BinaryOp::Div => a.checked_div(b).map(Val::Int).ok_or_else(overflow),
+      _ => a.checked_rem(b).map(Val::Int).ok_or_else(overflow),
+    },
+    (op, a @ (Val::Int(_) | Val::Float(_)), b @ (Val::Int(_) | Val::Float(_))) => {
+      let (a, b) = (
+        a.as_f64().unwrap_or_default(),
+        b.as_f64().unwrap_or_default(),
+      );
+      Ok(Val::Float(match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        _ => a % b,
+      }))
+    }
+    (_, a, b) => Err(query_error(&format!(
+      "Cannot do arithmetic on {} and {}",
+      a.type_name(),
+      b.type_name()
+    ))),
+  }
+}
+
+/// The text of a scalar when concatenated to a string
+fn display(value: &Val) -> String {
+  match value {
+    Val::Int(inner) => inner.to_string(),
+    Val::Float(inner) => inner.to_string(),
+    Val::Bool(inner) => inner.to_string(),
+    Val::Str(inner) => inner.clone(),
+    _ => String::new(),
+  }
+}
@@ -0,0 +1,836 @@
+//! Run a parsed query against a graph
+//!
+//! Each clause takes the rows produced by the clause before it and produces a new set of rows,
+//! starting from a single empty row.
+
+use crate::local::*;
+
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+use super::{
+  super::{
+    store::{EdgeId, NodeId},
+    Direction, Graph, Properties, Row, Value,
+  },
+  ast::*,
+  eval::{query_error, Eval, Record, Val},
+};
+
+pub fn run(graph: &mut Graph, query: &Query, params: &Properties) -> Result<Vec<Row>> {
+  let mut rows = vec![Record::new()];
+
+  for clause in query.clauses.iter() {
+    rows = match clause {
+      Clause::Create(patterns) => {
+        for row in rows.iter_mut() {
+          for pattern in patterns {
+            create_pattern(graph, params, row, pattern, false)?;
+          }
+        }
+        rows
+      }
+
+      Clause::Merge {
+        pattern,
+        on_create,
+        on_match,
+      } => {
+        let mut results = Vec::new();
+        for row in rows.iter() {
+          let found = match_patterns(&Eval { graph, params }, row, std::slice::from_ref(pattern))?;
+          match found.is_empty() {
+            true => {
+              let mut row = row.clone();
+              create_pattern(graph, params, &mut row, pattern, true)?;
+              set_items(graph, params, &row, on_create)?;
+              results.push(row);
+            }
+            false => {
+              for row in found {
+                set_items(graph, params, &row, on_match)?;
+                results.push(row);
+              }
+            }
+          }
+        }
+        results
+      }
+
+      Clause::Set(items) => {
+        for row in rows.iter() {
+          set_items(graph, params, row, items)?;
+        }
+        rows
+      }
+
+      Clause::Delete { detach, targets } => {
+        delete(graph, params, &rows, targets, *detach)?;
+        rows
+      }
+
+      Clause::Return(projection) => return finish(graph, params, rows, projection),
+      clause => read(graph, params, rows, clause)?,
+    };
+  }
+
+  // Queries without a RETURN clause don't produce any rows
+  Ok(Vec::new())
+}
+
+/// Run a query that doesn't change the graph, so the graph only needs to be borrowed
+pub fn run_read_only(graph: &Graph, query: &Query, params: &Properties) -> Result<Vec<Row>> {
+  let mut rows = vec![Record::new()];
+
+  for clause in query.clauses.iter() {
+    rows = match clause {
+      Clause::Return(projection) => return finish(graph, params, rows, projection),
+      clause => read(graph, params, rows, clause)?,
+    };
+  }
+  Ok(Vec::new())
+}
+
+/// Run one of the clauses that only read the graph
+fn read(
+  graph: &Graph,
+  params: &Properties,
+  rows: Vec<Record>,
+  clause: &Clause,
+) -> Result<Vec<Record>> {
+  let eval = Eval { graph, params };
+  match clause {
+    Clause::Match {
+      optional,
+      patterns,
+      filter,
+    } => {
+      let mut results = Vec::new();
+      for row in rows.iter() {
+        let mut found = Vec::new();
+        for candidate in match_patterns(&eval, row, patterns)? {
+          let keep = match filter {
+            Some(filter) => eval.eval(&candidate, filter)?.is_true(),
+            None => true,
+          };
+          if keep {
+            found.push(candidate);
+          }
+        }
+
+        // An optional match that finds nothing keeps the row, with the new variables set to null
+        if found.is_empty() && *optional {
+          let mut row = row.clone();
+          for var in pattern_variables(patterns) {
+            row.entry(var).or_insert(Val::Null);
+          }
+          found.push(row);
+        }
+        results.extend(found);
+      }
+      Ok(results)
+    }
+
+    Clause::Unwind { list, alias } => {
+      let mut results = Vec::new();
+      for row in rows.iter() {
+        let items = match eval.eval(row, list)? {
+          Val::Null => vec![],
+          Val::List(items) => items,
+          other => vec![other],
+        };
+        for item in items {
+          let mut row = row.clone();
+          row.insert(alias.clone(), item);
+          results.push(row);
+        }
+      }
+      Ok(results)
+    }
+
+    Clause::With(projection) => {
+      let projected = project(&eval, rows, projection)?;
+      match &projection.filter {
+        Some(filter) => {
+          let mut kept = Vec::new();
+          for row in projected {
+            if eval.eval(&row, filter)?.is_true() {
+              kept.push(row);
+            }
+          }
+          Ok(kept)
+        }
+        None => Ok(projected),
+      }
+    }
+
+    _ => Err(query_error(
+      "The query changes the graph, so it can't be run as read only",
+    )),
+  }
+}
+
+/// The rows of the RETURN clause
+fn finish(
+  graph: &Graph,
+  params: &Properties,
+  rows: Vec<Record>,
+  projection: &Projection,
+) -> Result<Vec<Row>> {
+  let eval = Eval { graph, params };
+  let projected = project(&eval, rows, projection)?;
+  Ok(
+    projected
+      .iter()
+      .map(|row| {
+        row
+          .iter()
+          .map(|(key, value)| (key.clone(), value.to_value(graph)))
+          .collect()
+      })
+      .collect(),
+  )
+}
+
+// -------------------------   Matching   --------------------------------
+
+/// The edges already used by a match, as a relationship is only matched once per pattern
+type Used = BTreeSet<EdgeId>;
+
+fn match_patterns(eval: &Eval, row: &Record, patterns: &[Pattern]) -> Result<Vec<Record>> {
+  let mut partial = vec![(row.clone(), Used::new())];
+  for pattern in patterns {
+    let mut next = Vec::new();
+    for (row, used) in partial.iter() {
+      match_pattern(eval, row, used, pattern, &mut next)?;
+    }
+    partial = next;
+  }
+  Ok(partial.into_iter().map(|(row, _)| row).collect())
+}
+
+fn match_pattern(
+  eval: &Eval,
+  row: &Record,
+  used: &Used,
+  pattern: &Pattern,
+  found: &mut Vec<(Record, Used)>,
+) -> Result<()> {
+  for id in start_candidates(eval, row, &pattern.start)? {
+    let mut row = row.clone();
+    if node_matches(eval, &row, id, &pattern.start)?
+      && bind(&mut row, &pattern.start.var, Val::Node(id))
+    {
+      extend(eval, row, used.clone(), id, &pattern.steps, found)?;
+    }
+  }
+  Ok(())
+}
+
+/// The nodes that could be the start of a pattern, using the indexes where possible
+fn start_candidates(eval: &Eval, row: &Record, node: &NodePattern) -> Result<Vec<NodeId>> {
+  if let Some(bound) = node.var.as_ref().and_then(|var| row.get(var)) {
+    return Ok(match bound {
+      Val::Node(id) => vec![*id],
+      _ => vec![],
+    });
+  }
+
+  if let Some((_, guid)) = node.properties.iter().find(|(key, _)| key == "guid") {
+    if let Val::Str(guid) = eval.eval(row, guid)? {
+      if let Ok(guid) = Uuid::parse_str(&guid) {
+        return Ok(eval.graph.find_guid(&guid).into_iter().collect());
+      }
+    }
+  }
+
+  Ok(match node.labels.first() {
+    Some(label) => eval.graph.with_label(label),
+    None => eval.graph.nodes().map(|(id, _)| id).collect(),
+  })
+}
+
+fn node_matches(eval: &Eval, row: &Record, id: NodeId, pattern: &NodePattern) -> Result<bool> {
+  let node = match eval.graph.node(id) {
+    Some(node) => node,
+    None => return Ok(false),
+  };
+  if !pattern
+    .labels
+    .iter()
+    .all(|label| node.labels.contains(label))
+  {
+    return Ok(false);
+  }
+  properties_match(eval, row, &node.properties, &pattern.properties)
+}
+
+fn properties_match(
+  eval: &Eval,
+  row: &Record,
+  properties: &Properties,
+  expected: &[(String, Expr)],
+) -> Result<bool> {
+  for (key, expr) in expected {
+    let actual = properties.get(key).map(Val::from).unwrap_or(Val::Null);
+    if actual.equals(&eval.eval(row, expr)?) != Some(true) {
+      return Ok(false);
+    }
+  }
+  Ok(true)
+}
+
+/// Add a value to the row, failing if the variable is already bound to something else
+fn bind(row: &mut Record, var: &Option<String>, value: Val) -> bool {
+  match var {
+    None => true,
+    Some(var) => match row.get(var) {
+      Some(existing) => *existing == value,
+      None => {
+        row.insert(var.clone(), value);
+        true
+      }
+    },
+  }
+}
+
+/// Follow the remaining steps of a pattern from the current node
+fn extend(
+  eval: &Eval,
+  row: Record,
+  used: Used,
+  current: NodeId,
+  steps: &[(RelPattern, NodePattern)],
+  found: &mut Vec<(Record, Used)>,
+) -> Result<()> {
+  let (rel, node) = match steps.first() {
+    Some(step) => step,
+    None => {
+      found.push((row, used));
+      return Ok(());
+    }
+  };
+
+  match rel.length {
+    None => {
+      for (edge, next) in step_edges(eval, &row, &used, current, rel)? {
+        let mut row = row.clone();
+        if node_matches(eval, &row, next, node)?
+          && bind(&mut row, &rel.var, Val::Edge(edge))
+          && bind(&mut row, &node.var, Val::Node(next))
+        {
+          let mut used = used.clone();
+          used.insert(edge);
+          extend(eval, row, used, next, &steps[1..], found)?;
+        }
+      }
+    }
+    Some((min, max)) => {
+      let mut paths = Vec::new();
+      walk(
+        eval,
+        &row,
+        &used,
+        current,
+        rel,
+        &mut vec![],
+        min,
+        max,
+        &mut paths,
+      )?;
+      for (path, next) in paths {
+        let mut row = row.clone();
+        let edges = Val::List(path.iter().map(|edge| Val::Edge(*edge)).collect());
+        if node_matches(eval, &row, next, node)?
+          && bind(&mut row, &rel.var, edges)
+          && bind(&mut row, &node.var, Val::Node(next))
+        {
+          let mut used = used.clone();
+          used.extend(path);
+          extend(eval, row, used, next, &steps[1..], found)?;
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// The unused edges from a node that match a relationship pattern, with the node at the other end
+fn step_edges(
+  eval: &Eval,
+  row: &Record,
+  used: &Used,
+  current: NodeId,
+  rel: &RelPattern,
+) -> Result<Vec<(EdgeId, NodeId)>> {
+  let mut steps = Vec::new();
+  for id in eval.graph.edges_of(current, None, rel.direction) {
+    let edge = match eval.graph.edge(id) {
+      Some(edge) => edge,
+      None => continue,
+    };
+    if used.contains(&id) || !(rel.types.is_empty() || rel.types.contains(&edge.label)) {
+      continue;
+    }
+    if properties_match(eval, row, &edge.properties, &rel.properties)? {
+      steps.push((id, edge.other(current)));
+    }
+  }
+  Ok(steps)
+}
+
+/// Collect every path of a variable length relationship, depth first
+#[allow(clippy::too_many_arguments)]
+fn walk(
+  eval: &Eval,
+  row: &Record,
+  used: &Used,
+  current: NodeId,
+  rel: &RelPattern,
+  path: &mut Vec<EdgeId>,
+  min: usize,
+  max: Option<usize>,
+  paths: &mut Vec<(Vec<EdgeId>, NodeId)>,
+) -> Result<()> {
+  if path.len() >= min {
+    paths.push((path.clone(), current));
+  }
+  if max.map(|max| path.len() >= max).unwrap_or(false) {
+    return Ok(());
+  }
+
+  for (edge, next) in step_edges(eval, row, used, current, rel)? {
+    if path.contains(&edge) {
+      continue;
+    }
+    path.push(edge);
+    walk(eval, row, used, next, rel, path, min, max, paths)?;
+    path.pop();
+  }
+  Ok(())
+}
+
+fn pattern_variables(patterns: &[Pattern]) -> Vec<String> {
+  patterns
+    .iter()
+    .flat_map(|pattern| {
+      std::iter::once(&pattern.start.var).chain(
+        pattern
+          .steps
+          .iter()
+          .flat_map(|(rel, node)| [&rel.var, &node.var]),
+      )
+    })
+    .flatten()
+    .cloned()
+    .collect()
+}
+
+// -------------------------   Updating   --------------------------------
+
+/// Evaluate a property map into values that can be stored, leaving out nulls
+fn stored_properties(
+  graph: &Graph,
+  params: &Properties,
+  row: &Record,
+  entries: &[(String, Expr)],
+) -> Result<Properties> {
+  let eval = Eval { graph, params };
+  let mut properties = Properties::new();
+  for (key, value) in eval.eval_map(row, entries)? {
+    if value != Val::Null {
+      properties.insert(key, value.to_property(graph)?);
+    }
+  }
+  Ok(properties)
+}
+
+/// Create every part of a pattern that isn't already bound in the row
+///
+/// MERGE may create relationships without a direction, which are created left to right.
+fn create_pattern(
+  graph: &mut Graph,
+  params: &Properties,
+  row: &mut Record,
+  pattern: &Pattern,
+  merge: bool,
+) -> Result<()> {
+  let mut current = create_node(graph, params, row, &pattern.start)?;
+
+  for (rel, node) in pattern.steps.iter() {
+    let next = create_node(graph, params, row, node)?;
+
+    let label = match (rel.types.as_slice(), rel.length) {
+      ([label], None) => label,
+      _ => {
+        return Err(query_error(
+          "Relationships must have exactly one type and no length to be created",
+        ))
+      }
+    };
+    let (source, target) = match rel.direction {
+      Direction::Outgoing => (current, next),
+      Direction::Incoming => (next, current),
+      Direction::Both if merge => (current, next),
+      Direction::Both => {
+        return Err(query_error(
+          "Relationships must have a direction to be created",
+        ))
+      }
+    };
+    if let Some(var) = rel.var.as_ref().filter(|var| row.contains_key(*var)) {
+      return Err(query_error(&format!(
+        "Variable `{}` is already bound and cannot be created",
+        var
+      )));
+    }
+
+    let properties = stored_properties(graph, params, row, &rel.properties)?;
+    let edge = graph.add_edge(label, source, target, properties)?;
+    bind(row, &rel.var, Val::Edge(edge));
+    current = next;
+  }
+  Ok(())
+}
+
+fn create_node(
+  graph: &mut Graph,
+  params: &Properties,
+  row: &mut Record,
+  node: &NodePattern,
+) -> Result<NodeId> {
+  if let Some(bound) = node.var.as_ref().and_then(|var| row.get(var)) {
+    return match (bound, node.labels.is_empty() && node.properties.is_empty()) {
+      (Val::Node(id), true) => Ok(*id),
+      (Val::Node(_), false) => Err(query_error(&format!(
+        "Variable `{}` is already bound, so it cannot be given labels or properties",
+        node.var.as_deref().unwrap_or_default()
+      ))),
+      _ => Err(query_error(&format!(
+        "Variable `{}` is not a node",
+        node.var.as_deref().unwrap_or_default()
+      ))),
+    };
+  }
+
+  let properties = stored_properties(graph, params, row, &node.properties)?;
+  let id = graph.add_node(node.labels.clone(), properties)?;
+  bind(row, &node.var, Val::Node(id));
+  Ok(id)
+}
+
+fn set_items(
+  graph: &mut Graph,
+  params: &Properties,
+  row: &Record,
+  items: &[SetItem],
+) -> Result<()> {
+  for item in items {
+    let (var, updates, replace) = {
+      let eval = Eval { graph, params };
+      match item {
+        SetItem::Property { var, key, value } => {
+          let value = eval.eval(row, value)?;
+          (var, vec![(key.clone(), value)], false)
+        }
+        SetItem::Replace { var, value } | SetItem::Merge { var, value } => {
+          let value = eval.eval(row, value)?;
+          let updates = match value {
+            Val::Null => vec![],
+            value => eval.properties(&value)?.into_iter().collect(),
+          };
+          (var, updates, matches!(item, SetItem::Replace { .. }))
+        }
+        SetItem::Labels { var, labels } => {
+          match row.get(var) {
+            Some(Val::Node(id)) => {
+              for label in labels {
+                graph.add_label(*id, label)?;
+              }
+            }
+            Some(Val::Null) => (),
+            _ => return Err(query_error(&format!("Variable `{}` is not a node", var))),
+          }
+          continue;
+        }
+      }
+    };
+
+    let updates: Vec<(String, Value)> = updates
+      .into_iter()
+      .map(|(key, value)| Ok((key, value.to_property(graph)?)))
+      .collect::<Result<_>>()?;
+
+    match row.get(var) {
+      Some(Val::Node(id)) => {
+        if replace {
+          let old: Vec<String> = graph
+            .node(*id)
+            .map(|node| node.properties.keys().cloned().collect())
+            .unwrap_or_default();
+          for key in old {
+            graph.set_property(*id, &key, Value::Null)?;
+          }
+        }
+        for (key, value) in updates {
+          graph.set_property(*id, &key, value)?;
+        }
+      }
+      Some(Val::Edge(id)) => {
+        if replace {
+          let old: Vec<String> = graph
+            .edge(*id)
+            .map(|edge| edge.properties.keys().cloned().collect())
+            .unwrap_or_default();
+          for key in old {
+            graph.set_edge_property(*id, &key, Value::Null)?;
+          }
+        }
+        for (key, value) in updates {
+          graph.set_edge_property(*id, &key, value)?;
+        }
+      }
+      // Setting a property on an optional match that found nothing does nothing
+      Some(Val::Null) => (),
+      Some(_) => {
+        return Err(query_error(&format!(
+          "Variable `{}` is not a node or relationship",
+          var
+        )))
+      }
+      None => return Err(query_error(&format!("Variable `{}` is not defined", var))),
+    }
+  }
+  Ok(())
+}
+
+fn delete(
+  graph: &mut Graph,
+  params: &Properties,
+  rows: &[Record],
+  targets: &[Expr],
+  detach: bool,
+) -> Result<()> {
+  let mut nodes = BTreeSet::new();
+  let mut edges = BTreeSet::new();
+
+  let eval = Eval { graph, params };
+  let mut pending = Vec::new();
+  for row in rows {
+    for target in targets {
+      pending.push(eval.eval(row, target)?);
+    }
+  }
+  while let Some(value) = pending.pop() {
+    match value {
+      Val::Null => (),
+      Val::Node(id) => {
+        nodes.insert(id);
+      }
+      Val::Edge(id) => {
+        edges.insert(id);
+      }
+      Val::List(items) => pending.extend(items),
+      other => {
+        return Err(query_error(&format!(
+          "Only nodes and relationships can be deleted, not {:?}",
+          other
+        )))
+      }
+    }
+  }
+
+  for edge in edges {
+    graph.remove_edge(edge);
+  }
+  for node in nodes {
+    if graph.node(node).is_some() {
+      graph.remove_node(node, detach)?;
+    }
+  }
+  Ok(())
+}
+
+// ------------------------   Projection   -------------------------------
+
+fn project(eval: &Eval, rows: Vec<Record>, projection: &Projection) -> Result<Vec<Record>> {
+  let items: Vec<ProjectionItem> = match projection.star {
+    true => rows
+      .first()
+      .map(|row| {
+        row
+          .keys()
+          .map(|name| ProjectionItem {
+            expr: Expr::Variable(name.clone()),
+            name: name.clone(),
+          })
+          .collect()
+      })
+      .unwrap_or_default(),
+    false => projection.items.clone(),
+  };
+
+  // Pairs of the projected row and the row it came from, which ORDER BY may also refer to
+  let mut results: Vec<(Record, Option<Record>)> =
+    match items.iter().any(|item| item.expr.contains_aggregate()) {
+      true => aggregate(eval, &rows, &items)?
+        .into_iter()
+        .map(|row| (row, None))
+        .collect(),
+      false => {
+        let mut results = Vec::new();
+        for row in rows {
+          let mut projected = Record::new();
+          for item in items.iter() {
+            projected.insert(item.name.clone(), eval.eval(&row, &item.expr)?);
+          }
+          results.push((projected, Some(row)));
+        }
+        results
+      }
+    };
+
+  if projection.distinct {
+    let mut seen: Vec<Record> = Vec::new();
+    results.retain(|(row, _)| match seen.contains(row) {
+      true => false,
+      false => {
+        seen.push(row.clone());
+        true
+      }
+    });
+  }
+
+  if !projection.order.is_empty() {
+    let mut keyed = Vec::new();
+    for (projected, original) in results {
+      let mut keys = Vec::new();
+      for sort in projection.order.iter() {
+        let key = match projected.get(&sort.name) {
+          Some(value) => value.clone(),
+          None => {
+            let mut scope = original.clone().unwrap_or_default();
+            scope.extend(projected.clone());
+            eval.eval(&scope, &sort.expr)?
+          }
+        };
+        keys.push(key);
+      }
+      keyed.push((keys, (projected, original)));
+    }
+
+    keyed.sort_by(|(a, _), (b, _)| {
+      projection
+        .order
+        .iter()
+        .zip(a.iter().zip(b))
+        .map(|(sort, (a, b))| match sort.descending {
+          true => b.sort_order(a),
+          false => a.sort_order(b),
+        })
+        .find(|order| order.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    results = keyed.into_iter().map(|(_, result)| result).collect();
+  }
+
+  let count = |expr: &Option<Expr>, clause: &str| -> Result<Option<usize>> {
+    match expr {
+      None => Ok(None),
+      Some(expr) => match eval.eval(&Record::new(), expr)? {
+        Val::Int(value) if value >= 0 => Ok(Some(value as usize)),
+        _ => Err(query_error(&format!(
+          "{} must be a positive integer",
+          clause
+        ))),
+      },
+    }
+  };
+  let skip = count(&projection.skip, "SKIP")?.unwrap_or(0);
+  let limit = count(&projection.limit, "LIMIT")?.unwrap_or(usize::MAX);
+
+  Ok(
+    results
+      .into_iter()
+      .skip(skip)
+      .take(limit)
+      .map(|(row, _)| row)
+      .collect(),
+  )
+}
+
+/// Group the rows by the plain columns and combine each group with the aggregate columns
+fn aggregate(eval: &Eval, rows: &[Record], items: &[ProjectionItem]) -> Result<Vec<Record>> {
+  let mut groups: Vec<(Vec<Val>, Vec<&Record>)> = Vec::new();
+  let mut index: HashMap<String, usize> = HashMap::new();
+
+  for row in rows {
+    let mut key = Vec::new();
+    for item in items.iter().filter(|item| !item.expr.contains_aggregate()) {
+      key.push(eval.eval(row, &item.expr)?);
+    }
+
+    // Values don't implement Hash, so the debug form stands in for it
+    let hashed = format!("{:?}", key);
+    match index.get(&hashed) {
+      Some(position) => groups[*position].1.push(row),
+      None => {
+        index.insert(hashed, groups.len());
+        groups.push((key, vec![row]));
+      }
+    }
+  }
+
+  // Aggregating nothing still produces a row, such as a count of 0
+  if groups.is_empty() && items.iter().all(|item| item.expr.contains_aggregate()) {
+    groups.push((vec![], vec![]));
+  }
+
+  let mut results = Vec::new();
+  for (key, group) in groups {
+    let mut key = key.into_iter();
+    let mut projected = Record::new();
+    for item in items {
+      let value = match item.expr.contains_aggregate() {
+        true => grouped(eval, &group, &item.expr)?,
+        false => key.next().unwrap_or(Val::Null),
+      };
+      projected.insert(item.name.clone(), value);
+    }
+    results.push(projected);
+  }
+  Ok(results)
+}
+
+/// Evaluate a column holding aggregates for one group. Each aggregate is worked out over the whole
+/// group, and the rest of the expression is evaluated against the group's first row, where the
+/// grouping columns are the same for every row.
+fn grouped(eval: &Eval, group: &[&Record], expr: &Expr) -> Result<Val> {
+  if expr.is_aggregate() {
+    return eval.aggregate(group, expr);
+  }
+  let mut scope = group.first().map(|row| (*row).clone()).unwrap_or_default();
+  let mut expr = expr.clone();
+  replace_aggregates(eval, group, &mut expr, &mut scope)?;
+  eval.eval(&scope, &expr)
+}
+
+/// Swap each aggregate inside the expression for a variable holding its value. The names start with
+/// a space, which a query can't use, so they never hide a variable of the query.
+fn replace_aggregates(
+  eval: &Eval,
+  group: &[&Record],
+  expr: &mut Expr,
+  scope: &mut Record,
+) -> Result<()> {
+  if expr.is_aggregate() {
+    let name = format!(" aggregate {}", scope.len());
+    scope.insert(name.clone(), eval.aggregate(group, expr)?);
+    *expr = Expr::Variable(name);
+    return Ok(());
+  }
+  for child in expr.children_mut() {
+    replace_aggregates(eval, group, child, scope)?;
+  }
+  Ok(())
+}
@@ -0,0 +1,192 @@
+//! Split a query into tokens
+//!
+//! Keywords are not distinguished from identifiers here, as Cypher keywords are case insensitive and
+//! only reserved in certain positions. The parser decides what a bare word means.
+
+use crate::local::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+  /// A bare word: a keyword, variable, label, or function name
+  Ident(String),
+
+  /// A name escaped with backticks, which is never treated as a keyword
+  Quoted(String),
+
+  /// A query parameter, without the leading `$`
+  Param(String),
+
+  Str(String),
+  Int(i64),
+  Float(f64),
+  Symbol(&'static str),
+}
+
+/// A token and the byte range it was read from
+#[derive(Clone, Debug)]
+pub struct Spanned {
+  pub token: Token,
+  pub start: usize,
+  pub end: usize,
+}
+
+/// Multi-character symbols come first so they are matched before their prefixes
+const SYMBOLS: [&str; 24] = [
+  "..", "<>", "<=", ">=", "+=", "(", ")", "[", "]", "{", "}", ":", ";", ",", ".", "=", "<", ">",
+  "+", "-", "*", "/", "%", "|",
+];
+
+/// Describe where in the query a byte offset is, for error messages
+pub fn position(query: &str, offset: usize) -> String {
+  let before = &query[..offset.min(query.len())];
+  let line = before.matches('\n').count() + 1;
+  let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+  format!("line {}, column {}", line, column)
+}
+
+#[track_caller]
+pub fn syntax_error(query: &str, offset: usize, msg: &str) -> AllWhat<WranglerErrorKind> {
+  let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+  err
+    .set_context(&format!(
+      "Invalid query at {}: {}",
+      position(query, offset),
+      msg
+    ))
+    .set_dev_context(query)
+}
+
+pub fn tokenize(query: &str) -> Result<Vec<Spanned>> {
+  let mut tokens = Vec::new();
+  let mut chars = query.char_indices().peekable();
+
+  while let Some(&(start, c)) = chars.peek() {
+    // Whitespace and line comments
+    if c.is_whitespace() {
+      chars.next();
+      continue;
+    }
+    if query[start..].starts_with("//") {
+      while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+      continue;
+    }
+
+    let token = if c.is_alphabetic() || c == '_' {
+      let mut word = String::new();
+      while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+        word.push(c);
+      }
+      Token::Ident(word)
+    } else if c.is_ascii_digit() {
+      number(query, start, &mut chars)?
+    } else if c == '$' {
+      chars.next();
+      let mut name = String::new();
+      while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+        name.push(c);
+      }
+      if name.is_empty() {
+        return Err(syntax_error(
+          query,
+          start,
+          "Expected a parameter name after '$'",
+        ));
+      }
+      Token::Param(name)
+    } else if c == '`' {
+      chars.next();
+      let mut name = String::new();
+      loop {
+        match chars.next() {
+          Some((_, '`')) => break,
+          Some((_, c)) => name.push(c),
+          None => return Err(syntax_error(query, start, "Unterminated quoted name")),
+        }
+      }
+      Token::Quoted(name)
+    } else if c == '\'' || c == '"' {
+      chars.next();
+      Token::Str(string(query, start, c, &mut chars)?)
+    } else {
+      match SYMBOLS
+        .iter()
+        .find(|symbol| query[start..].starts_with(*symbol))
+      {
+        Some(symbol) => {
+          for _ in 0..symbol.len() {
+            chars.next();
+          }
+          Token::Symbol(symbol)
+        }
+        None => {
+          return Err(syntax_error(
+            query,
+            start,
+            &format!("Unexpected character '{}'", c),
+          ))
+        }
+      }
+    };
+
+    let end = chars.peek().map(|(end, _)| *end).unwrap_or(query.len());
+    tokens.push(Spanned { token, start, end });
+  }
+
+  Ok(tokens)
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+fn number(query: &str, start: usize, chars: &mut Chars) -> Result<Token> {
+  let mut end = start;
+  let mut is_float = false;
+  while let Some((pos, c)) = chars.peek().copied() {
+    let next = query[pos + c.len_utf8()..].chars().next();
+    match c {
+      '0'..='9' => (),
+      // A range such as `*1..3` must not be read as a float
+      '.' if !is_float && next.map(|n| n.is_ascii_digit()).unwrap_or(false) => is_float = true,
+      'e' | 'E'
+        if next
+          .map(|n| n.is_ascii_digit() || n == '-')
+          .unwrap_or(false) =>
+      {
+        is_float = true;
+        chars.next();
+        end = pos + 1;
+        if let Some((pos, _)) = chars.next_if(|(_, c)| *c == '-') {
+          end = pos + 1;
+        }
+        continue;
+      }
+      _ => break,
+    }
+    chars.next();
+    end = pos + 1;
+  }
+
+  let text = &query[start..end];
+  match is_float {
+    true => text.parse().map(Token::Float).ok(),
+    false => text.parse().map(Token::Int).ok(),
+  }
+  .ok_or_else(|| syntax_error(query, start, &format!("Invalid number '{}'", text)))
+}
+
+fn string(query: &str, start: usize, quote: char, chars: &mut Chars) -> Result<String> {
+  let mut value = String::new();
+  loop {
+    match chars.next() {
+      Some((_, c)) if c == quote => return Ok(value),
+      Some((pos, '\\')) => match chars.next() {
+        Some((_, 'n')) => value.push('\n'),
+        Some((_, 't')) => value.push('\t'),
+        Some((_, 'r')) => value.push('\r'),
+        Some((_, c @ ('\\' | '\'' | '"'))) => value.push(c),
+        _ => return Err(syntax_error(query, pos, "Invalid escape sequence")),
+      },
+      Some((_, c)) => value.push(c),
+      None => return Err(syntax_error(query, start, "Unterminated string")),
+    }
+  }
+}
@@ -0,0 +1,59 @@
+//! A query engine for the in-memory graph, using a practical subset of OpenCypher
+//!
+//! The goal is for the queries sent to Neo4j to also run against [Graph], so either can be used as
+//! the backing store. Supported clauses:
+//!
+//! - `MATCH` and `OPTIONAL MATCH`, with labels, properties, and directed, undirected, or variable
+//!   length (`*1..3`) relationships, followed by an optional `WHERE`
+//! - `UNWIND list AS name`
+//! - `CREATE`, and `MERGE` with `ON CREATE SET` / `ON MATCH SET`
+//! - `SET n.key = value`, `SET n = map`, `SET n += map`, and `SET n:Label`
+//! - `DELETE` and `DETACH DELETE`
+//! - `WITH` and `RETURN`, with `DISTINCT`, aliases, aggregates (count, collect, sum, avg, min, max),
+//!   `ORDER BY`, `SKIP` and `LIMIT`
+//!
+//! The changes a query makes are recorded as it runs and undone if any part of it fails, so a query
+//! is applied either completely or not at all.
+
+use crate::local::*;
+
+use super::{Graph, Properties, Row};
+
+pub mod ast;
+mod eval;
+mod exec;
+mod lexer;
+mod parser;
+
+pub use parser::parse;
+
+impl Graph {
+  /// Run a Cypher query with the parameters bound by name, returning the rows of the RETURN clause
+  pub fn execute(&mut self, query: &str, params: &Properties) -> Result<Vec<Row>> {
+    let parsed = parse(query)?;
+    self
+      .run(&parsed, params)
+      .map_err(|err| err.set_dev_context(query))
+  }
+
+  /// Run a query that has already been parsed
+  pub fn run(&mut self, query: &ast::Query, params: &Properties) -> Result<Vec<Row>> {
+    if query.is_read_only() {
+      return self.run_read_only(query, params);
+    }
+
+    self.begin();
+    let rows = exec::run(self, query, params);
+    match rows.is_ok() {
+      true => self.commit(),
+      false => self.rollback(),
+    }
+    rows
+  }
+
+  /// Run a query that doesn't change the graph, which only needs the graph to be borrowed. Queries
+  /// that would change it fail.
+  pub fn run_read_only(&self, query: &ast::Query, params: &Properties) -> Result<Vec<Row>> {
+    exec::run_read_only(self, query, params)
+  }
+}
@@ -0,0 +1,691 @@
+//! A recursive descent parser for the supported subset of Cypher
+
+use crate::local::*;
+
+use super::{
+  super::{Direction, Value},
+  ast::*,
+  lexer::{syntax_error, tokenize, Spanned, Token},
+};
+
+/// Parse a query into clauses, without checking it against any graph
+pub fn parse(query: &str) -> Result<Query> {
+  let mut parser = Parser {
+    query,
+    tokens: tokenize(query)?,
+    pos: 0,
+  };
+  parser.query()
+}
+
+struct Parser<'a> {
+  query: &'a str,
+  tokens: Vec<Spanned>,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  // -------------------------   Helpers   -------------------------------
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos).map(|spanned| &spanned.token)
+  }
+
+  fn peek_at(&self, offset: usize) -> Option<&Token> {
+    self
+      .tokens
+      .get(self.pos + offset)
+      .map(|spanned| &spanned.token)
+  }
+
+  fn advance(&mut self) -> Option<Token> {
+    let token = self.peek().cloned();
+    self.pos += 1;
+    token
+  }
+
+  #[track_caller]
+  fn error(&self, msg: &str) -> AllWhat<WranglerErrorKind> {
+    let offset = self
+      .tokens
+      .get(self.pos)
+      .map(|spanned| spanned.start)
+      .unwrap_or(self.query.len());
+    syntax_error(self.query, offset, msg)
+  }
+
+  /// The source text of the tokens from start up to the current position
+  fn text_since(&self, start: usize) -> String {
+    match (self.tokens.get(start), self.tokens.get(self.pos - 1)) {
+      (Some(first), Some(last)) => self.query[first.start..last.end].to_string(),
+      _ => String::new(),
+    }
+  }
+
+  fn is_keyword(&self, keyword: &str) -> bool {
+    matches!(self.peek(), Some(Token::Ident(word)) if word.eq_ignore_ascii_case(keyword))
+  }
+
+  fn eat_keyword(&mut self, keyword: &str) -> bool {
+    let found = self.is_keyword(keyword);
+    if found {
+      self.pos += 1;
+    }
+    found
+  }
+
+  fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+    match self.eat_keyword(keyword) {
+      true => Ok(()),
+      false => Err(self.error(&format!("Expected {}", keyword))),
+    }
+  }
+
+  fn is_symbol(&self, symbol: &str) -> bool {
+    matches!(self.peek(), Some(Token::Symbol(found)) if *found == symbol)
+  }
+
+  fn eat_symbol(&mut self, symbol: &str) -> bool {
+    let found = self.is_symbol(symbol);
+    if found {
+      self.pos += 1;
+    }
+    found
+  }
+
+  fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+    match self.eat_symbol(symbol) {
+      true => Ok(()),
+      false => Err(self.error(&format!("Expected '{}'", symbol))),
+    }
+  }
+
+  /// A variable, label, or property name
+  fn name(&mut self) -> Result<String> {
+    match self.peek() {
+      Some(Token::Ident(name)) | Some(Token::Quoted(name)) => {
+        let name = name.clone();
+        self.pos += 1;
+        Ok(name)
+      }
+      _ => Err(self.error("Expected a name")),
+    }
+  }
+
+  fn is_name(&self) -> bool {
+    matches!(self.peek(), Some(Token::Ident(_)) | Some(Token::Quoted(_)))
+  }
+
+  fn integer(&mut self) -> Result<usize> {
+    match self.peek() {
+      Some(Token::Int(value)) if *value >= 0 => {
+        let value = *value as usize;
+        self.pos += 1;
+        Ok(value)
+      }
+      _ => Err(self.error("Expected a positive integer")),
+    }
+  }
+
+  // -------------------------   Clauses   -------------------------------
+
+  fn query(&mut self) -> Result<Query> {
+    let mut clauses = Vec::new();
+
+    while self.peek().is_some() && !self.is_symbol(";") {
+      let clause = self.clause()?;
+      let is_return = matches!(clause, Clause::Return(_));
+      clauses.push(clause);
+
+      if is_return {
+        break;
+      }
+    }
+
+    self.eat_symbol(";");
+    if self.peek().is_some() {
+      return Err(self.error("Unexpected input after the end of the query"));
+    }
+    if clauses.is_empty() {
+      return Err(self.error("The query is empty"));
+    }
+    Ok(Query { clauses })
+  }
+
+  fn clause(&mut self) -> Result<Clause> {
+    if self.eat_keyword("MATCH") {
+      self.match_clause(false)
+    } else if self.eat_keyword("OPTIONAL") {
+      self.expect_keyword("MATCH")?;
+      self.match_clause(true)
+    } else if self.eat_keyword("UNWIND") {
+      let list = self.expr()?;
+      self.expect_keyword("AS")?;
+      Ok(Clause::Unwind {
+        list,
+        alias: self.name()?,
+      })
+    } else if self.eat_keyword("CREATE") {
+      Ok(Clause::Create(self.patterns()?))
+    } else if self.eat_keyword("MERGE") {
+      self.merge_clause()
+    } else if self.eat_keyword("SET") {
+      Ok(Clause::Set(self.set_items()?))
+    } else if self.eat_keyword("DETACH") {
+      self.expect_keyword("DELETE")?;
+      self.delete_clause(true)
+    } else if self.eat_keyword("DELETE") {
+      self.delete_clause(false)
+    } else if self.eat_keyword("WITH") {
+      let mut projection = self.projection()?;
+      if self.eat_keyword("WHERE") {
+        projection.filter = Some(self.expr()?);
+      }
+      Ok(Clause::With(projection))
+    } else if self.eat_keyword("RETURN") {
+      Ok(Clause::Return(self.projection()?))
+    } else {
+      Err(self.error(
+        "Expected one of MATCH, OPTIONAL MATCH, UNWIND, CREATE, MERGE, SET, DELETE, WITH or RETURN",
+      ))
+    }
+  }
+
+  fn match_clause(&mut self, optional: bool) -> Result<Clause> {
+    let patterns = self.patterns()?;
+    let filter = match self.eat_keyword("WHERE") {
+      true => Some(self.expr()?),
+      false => None,
+    };
+    Ok(Clause::Match {
+      optional,
+      patterns,
+      filter,
+    })
+  }
+
+  fn merge_clause(&mut self) -> Result<Clause> {
+    let pattern = self.pattern()?;
+    let mut on_create = Vec::new();
+    let mut on_match = Vec::new();
+
+    while self.eat_keyword("ON") {
+      let target = match (self.eat_keyword("CREATE"), self.eat_keyword("MATCH")) {
+        (true, _) => &mut on_create,
+        (_, true) => &mut on_match,
+        _ => return Err(self.error("Expected CREATE or MATCH after ON")),
+      };
+      self.expect_keyword("SET")?;
+      target.extend(self.set_items()?);
+    }
+
+    Ok(Clause::Merge {
+      pattern,
+      on_create,
+      on_match,
+    })
+  }
+
+  fn delete_clause(&mut self, detach: bool) -> Result<Clause> {
+    let mut targets = vec![self.expr()?];
+    while self.eat_symbol(",") {
+      targets.push(self.expr()?);
+    }
+    Ok(Clause::Delete { detach, targets })
+  }
+
+  fn set_items(&mut self) -> Result<Vec<SetItem>> {
+    let mut items = vec![self.set_item()?];
+    while self.eat_symbol(",") {
+      items.push(self.set_item()?);
+    }
+    Ok(items)
+  }
+
+  fn set_item(&mut self) -> Result<SetItem> {
+    let var = self.name()?;
+
+    if self.eat_symbol(".") {
+      let key = self.name()?;
+      self.expect_symbol("=")?;
+      Ok(SetItem::Property {
+        var,
+        key,
+        value: self.expr()?,
+      })
+    } else if self.eat_symbol("=") {
+      Ok(SetItem::Replace {
+        var,
+        value: self.expr()?,
+      })
+    } else if self.eat_symbol("+=") {
+      Ok(SetItem::Merge {
+        var,
+        value: self.expr()?,
+      })
+    } else if self.is_symbol(":") {
+      Ok(SetItem::Labels {
+        var,
+        labels: self.labels()?,
+      })
+    } else {
+      Err(self.error("Expected '.', '=', '+=' or ':' in SET"))
+    }
+  }
+
+  fn projection(&mut self) -> Result<Projection> {
+    let mut projection = Projection {
+      distinct: self.eat_keyword("DISTINCT"),
+      ..Projection::default()
+    };
+
+    match self.eat_symbol("*") {
+      true => projection.star = true,
+      false => loop {
+        let start = self.pos;
+        let expr = self.expr()?;
+        let name = match self.eat_keyword("AS") {
+          true => self.name()?,
+          false => self.text_since(start),
+        };
+        projection.items.push(ProjectionItem { expr, name });
+
+        if !self.eat_symbol(",") {
+          break;
+        }
+      },
+    }
+
+    if self.eat_keyword("ORDER") {
+      self.expect_keyword("BY")?;
+      loop {
+        let start = self.pos;
+        let expr = self.expr()?;
+        let name = self.text_since(start);
+        let descending = self.eat_keyword("DESC") || self.eat_keyword("DESCENDING");
+        if !descending {
+          let _ = self.eat_keyword("ASC") || self.eat_keyword("ASCENDING");
+        }
+        projection.order.push(SortItem {
+          expr,
+          name,
+          descending,
+        });
+
+        if !self.eat_symbol(",") {
+          break;
+        }
+      }
+    }
+
+    if self.eat_keyword("SKIP") {
+      projection.skip = Some(self.expr()?);
+    }
+    if self.eat_keyword("LIMIT") {
+      projection.limit = Some(self.expr()?);
+    }
+    Ok(projection)
+  }
+
+  // -------------------------   Patterns   ------------------------------
+
+  fn patterns(&mut self) -> Result<Vec<Pattern>> {
+    let mut patterns = vec![self.pattern()?];
+    while self.eat_symbol(",") {
+      patterns.push(self.pattern()?);
+    }
+    Ok(patterns)
+  }
+
+  fn pattern(&mut self) -> Result<Pattern> {
+    let start = self.node_pattern()?;
+    let mut steps = Vec::new();
+    while self.is_symbol("-") || self.is_symbol("<") {
+      let rel = self.rel_pattern()?;
+      steps.push((rel, self.node_pattern()?));
+    }
+    Ok(Pattern { start, steps })
+  }
+
+  fn labels(&mut self) -> Result<Vec<String>> {
+    let mut labels = Vec::new();
+    while self.eat_symbol(":") {
+      labels.push(self.name()?);
+    }
+    Ok(labels)
+  }
+
+  fn node_pattern(&mut self) -> Result<NodePattern> {
+    self.expect_symbol("(")?;
+    let var = match self.is_name() {
+      true => Some(self.name()?),
+      false => None,
+    };
+    let labels = self.labels()?;
+    let properties = match self.is_symbol("{") {
+      true => self.map()?,
+      false => Vec::new(),
+    };
+    self.expect_symbol(")")?;
+
+    Ok(NodePattern {
+      var,
+      labels,
+      properties,
+    })
+  }
+
+  fn rel_pattern(&mut self) -> Result<RelPattern> {
+    let incoming = self.eat_symbol("<");
+    self.expect_symbol("-")?;
+
+    let mut rel = RelPattern {
+      var: None,
+      types: Vec::new(),
+      direction: Direction::Both,
+      properties: Vec::new(),
+      length: None,
+    };
+
+    if self.eat_symbol("[") {
+      if self.is_name() {
+        rel.var = Some(self.name()?);
+      }
+      if self.eat_symbol(":") {
+        rel.types.push(self.name()?);
+        while self.eat_symbol("|") {
+          self.eat_symbol(":");
+          rel.types.push(self.name()?);
+        }
+      }
+      if self.eat_symbol("*") {
+        let min = match self.peek() {
+          Some(Token::Int(_)) => Some(self.integer()?),
+          _ => None,
+        };
+        rel.length = match self.eat_symbol("..") {
+          true => {
+            let max = match self.peek() {
+              Some(Token::Int(_)) => Some(self.integer()?),
+              _ => None,
+            };
+            Some((min.unwrap_or(1), max))
+          }
+          // A single number is an exact length
+          false => Some((min.unwrap_or(1), min)),
+        };
+      }
+      if self.is_symbol("{") {
+        rel.properties = self.map()?;
+      }
+      self.expect_symbol("]")?;
+    }
+
+    self.expect_symbol("-")?;
+    let outgoing = self.eat_symbol(">");
+
+    rel.direction = match (incoming, outgoing) {
+      (true, true) => return Err(self.error("A relationship cannot point both ways")),
+      (true, false) => Direction::Incoming,
+      (false, true) => Direction::Outgoing,
+      (false, false) => Direction::Both,
+    };
+    Ok(rel)
+  }
+
+  /// A map literal: `{key: value, ...}`
+  fn map(&mut self) -> Result<Vec<(String, Expr)>> {
+    self.expect_symbol("{")?;
+    let mut entries = Vec::new();
+    if !self.eat_symbol("}") {
+      loop {
+        let key = self.name()?;
+        self.expect_symbol(":")?;
+        entries.push((key, self.expr()?));
+
+        if !self.eat_symbol(",") {
+          break;
+        }
+      }
+      self.expect_symbol("}")?;
+    }
+    Ok(entries)
+  }
+
+  // ------------------------   Expressions   ----------------------------
+
+  fn expr(&mut self) -> Result<Expr> {
+    self.or()
+  }
+
+  fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+    Expr::Binary(op, Box::new(left), Box::new(right))
+  }
+
+  fn or(&mut self) -> Result<Expr> {
+    let mut expr = self.xor()?;
+    while self.eat_keyword("OR") {
+      expr = Parser::binary(BinaryOp::Or, expr, self.xor()?);
+    }
+    Ok(expr)
+  }
+
+  fn xor(&mut self) -> Result<Expr> {
+    let mut expr = self.and()?;
+    while self.eat_keyword("XOR") {
+      expr = Parser::binary(BinaryOp::Xor, expr, self.and()?);
+    }
+    Ok(expr)
+  }
+
+  fn and(&mut self) -> Result<Expr> {
+    let mut expr = self.not()?;
+    while self.eat_keyword("AND") {
+      expr = Parser::binary(BinaryOp::And, expr, self.not()?);
+    }
+    Ok(expr)
+  }
+
+  fn not(&mut self) -> Result<Expr> {
+    match self.eat_keyword("NOT") {
+      true => Ok(Expr::Not(Box::new(self.not()?))),
+      false => self.comparison(),
+    }
+  }
+
+  fn comparison(&mut self) -> Result<Expr> {
+    let mut expr = self.additive()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol("=")) => BinaryOp::Eq,
+        Some(Token::Symbol("<>")) => BinaryOp::Ne,
+        Some(Token::Symbol("<")) => BinaryOp::Lt,
+        Some(Token::Symbol("<=")) => BinaryOp::Le,
+        Some(Token::Symbol(">")) => BinaryOp::Gt,
+        Some(Token::Symbol(">=")) => BinaryOp::Ge,
+        _ if self.is_keyword("IN") => BinaryOp::In,
+        _ if self.is_keyword("CONTAINS") => BinaryOp::Contains,
+        _ if self.is_keyword("STARTS") => {
+          self.pos += 1;
+          if !self.is_keyword("WITH") {
+            return Err(self.error("Expected WITH"));
+          }
+          BinaryOp::StartsWith
+        }
+        _ if self.is_keyword("ENDS") => {
+          self.pos += 1;
+          if !self.is_keyword("WITH") {
+            return Err(self.error("Expected WITH"));
+          }
+          BinaryOp::EndsWith
+        }
+        _ if self.is_keyword("IS") => {
+          self.pos += 1;
+          let negated = self.eat_keyword("NOT");
+          self.expect_keyword("NULL")?;
+          expr = Expr::IsNull {
+            expr: Box::new(expr),
+            negated,
+          };
+          continue;
+        }
+        _ => return Ok(expr),
+      };
+      self.pos += 1;
+      expr = Parser::binary(op, expr, self.additive()?);
+    }
+  }
+
+  fn additive(&mut self) -> Result<Expr> {
+    let mut expr = self.multiplicative()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol("+")) => BinaryOp::Add,
+        Some(Token::Symbol("-")) => BinaryOp::Sub,
+        _ => return Ok(expr),
+      };
+      self.pos += 1;
+      expr = Parser::binary(op, expr, self.multiplicative()?);
+    }
+  }
+
+  fn multiplicative(&mut self) -> Result<Expr> {
+    let mut expr = self.unary()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol("*")) => BinaryOp::Mul,
+        Some(Token::Symbol("/")) => BinaryOp::Div,
+        Some(Token::Symbol("%")) => BinaryOp::Mod,
+        _ => return Ok(expr),
+      };
+      self.pos += 1;
+      expr = Parser::binary(op, expr, self.unary()?);
+    }
+  }
+
+  fn unary(&mut self) -> Result<Expr> {
+    if self.eat_symbol("-") {
+      Ok(Expr::Negate(Box::new(self.unary()?)))
+    } else if self.eat_symbol("+") {
+      self.unary()
+    } else {
+      self.postfix()
+    }
+  }
+
+  fn postfix(&mut self) -> Result<Expr> {
+    let mut expr = self.primary()?;
+    loop {
+      if self.eat_symbol(".") {
+        expr = Expr::Property(Box::new(expr), self.name()?);
+      } else if self.eat_symbol("[") {
+        let index = self.expr()?;
+        self.expect_symbol("]")?;
+        expr = Expr::Index(Box::new(expr), Box::new(index));
+      } else if self.is_symbol(":") {
+        expr = Expr::HasLabels(Box::new(expr), self.labels()?);
+      } else {
+        return Ok(expr);
+      }
+    }
+  }
+
+  fn primary(&mut self) -> Result<Expr> {
+    let token = match self.peek() {
+      Some(token) => token.clone(),
+      None => return Err(self.error("Expected an expression")),
+    };
+
+    match token {
+      Token::Int(value) => {
+        self.pos += 1;
+        Ok(Expr::Literal(Value::Integer(value)))
+      }
+      Token::Float(value) => {
+        self.pos += 1;
+        Ok(Expr::Literal(Value::Float(value)))
+      }
+      Token::Str(value) => {
+        self.pos += 1;
+        Ok(Expr::Literal(Value::String(value)))
+      }
+      Token::Param(name) => {
+        self.pos += 1;
+        Ok(Expr::Param(name))
+      }
+      Token::Quoted(name) => {
+        self.pos += 1;
+        Ok(Expr::Variable(name))
+      }
+      Token::Symbol("(") => {
+        self.pos += 1;
+        let expr = self.expr()?;
+        self.expect_symbol(")")?;
+        Ok(expr)
+      }
+      Token::Symbol("[") => {
+        self.pos += 1;
+        let mut items = Vec::new();
+        if !self.eat_symbol("]") {
+          loop {
+            items.push(self.expr()?);
+            if !self.eat_symbol(",") {
+              break;
+            }
+          }
+          self.expect_symbol("]")?;
+        }
+        Ok(Expr::List(items))
+      }
+      Token::Symbol("{") => Ok(Expr::Map(self.map()?)),
+      Token::Ident(word) => {
+        if word.eq_ignore_ascii_case("true") {
+          self.pos += 1;
+          Ok(Expr::Literal(Value::Bool(true)))
+        } else if word.eq_ignore_ascii_case("false") {
+          self.pos += 1;
+          Ok(Expr::Literal(Value::Bool(false)))
+        } else if word.eq_ignore_ascii_case("null") {
+          self.pos += 1;
+          Ok(Expr::Literal(Value::Null))
+        } else if self.peek_at(1) == Some(&Token::Symbol("(")) {
+          self.function(word)
+        } else {
+          self.pos += 1;
+          Ok(Expr::Variable(word))
+        }
+      }
+      _ => Err(self.error("Expected an expression")),
+    }
+  }
+
+  fn function(&mut self, word: String) -> Result<Expr> {
+    // Skip the name and the opening bracket
+    self.advance();
+    self.advance();
+    let name = word.to_lowercase();
+
+    if name == "count" && self.eat_symbol("*") {
+      self.expect_symbol(")")?;
+      return Ok(Expr::CountStar);
+    }
+
+    let distinct = self.eat_keyword("DISTINCT");
+    let mut args = Vec::new();
+    if !self.eat_symbol(")") {
+      loop {
+        args.push(self.expr()?);
+        if !self.eat_symbol(",") {
+          break;
+        }
+      }
+      self.expect_symbol(")")?;
+    }
+
+    Ok(Expr::Function {
+      name,
+      distinct,
+      args,
+    })
+  }
+}
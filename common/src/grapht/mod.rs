@@ -1,11 +1,118 @@
 //! Tools for creating an in-memory Graph Db
 
+use uuid::Uuid;
+
+// The values stored in the graph
+pub mod value;
+pub use value::{Edge, Node, Properties, Row, Value};
+
+// The in-memory graph itself
+pub mod store;
+pub use store::{Direction, Graph};
+
+// Saving the graph to disk
+pub mod snapshot;
+
+// Querying the graph with Cypher
+pub mod cypher;
+
 pub trait GraphtPayload {}
 
-pub trait GraphtNode {}
+/// A value that can be stored as a node in the graph
+pub trait GraphtNode {
+  /// The label the node is stored under. This must be a valid identifier
+  fn label(&self) -> &'static str;
+
+  /// The unique identifier of the node, which is also stored in its `guid` property
+  fn guid(&self) -> Uuid;
+
+  /// The scalar values to be stored on the node
+  fn properties(&self) -> Properties;
+
+  /// A lightweight pointer to this node, used for linking it to others
+  fn node_ref(&self) -> NodeRef {
+    NodeRef {
+      label: self.label(),
+      guid: self.guid(),
+    }
+  }
+}
+
+/// A directed link between two nodes in the graph
+pub trait GraphtEdge {
+  /// The type of relationship. This must be a valid identifier
+  fn label(&self) -> &'static str;
+
+  /// The node the edge starts at
+  fn source(&self) -> NodeRef;
+
+  /// The node the edge points to
+  fn target(&self) -> NodeRef;
+
+  /// The values to be stored on the edge
+  fn properties(&self) -> Properties {
+    Properties::new()
+  }
+}
+
+/// Identifies a single node by its label and guid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeRef {
+  pub label: &'static str,
+  pub guid: Uuid,
+}
+
+/// A generic edge for relationships that do not carry any extra data
+#[derive(Clone, Debug)]
+pub struct Link {
+  pub label: &'static str,
+  pub source: NodeRef,
+  pub target: NodeRef,
+  pub properties: Properties,
+}
+
+impl Link {
+  pub fn new(label: &'static str, source: NodeRef, target: NodeRef) -> Link {
+    Link {
+      label,
+      source,
+      target,
+      properties: Properties::new(),
+    }
+  }
+}
 
-pub trait GraphtEdge {}
+impl GraphtEdge for Link {
+  fn label(&self) -> &'static str {
+    self.label
+  }
+
+  fn source(&self) -> NodeRef {
+    self.source
+  }
+
+  fn target(&self) -> NodeRef {
+    self.target
+  }
+
+  fn properties(&self) -> Properties {
+    self.properties.clone()
+  }
+}
+
+/// Test whether a label or property key can be safely written into a query without quoting
+///
+/// Labels cannot be passed as query parameters, so they must be checked before being used.
+pub fn is_identifier(name: &str) -> bool {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+      chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+    _ => false,
+  }
+}
 
 pub mod prelude {
-  pub use super::{GraphtEdge, GraphtNode, GraphtPayload};
+  pub use super::{GraphtEdge, GraphtNode, GraphtPayload, Link, NodeRef, Properties, Row, Value};
 }
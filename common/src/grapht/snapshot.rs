@@ -0,0 +1,338 @@
+//! Save and load a whole graph to a single local file
+//!
+//! Snapshots are written as JSON lines so they can be inspected and diffed by hand:
+//!
+//! ```text
+//! {"type":"Header","format":"grapht","version":1}
+//! {"type":"Node","id":0,"labels":["Organization"],"properties":{...}}
+//! {"type":"Edge","id":2,"label":"PARENT_OF","source":0,"target":1,"properties":{...}}
+//! {"type":"Footer","nodes":2,"edges":1,"checksum":"1c291ca3"}
+//! ```
+//!
+//! The footer holds a CRC32 of every byte before it, so a truncated or edited file is rejected
+//! instead of silently loading a partial graph. Files are written to a temporary sibling and renamed
+//! into place, so a crash while saving leaves the previous snapshot intact. JSON has no NaN or
+//! infinity, so a graph holding one is not saved rather than written in a way it can't be read back.
+
+use crate::local::*;
+
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeSet, HashMap},
+  fs,
+  io::Write,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::{
+  store::{EdgeId, NodeId},
+  Graph, Properties, Value,
+};
+
+/// Identifies a file as a grapht snapshot
+const FORMAT: &str = "grapht";
+
+/// The current layout of the file. Bump this when the records change.
+pub const VERSION: u32 = 1;
+
+/// A single line of the snapshot
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum Record {
+  Header {
+    format: String,
+    version: u32,
+  },
+  Node {
+    id: NodeId,
+    labels: BTreeSet<String>,
+    properties: Properties,
+  },
+  Edge {
+    id: EdgeId,
+    label: String,
+    source: NodeId,
+    target: NodeId,
+    properties: Properties,
+  },
+  Footer {
+    nodes: usize,
+    edges: usize,
+    checksum: String,
+  },
+}
+
+#[track_caller]
+fn snapshot_error(path: &Path, ctx: &str) -> AllWhat<WranglerErrorKind> {
+  let err: AllWhat<WranglerErrorKind> = SerializationError.into();
+  err.set_context(&format!("Invalid snapshot '{}': {}", path.display(), ctx))
+}
+
+fn checksum(bytes: &[u8]) -> String {
+  format!("{:08x}", crc32fast::hash(bytes))
+}
+
+fn write_record(buffer: &mut Vec<u8>, record: &Record) -> Result<()> {
+  serde_json::to_writer(&mut *buffer, record).map_err(|err| {
+    let result: AllWhat<WranglerErrorKind> = SerializationError.into();
+    result
+      .set_context("Could not write a record to the snapshot")
+      .set_dev_context(&format!("From <serde_json>:\n{:#?}", err))
+  })?;
+  buffer.push(b'\n');
+  Ok(())
+}
+
+/// Counts the saves made by this process, so two threads saving at once use different files
+static SAVES: AtomicUsize = AtomicUsize::new(0);
+
+/// The temporary file a snapshot is written to before replacing the original. It is unique to this
+/// save, so concurrent saves of the same snapshot don't write over each other.
+fn temp_path(path: &Path) -> PathBuf {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(format!(
+    ".{}.{}.tmp",
+    std::process::id(),
+    SAVES.fetch_add(1, Ordering::Relaxed)
+  ));
+  path.with_file_name(name)
+}
+
+/// The name of the first property holding a NaN or infinite float, looking inside lists and maps
+fn non_finite(properties: &Properties) -> Option<&str> {
+  fn finite(value: &Value) -> bool {
+    match value {
+      Value::Float(value) => value.is_finite(),
+      Value::List(values) => values.iter().all(finite),
+      Value::Map(properties) => properties.values().all(finite),
+      Value::Node(node) => node.properties.values().all(finite),
+      Value::Edge(edge) => edge.properties.values().all(finite),
+      _ => true,
+    }
+  }
+  properties
+    .iter()
+    .find(|(_, value)| !finite(value))
+    .map(|(name, _)| name.as_str())
+}
+
+#[track_caller]
+fn not_finite(what: &str, id: u64, property: &str) -> AllWhat<WranglerErrorKind> {
+  let err: AllWhat<WranglerErrorKind> = SerializationError.into();
+  err.set_context(&format!(
+    "Could not save the graph: {} {} has a NaN or infinite value in '{}'",
+    what, id, property
+  ))
+}
+
+impl Graph {
+  /// Encode the whole graph into the snapshot format
+  pub fn to_snapshot(&self) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    write_record(
+      &mut buffer,
+      &Record::Header {
+        format: FORMAT.to_string(),
+        version: VERSION,
+      },
+    )?;
+
+    for (id, node) in self.nodes() {
+      if let Some(property) = non_finite(&node.properties) {
+        return Err(not_finite("node", id, property));
+      }
+      write_record(
+        &mut buffer,
+        &Record::Node {
+          id,
+          labels: node.labels.clone(),
+          properties: node.properties.clone(),
+        },
+      )?;
+    }
+
+    for (id, edge) in self.edges() {
+      if let Some(property) = non_finite(&edge.properties) {
+        return Err(not_finite("edge", id, property));
+      }
+      write_record(
+        &mut buffer,
+        &Record::Edge {
+          id,
+          label: edge.label.clone(),
+          source: edge.source,
+          target: edge.target,
+          properties: edge.properties.clone(),
+        },
+      )?;
+    }
+
+    let footer = Record::Footer {
+      nodes: self.node_count(),
+      edges: self.edge_count(),
+      checksum: checksum(&buffer),
+    };
+    write_record(&mut buffer, &footer)?;
+    Ok(buffer)
+  }
+
+  /// Rebuild a graph from a snapshot, verifying the version and checksum
+  ///
+  /// The path is only used for the error messages.
+  pub fn from_snapshot(bytes: &[u8], path: &Path) -> Result<Graph> {
+    // The footer is the last line, and the checksum covers everything before it
+    let body = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let split = body
+      .iter()
+      .rposition(|byte| *byte == b'\n')
+      .map(|pos| pos + 1)
+      .ok_or_else(|| snapshot_error(path, "the file is empty or truncated"))?;
+    let (content, footer) = body.split_at(split);
+
+    let parse = |line: &[u8], number: usize| -> Result<Record> {
+      serde_json::from_slice(line).map_err(|err| {
+        snapshot_error(path, &format!("line {} could not be read", number))
+          .set_dev_context(&format!("From <serde_json>:\n{:#?}", err))
+      })
+    };
+
+    let (nodes, edges) = match parse(footer, content.split(|b| *b == b'\n').count())? {
+      Record::Footer {
+        nodes,
+        edges,
+        checksum: expected,
+      } => {
+        let actual = checksum(content);
+        if actual != expected {
+          return Err(snapshot_error(
+            path,
+            &format!(
+              "the checksum does not match (expected {}, found {}). The file may be corrupt",
+              expected, actual
+            ),
+          ));
+        }
+        (nodes, edges)
+      }
+      _ => return Err(snapshot_error(path, "the file is truncated")),
+    };
+
+    let mut graph = Graph::new();
+    let mut ids: HashMap<NodeId, NodeId> = HashMap::new();
+    let mut lines = content
+      .split(|byte| *byte == b'\n')
+      .filter(|line| !line.is_empty())
+      .enumerate();
+
+    match lines.next() {
+      Some((_, line)) => match parse(line, 1)? {
+        Record::Header { format, version } if format == FORMAT => {
+          if version != VERSION {
+            return Err(snapshot_error(
+              path,
+              &format!(
+                "version {} is not supported (expected {})",
+                version, VERSION
+              ),
+            ));
+          }
+        }
+        _ => return Err(snapshot_error(path, "the file is not a grapht snapshot")),
+      },
+      None => return Err(snapshot_error(path, "the header is missing")),
+    }
+
+    for (index, line) in lines {
+      let number = index + 1;
+      match parse(line, number)? {
+        Record::Node {
+          id,
+          labels,
+          properties,
+        } => {
+          let new_id = graph
+            .add_node(labels.into_iter().collect(), properties)
+            .set_context(&format!("Line {} of '{}'", number, path.display()))?;
+          ids.insert(id, new_id);
+        }
+        Record::Edge {
+          label,
+          source,
+          target,
+          properties,
+          ..
+        } => {
+          let lookup = |id: NodeId| {
+            ids.get(&id).copied().ok_or_else(|| {
+              snapshot_error(
+                path,
+                &format!("line {} links to node {} which was not defined", number, id),
+              )
+            })
+          };
+          graph.add_edge(&label, lookup(source)?, lookup(target)?, properties)?;
+        }
+        _ => {
+          return Err(snapshot_error(
+            path,
+            &format!("line {} is out of place", number),
+          ))
+        }
+      }
+    }
+
+    if graph.node_count() != nodes || graph.edge_count() != edges {
+      return Err(snapshot_error(
+        path,
+        &format!(
+          "expected {} nodes and {} edges but found {} and {}",
+          nodes,
+          edges,
+          graph.node_count(),
+          graph.edge_count()
+        ),
+      ));
+    }
+
+    Ok(graph)
+  }
+
+  /// Write the graph to the given file, replacing it atomically
+  pub fn save(&self, path: &Path) -> Result<()> {
+    let bytes = self.to_snapshot()?;
+    let temp = temp_path(path);
+
+    let written = (|| -> std::io::Result<()> {
+      if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+      {
+        fs::create_dir_all(parent)?;
+      }
+      let mut file = fs::File::create(&temp)?;
+      file.write_all(&bytes)?;
+      file.sync_all()?;
+      fs::rename(&temp, path)
+    })();
+
+    if let Err(err) = written {
+      let _ = fs::remove_file(&temp);
+      let result: AllWhat<WranglerErrorKind> = err.into();
+      return Err(result.set_context(&format!("Could not save the graph to '{}'", path.display())));
+    }
+    Ok(())
+  }
+
+  /// Read a graph previously written by [Graph::save]
+  pub fn load(path: &Path) -> Result<Graph> {
+    let bytes = fs::read(path).map_err(|err| {
+      let result: AllWhat<WranglerErrorKind> = err.into();
+      result.set_context(&format!(
+        "Could not read the graph from '{}'",
+        path.display()
+      ))
+    })?;
+    Graph::from_snapshot(&bytes, path)
+  }
+}
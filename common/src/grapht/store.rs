@@ -0,0 +1,566 @@
+//! An in-memory property graph
+//!
+//! Nodes and edges are stored by an internal sequential id, so iteration follows insertion order.
+//! Nodes created from the model also carry a `guid` property, which is indexed for quick lookup.
+
+use crate::local::*;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use uuid::Uuid;
+
+use super::{GraphtEdge, GraphtNode, NodeRef, Properties, Value};
+
+/// The internal identifier of a node
+pub type NodeId = u64;
+
+/// The internal identifier of an edge
+pub type EdgeId = u64;
+
+/// Which way to follow edges when walking the graph
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+  Outgoing,
+  Incoming,
+  Both,
+}
+
+/// A node as it is stored in the graph
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeData {
+  pub labels: BTreeSet<String>,
+  pub properties: Properties,
+}
+
+/// A directed edge as it is stored in the graph
+#[derive(Clone, Debug, PartialEq)]
+pub struct EdgeData {
+  pub label: String,
+  pub source: NodeId,
+  pub target: NodeId,
+  pub properties: Properties,
+}
+
+impl NodeData {
+  /// The node's guid property, if it has one
+  pub fn guid(&self) -> Option<Uuid> {
+    self.properties.get("guid").and_then(|guid| guid.as_uuid())
+  }
+
+  /// Create a snapshot suitable for returning from a query
+  pub fn snapshot(&self) -> super::Node {
+    super::Node {
+      labels: self.labels.iter().cloned().collect(),
+      properties: self.properties.clone(),
+    }
+  }
+}
+
+impl EdgeData {
+  /// Create a snapshot suitable for returning from a query
+  pub fn snapshot(&self) -> super::Edge {
+    super::Edge {
+      label: self.label.clone(),
+      properties: self.properties.clone(),
+    }
+  }
+
+  /// The node at the other end of the edge from the given one
+  pub fn other(&self, node: NodeId) -> NodeId {
+    match self.source == node {
+      true => self.target,
+      false => self.source,
+    }
+  }
+}
+
+/// A change made to the graph, holding what is needed to reverse it
+#[derive(Clone, Debug)]
+enum Undo {
+  AddNode(NodeId),
+  /// The node as it was before a property or label changed
+  ChangeNode(NodeId, NodeData),
+  RemoveNode(NodeId, NodeData),
+  AddEdge(EdgeId),
+  /// The properties of the edge before they changed
+  ChangeEdge(EdgeId, Properties),
+  RemoveEdge(EdgeId, EdgeData),
+}
+
+/// The changes made since [Graph::begin]
+#[derive(Clone, Debug)]
+struct Journal {
+  next_id: u64,
+  changes: Vec<Undo>,
+}
+
+#[track_caller]
+fn graph_error(kind: WranglerErrorKind, ctx: &str) -> AllWhat<WranglerErrorKind> {
+  let err: AllWhat<WranglerErrorKind> = kind.into();
+  err.set_context(ctx)
+}
+
+/// A property graph held entirely in memory
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+  nodes: BTreeMap<NodeId, NodeData>,
+  edges: BTreeMap<EdgeId, EdgeData>,
+
+  /// The next id to hand out. Nodes and edges share the sequence.
+  next_id: u64,
+
+  // Indexes for the common lookups
+  guids: HashMap<Uuid, NodeId>,
+  labels: HashMap<String, BTreeSet<NodeId>>,
+  outgoing: HashMap<NodeId, BTreeSet<EdgeId>>,
+  incoming: HashMap<NodeId, BTreeSet<EdgeId>>,
+
+  /// Recorded while a query runs, so a failed query can be undone
+  journal: Option<Journal>,
+}
+
+impl Graph {
+  pub fn new() -> Graph {
+    Graph::default()
+  }
+
+  pub fn node_count(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn edge_count(&self) -> usize {
+    self.edges.len()
+  }
+
+  fn take_id(&mut self) -> u64 {
+    let id = self.next_id;
+    self.next_id += 1;
+    id
+  }
+
+  fn record(&mut self, change: Undo) {
+    if let Some(journal) = self.journal.as_mut() {
+      journal.changes.push(change);
+    }
+  }
+
+  // ---------------------------   Nodes   -------------------------------
+
+  /// Add a node with the given labels and properties, returning its id
+  ///
+  /// Fails if the properties contain a guid already used by another node.
+  pub fn add_node(&mut self, labels: Vec<String>, properties: Properties) -> Result<NodeId> {
+    let node = NodeData {
+      labels: labels.into_iter().collect(),
+      properties,
+    };
+
+    if let Some(guid) = node.guid() {
+      if self.guids.contains_key(&guid) {
+        return Err(graph_error(
+          Conflict,
+          &format!("A node with guid {} already exists", guid),
+        ));
+      }
+    }
+
+    let id = self.take_id();
+    self.index_node(id, &node);
+    self.nodes.insert(id, node);
+    self.record(Undo::AddNode(id));
+    Ok(id)
+  }
+
+  /// Add a node from the model
+  pub fn insert(&mut self, node: &dyn GraphtNode) -> Result<NodeId> {
+    let mut properties = node.properties();
+    properties.insert("guid".to_string(), node.guid().into());
+    self.add_node(vec![node.label().to_string()], properties)
+  }
+
+  fn index_node(&mut self, id: NodeId, node: &NodeData) {
+    if let Some(guid) = node.guid() {
+      self.guids.insert(guid, id);
+    }
+    for label in node.labels.iter() {
+      self.labels.entry(label.clone()).or_default().insert(id);
+    }
+  }
+
+  fn unindex_node(&mut self, id: NodeId, node: &NodeData) {
+    if let Some(guid) = node.guid() {
+      self.guids.remove(&guid);
+    }
+    for label in node.labels.iter() {
+      if let Some(ids) = self.labels.get_mut(label) {
+        ids.remove(&id);
+      }
+    }
+  }
+
+  pub fn node(&self, id: NodeId) -> Option<&NodeData> {
+    self.nodes.get(&id)
+  }
+
+  /// All of the nodes in insertion order
+  pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &NodeData)> {
+    self.nodes.iter().map(|(id, node)| (*id, node))
+  }
+
+  /// Look up a node by its guid property
+  pub fn find_guid(&self, guid: &Uuid) -> Option<NodeId> {
+    self.guids.get(guid).copied()
+  }
+
+  /// Look up a node by its label and guid
+  pub fn find_ref(&self, node: &NodeRef) -> Option<NodeId> {
+    self
+      .find_guid(&node.guid)
+      .filter(|id| self.nodes[id].labels.contains(node.label))
+  }
+
+  /// All the nodes with the given label
+  pub fn with_label(&self, label: &str) -> Vec<NodeId> {
+    self
+      .labels
+      .get(label)
+      .map(|ids| ids.iter().copied().collect())
+      .unwrap_or_default()
+  }
+
+  /// Find all the nodes with the label (if given) whose properties include every one of the given
+  /// properties
+  pub fn find_nodes(&self, label: Option<&str>, properties: &Properties) -> Vec<NodeId> {
+    // Use the guid index when possible, as it is the most common lookup
+    let candidates: Vec<NodeId> = match (properties.get("guid").and_then(Value::as_uuid), label) {
+      (Some(guid), _) => self.find_guid(&guid).into_iter().collect(),
+      (None, Some(label)) => self.with_label(label),
+      (None, None) => self.nodes.keys().copied().collect(),
+    };
+
+    candidates
+      .into_iter()
+      .filter(|id| {
+        let node = &self.nodes[id];
+        label
+          .map(|label| node.labels.contains(label))
+          .unwrap_or(true)
+          && properties
+            .iter()
+            .all(|(key, value)| node.properties.get(key) == Some(value))
+      })
+      .collect()
+  }
+
+  /// Set (or remove, when the value is Null) a single property on a node
+  pub fn set_property(&mut self, id: NodeId, key: &str, value: Value) -> Result<()> {
+    // Keep the guids unique
+    if let Some(other) = value.as_uuid().and_then(|guid| self.find_guid(&guid)) {
+      if key == "guid" && other != id {
+        return Err(graph_error(
+          Conflict,
+          &format!(
+            "Cannot set the guid of node {} as it is already used by node {}",
+            id, other
+          ),
+        ));
+      }
+    }
+
+    let mut node = self
+      .nodes
+      .remove(&id)
+      .ok_or_else(|| graph_error(NotFound, &format!("Node {} does not exist", id)))?;
+
+    self.unindex_node(id, &node);
+    if self.journal.is_some() {
+      self.record(Undo::ChangeNode(id, node.clone()));
+    }
+    match value {
+      Value::Null => node.properties.remove(key),
+      value => node.properties.insert(key.to_string(), value),
+    };
+    self.index_node(id, &node);
+    self.nodes.insert(id, node);
+    Ok(())
+  }
+
+  /// Add a label to a node
+  pub fn add_label(&mut self, id: NodeId, label: &str) -> Result<()> {
+    let node = self
+      .nodes
+      .get(&id)
+      .ok_or_else(|| graph_error(NotFound, &format!("Node {} does not exist", id)))?;
+    if self.journal.is_some() {
+      self.record(Undo::ChangeNode(id, node.clone()));
+    }
+
+    let node = self.nodes.get_mut(&id).unwrap();
+    node.labels.insert(label.to_string());
+    self.labels.entry(label.to_string()).or_default().insert(id);
+    Ok(())
+  }
+
+  /// Remove a node. Unless detach is set, this fails if the node still has edges.
+  pub fn remove_node(&mut self, id: NodeId, detach: bool) -> Result<NodeData> {
+    let edges = self.edges_of(id, None, Direction::Both);
+    if !edges.is_empty() {
+      match detach {
+        true => edges.into_iter().for_each(|edge| {
+          self.remove_edge(edge);
+        }),
+        false => {
+          return Err(graph_error(
+            Conflict,
+            &format!(
+              "Cannot delete node {} as it still has {} relationships",
+              id,
+              edges.len()
+            ),
+          ))
+        }
+      }
+    }
+
+    let node = self
+      .nodes
+      .remove(&id)
+      .ok_or_else(|| graph_error(NotFound, &format!("Node {} does not exist", id)))?;
+    self.unindex_node(id, &node);
+    self.outgoing.remove(&id);
+    self.incoming.remove(&id);
+    if self.journal.is_some() {
+      self.record(Undo::RemoveNode(id, node.clone()));
+    }
+    Ok(node)
+  }
+
+  // ---------------------------   Edges   -------------------------------
+
+  /// Add a directed edge between two existing nodes
+  pub fn add_edge(
+    &mut self,
+    label: &str,
+    source: NodeId,
+    target: NodeId,
+    properties: Properties,
+  ) -> Result<EdgeId> {
+    for id in [source, target] {
+      if !self.nodes.contains_key(&id) {
+        return Err(graph_error(
+          NotFound,
+          &format!("Cannot link to node {} as it does not exist", id),
+        ));
+      }
+    }
+
+    let id = self.take_id();
+    self.link_edge(
+      id,
+      EdgeData {
+        label: label.to_string(),
+        source,
+        target,
+        properties,
+      },
+    );
+    self.record(Undo::AddEdge(id));
+    Ok(id)
+  }
+
+  fn link_edge(&mut self, id: EdgeId, edge: EdgeData) {
+    self.outgoing.entry(edge.source).or_default().insert(id);
+    self.incoming.entry(edge.target).or_default().insert(id);
+    self.edges.insert(id, edge);
+  }
+
+  /// Link two model nodes, replacing the properties of the edge if it already exists
+  pub fn relate(&mut self, edge: &dyn GraphtEdge) -> Result<EdgeId> {
+    let find = |node: NodeRef| {
+      self.find_ref(&node).ok_or_else(|| {
+        graph_error(
+          NotFound,
+          &format!(
+            "Could not link {} {}: the node does not exist",
+            node.label, node.guid
+          ),
+        )
+      })
+    };
+    let source = find(edge.source())?;
+    let target = find(edge.target())?;
+
+    let existing = self
+      .edges_of(source, Some(edge.label()), Direction::Outgoing)
+      .into_iter()
+      .find(|id| self.edges[id].target == target);
+
+    match existing {
+      Some(id) => {
+        let old = std::mem::replace(
+          &mut self.edges.get_mut(&id).unwrap().properties,
+          edge.properties(),
+        );
+        self.record(Undo::ChangeEdge(id, old));
+        Ok(id)
+      }
+      None => self.add_edge(edge.label(), source, target, edge.properties()),
+    }
+  }
+
+  pub fn edge(&self, id: EdgeId) -> Option<&EdgeData> {
+    self.edges.get(&id)
+  }
+
+  /// All of the edges in insertion order
+  pub fn edges(&self) -> impl Iterator<Item = (EdgeId, &EdgeData)> {
+    self.edges.iter().map(|(id, edge)| (*id, edge))
+  }
+
+  /// Set (or remove, when the value is Null) a single property on an edge
+  pub fn set_edge_property(&mut self, id: EdgeId, key: &str, value: Value) -> Result<()> {
+    let edge = self
+      .edges
+      .get_mut(&id)
+      .ok_or_else(|| graph_error(NotFound, &format!("Edge {} does not exist", id)))?;
+
+    let old = match self.journal.is_some() {
+      true => Some(edge.properties.clone()),
+      false => None,
+    };
+    match value {
+      Value::Null => edge.properties.remove(key),
+      value => edge.properties.insert(key.to_string(), value),
+    };
+    if let Some(old) = old {
+      self.record(Undo::ChangeEdge(id, old));
+    }
+    Ok(())
+  }
+
+  pub fn remove_edge(&mut self, id: EdgeId) -> Option<EdgeData> {
+    let edge = self.edges.remove(&id)?;
+    if let Some(ids) = self.outgoing.get_mut(&edge.source) {
+      ids.remove(&id);
+    }
+    if let Some(ids) = self.incoming.get_mut(&edge.target) {
+      ids.remove(&id);
+    }
+    if self.journal.is_some() {
+      self.record(Undo::RemoveEdge(id, edge.clone()));
+    }
+    Some(edge)
+  }
+
+  // -------------------------   Rollback   ------------------------------
+
+  /// Start recording changes, so they can be undone by [Graph::rollback]
+  pub fn begin(&mut self) {
+    self.journal = Some(Journal {
+      next_id: self.next_id,
+      changes: Vec::new(),
+    });
+  }
+
+  /// Keep the changes made since [Graph::begin]
+  pub fn commit(&mut self) {
+    self.journal = None;
+  }
+
+  /// Undo the changes made since [Graph::begin], newest first
+  pub fn rollback(&mut self) {
+    let Some(journal) = self.journal.take() else {
+      return;
+    };
+
+    for change in journal.changes.into_iter().rev() {
+      match change {
+        Undo::AddNode(id) => {
+          if let Some(node) = self.nodes.remove(&id) {
+            self.unindex_node(id, &node);
+          }
+          self.outgoing.remove(&id);
+          self.incoming.remove(&id);
+        }
+        Undo::ChangeNode(id, old) | Undo::RemoveNode(id, old) => {
+          if let Some(node) = self.nodes.remove(&id) {
+            self.unindex_node(id, &node);
+          }
+          self.index_node(id, &old);
+          self.nodes.insert(id, old);
+        }
+        Undo::AddEdge(id) => {
+          self.remove_edge(id);
+        }
+        Undo::ChangeEdge(id, old) => {
+          if let Some(edge) = self.edges.get_mut(&id) {
+            edge.properties = old;
+          }
+        }
+        Undo::RemoveEdge(id, edge) => self.link_edge(id, edge),
+      }
+    }
+    self.next_id = journal.next_id;
+  }
+
+  // -------------------------   Traversal   -----------------------------
+
+  /// The edges attached to a node, optionally limited to a single label
+  pub fn edges_of(&self, node: NodeId, label: Option<&str>, direction: Direction) -> Vec<EdgeId> {
+    let empty = BTreeSet::new();
+    let outgoing = match direction {
+      Direction::Incoming => &empty,
+      _ => self.outgoing.get(&node).unwrap_or(&empty),
+    };
+    let incoming = match direction {
+      Direction::Outgoing => &empty,
+      _ => self.incoming.get(&node).unwrap_or(&empty),
+    };
+
+    // A self-referencing edge shows up in both sets but should only be returned once
+    outgoing
+      .union(incoming)
+      .copied()
+      .filter(|id| {
+        label
+          .map(|label| self.edges[id].label == label)
+          .unwrap_or(true)
+      })
+      .collect()
+  }
+
+  /// The nodes one step away from the given node
+  pub fn neighbors(&self, node: NodeId, label: Option<&str>, direction: Direction) -> Vec<NodeId> {
+    self
+      .edges_of(node, label, direction)
+      .into_iter()
+      .map(|id| self.edges[&id].other(node))
+      .collect()
+  }
+
+  /// Walk breadth first from the start node, following only the matching edges, and return every
+  /// node reached within the maximum depth. The start node is not included.
+  pub fn traverse(
+    &self,
+    start: NodeId,
+    label: Option<&str>,
+    direction: Direction,
+    max_depth: usize,
+  ) -> Vec<NodeId> {
+    let mut seen = BTreeSet::from([start]);
+    let mut found = Vec::new();
+    let mut queue = VecDeque::from([(start, 0)]);
+
+    while let Some((node, depth)) = queue.pop_front() {
+      if depth == max_depth {
+        continue;
+      }
+      for next in self.neighbors(node, label, direction) {
+        if seen.insert(next) {
+          found.push(next);
+          queue.push_back((next, depth + 1));
+        }
+      }
+    }
+    found
+  }
+}
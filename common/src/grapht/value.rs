@@ -0,0 +1,185 @@
+//! The data stored in a graph and returned from queries against it
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Named values attached to a node or edge
+pub type Properties = BTreeMap<String, Value>;
+
+/// A single result of a query, keyed by the names in the RETURN clause
+pub type Row = BTreeMap<String, Value>;
+
+/// A single value stored in the graph, passed as a query parameter, or returned in a row
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum Value {
+  Null,
+  Bool(bool),
+  Integer(i64),
+  Float(f64),
+  String(String),
+  List(Vec<Value>),
+  Map(Properties),
+  Node(Node),
+  Edge(Edge),
+}
+
+/// A snapshot of a node as returned from a query
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Node {
+  pub labels: Vec<String>,
+  pub properties: Properties,
+}
+
+/// A snapshot of an edge as returned from a query
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Edge {
+  pub label: String,
+  pub properties: Properties,
+}
+
+impl Value {
+  pub fn is_null(&self) -> bool {
+    matches!(self, Value::Null)
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Value::String(value) => Some(value),
+      _ => None,
+    }
+  }
+
+  pub fn as_i64(&self) -> Option<i64> {
+    match self {
+      Value::Integer(value) => Some(*value),
+      _ => None,
+    }
+  }
+
+  pub fn as_f64(&self) -> Option<f64> {
+    match self {
+      Value::Float(value) => Some(*value),
+      Value::Integer(value) => Some(*value as f64),
+      _ => None,
+    }
+  }
+
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      Value::Bool(value) => Some(*value),
+      _ => None,
+    }
+  }
+
+  pub fn as_node(&self) -> Option<&Node> {
+    match self {
+      Value::Node(node) => Some(node),
+      _ => None,
+    }
+  }
+
+  /// Read a guid that was stored as a string
+  pub fn as_uuid(&self) -> Option<Uuid> {
+    self.as_str().and_then(|value| Uuid::parse_str(value).ok())
+  }
+}
+
+impl From<bool> for Value {
+  fn from(value: bool) -> Value {
+    Value::Bool(value)
+  }
+}
+
+impl From<i32> for Value {
+  fn from(value: i32) -> Value {
+    Value::Integer(value.into())
+  }
+}
+
+impl From<i64> for Value {
+  fn from(value: i64) -> Value {
+    Value::Integer(value)
+  }
+}
+
+impl From<f64> for Value {
+  fn from(value: f64) -> Value {
+    Value::Float(value)
+  }
+}
+
+impl From<&str> for Value {
+  fn from(value: &str) -> Value {
+    Value::String(value.to_string())
+  }
+}
+
+impl From<String> for Value {
+  fn from(value: String) -> Value {
+    Value::String(value)
+  }
+}
+
+impl From<&String> for Value {
+  fn from(value: &String) -> Value {
+    Value::String(value.clone())
+  }
+}
+
+/// Guids are stored in their hyphenated string form
+impl From<Uuid> for Value {
+  fn from(value: Uuid) -> Value {
+    Value::String(value.to_string())
+  }
+}
+
+/// Money is stored as its decimal string, as a float can't hold most amounts of cents exactly
+impl From<Decimal> for Value {
+  fn from(value: Decimal) -> Value {
+    Value::String(value.to_string())
+  }
+}
+
+/// Dates are stored as ISO 8601 strings, which sort and compare in date order
+impl From<NaiveDate> for Value {
+  fn from(value: NaiveDate) -> Value {
+    Value::String(value.format("%Y-%m-%d").to_string())
+  }
+}
+
+impl From<DateTime<Utc>> for Value {
+  fn from(value: DateTime<Utc>) -> Value {
+    Value::String(value.to_rfc3339())
+  }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+  fn from(value: Option<T>) -> Value {
+    match value {
+      Some(inner) => inner.into(),
+      None => Value::Null,
+    }
+  }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+  fn from(value: Vec<T>) -> Value {
+    Value::List(value.into_iter().map(|item| item.into()).collect())
+  }
+}
+
+impl From<Properties> for Value {
+  fn from(value: Properties) -> Value {
+    Value::Map(value)
+  }
+}
+
+impl From<Node> for Value {
+  fn from(value: Node) -> Value {
+    Value::Node(value)
+  }
+}
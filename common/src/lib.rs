@@ -1,5 +1,8 @@
 //! Library to facilitate communication between the client and server portions of the wrangler
 
+// The derive macros refer to the model by its crate name, so make it resolvable from inside the crate
+extern crate self as wrangler_common;
+
 // The description of information stored in the database
 pub mod model;
 
@@ -26,7 +29,7 @@ pub mod prelude {
   pub use super::errors::WranglerErrorKind::{self, *};
 
   // This will be split out into it's own crate
-  pub use crate::errors::AllWhat;
+  pub use crate::errors::{allwhat::ResultPlus, AllWhat};
 
   pub type Result<O> = core::result::Result<O, AllWhat<WranglerErrorKind>>;
 }
@@ -0,0 +1,37 @@
+// This is the definition of an Address: a physical or mailing location for an Organization
+
+use super::local::*;
+
+#[derive(Accessible, Clone, Debug, Deserialize, Serialize)]
+pub struct Address {
+  /// A globally unique identifier for the Address
+  pub guid: Uuid,
+  pub street1: String,
+  pub street2: Option<String>,
+  pub street3: Option<String>,
+  pub city: Option<String>,
+  pub state: Option<String>,
+  pub postal_code: Option<String>,
+}
+
+impl GraphtNode for Address {
+  fn label(&self) -> &'static str {
+    ModelNode::Address.label()
+  }
+
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
+
+  fn properties(&self) -> Properties {
+    Properties::from([
+      ("guid".to_string(), self.guid.into()),
+      ("street1".to_string(), self.street1.clone().into()),
+      ("street2".to_string(), self.street2.clone().into()),
+      ("street3".to_string(), self.street3.clone().into()),
+      ("city".to_string(), self.city.clone().into()),
+      ("state".to_string(), self.state.clone().into()),
+      ("postal_code".to_string(), self.postal_code.clone().into()),
+    ])
+  }
+}
@@ -0,0 +1,75 @@
+// This is the definition of an Invoice: a bill sent to an Organization for a group of line items
+
+use super::{line_item::LineItem, local::*, organization::Organization};
+use std::sync::Arc;
+
+#[derive(Accessible, Clone, Debug, Deserialize, Serialize)]
+pub struct Invoice {
+  /// A globally unique identifier for the Invoice
+  pub guid: Uuid,
+  /// The invoice number printed on the bill
+  pub number: Option<i32>,
+  pub date: Option<NaiveDate>,
+  pub total: Decimal,
+  /// The amount of unpaid value on the invoice. This number is always positive.
+  pub balance: Decimal,
+  /// The Organization that contains the accounting department. This should be the parent that handles
+  /// the money as opposed to the specific organization that sent the specific submission
+  #[serde(with = "reference::arc")]
+  pub billed_to: Arc<Organization>,
+  pub items: Vec<LineItem>,
+}
+
+impl Invoice {
+  /// Add a line item to the bill, increasing both the total and the unpaid balance
+  pub fn add(&mut self, line_item: LineItem) {
+    self.total += line_item.total();
+    if !line_item.paid {
+      self.balance += line_item.total();
+    }
+    self.items.push(line_item);
+  }
+
+  /// Apply a credit to the unpaid line items, in the order they were added. The credit stops at the
+  /// first item it can't cover, so a later item is never paid ahead of an earlier one. This invoice
+  /// will be flagged as unpaid until the balance is zero.
+  ///
+  /// Returns the portion of the credit that was not used.
+  pub fn apply(&mut self, credit: Decimal) -> Decimal {
+    let mut remaining = credit;
+    for item in self.items.iter_mut().filter(|item| !item.paid) {
+      let amount = item.total();
+      if amount > remaining {
+        break;
+      }
+      item.paid = true;
+      self.balance -= amount;
+      remaining -= amount;
+    }
+    remaining
+  }
+
+  pub fn is_paid(&self) -> bool {
+    self.balance.is_zero()
+  }
+}
+
+impl GraphtNode for Invoice {
+  fn label(&self) -> &'static str {
+    ModelNode::Invoice.label()
+  }
+
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
+
+  fn properties(&self) -> Properties {
+    Properties::from([
+      ("guid".to_string(), self.guid.into()),
+      ("number".to_string(), self.number.into()),
+      ("date".to_string(), self.date.into()),
+      ("total".to_string(), self.total.into()),
+      ("balance".to_string(), self.balance.into()),
+    ])
+  }
+}
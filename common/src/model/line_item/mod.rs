@@ -0,0 +1,49 @@
+// This is the definition of a LineItem: a single billable service performed for a Submission
+
+use super::local::*;
+
+#[derive(Accessible, Clone, Debug, Deserialize, Serialize)]
+pub struct LineItem {
+  /// A globally unique identifier for the LineItem
+  pub guid: Uuid,
+  pub name: String,
+  pub quantity: Decimal,
+  pub price: Decimal,
+  pub started_on: Option<NaiveDate>,
+  pub finished_on: Option<NaiveDate>,
+  pub paid: bool,
+}
+
+impl LineItem {
+  /// The amount billed for this item
+  pub fn total(&self) -> Decimal {
+    self.quantity * self.price
+  }
+
+  /// Mark the service as completed on the given date
+  pub fn finish(&mut self, finished_on: NaiveDate) {
+    self.finished_on = Some(finished_on);
+  }
+}
+
+impl GraphtNode for LineItem {
+  fn label(&self) -> &'static str {
+    ModelNode::LineItem.label()
+  }
+
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
+
+  fn properties(&self) -> Properties {
+    Properties::from([
+      ("guid".to_string(), self.guid.into()),
+      ("name".to_string(), self.name.clone().into()),
+      ("quantity".to_string(), self.quantity.into()),
+      ("price".to_string(), self.price.into()),
+      ("started_on".to_string(), self.started_on.into()),
+      ("finished_on".to_string(), self.finished_on.into()),
+      ("paid".to_string(), self.paid.into()),
+    ])
+  }
+}
@@ -5,6 +5,9 @@ pub trait Unwrapable: std::fmt::Debug + Clone {
   fn unwrap<Value>(&self) -> Value;
 }
 
+/// Generates the Field/FieldValue enums and the accessors for a struct with named fields
+pub use wrangler_macros::Accessible;
+
 pub trait Accessible {
   type Struct;
   type Field: Clone;
@@ -19,27 +22,111 @@ pub trait Accessible {
   fn set(&mut self, value: Self::FieldValue);
 }
 
+pub mod address;
+pub mod invoice;
+pub mod line_item;
 pub mod organization;
+pub mod payment;
+pub mod person;
+pub mod submission;
+
+// Serialize links by guid
+pub mod reference;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grapht::{Link, NodeRef};
+use address::Address;
+use invoice::Invoice;
+use line_item::LineItem;
 use organization::Organization;
+use payment::Payment;
+use person::Person;
+use submission::Submission;
 
 /// Identifiers for the desired object defined in the model
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ModelNode {
   Organization,
   Submission,
   LineItem,
   Invoice,
   Payment,
+  Person,
   Address,
 }
 
 /// Identifiers for the desired link defined in the model
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ModelEdge {
   OrganizationParent,
+  /// The link back from a child organization to its parent
+  OrganizationChild,
+  OrganizationAddress,
+  SubmissionOrganization,
+  /// The link back from a submission to the organization that sent it
+  SubmissionSender,
+  SubmissionPerson,
+  SubmissionLineItem,
+  /// The link back from a line item to its submission
+  LineItemSubmission,
+  InvoiceOrganization,
+  InvoiceLineItem,
+  PaymentOrganization,
+  PaymentInvoice,
+}
+
+impl ModelNode {
+  /// The label used when storing the node in a graph database
+  pub const fn label(&self) -> &'static str {
+    match self {
+      ModelNode::Organization => "Organization",
+      ModelNode::Submission => "Submission",
+      ModelNode::LineItem => "Service",
+      ModelNode::Invoice => "Invoice",
+      ModelNode::Payment => "Payment",
+      ModelNode::Person => "Person",
+      ModelNode::Address => "Address",
+    }
+  }
+}
+
+impl ModelEdge {
+  /// The relationship type used when storing the edge in a graph database
+  pub const fn label(&self) -> &'static str {
+    match self {
+      ModelEdge::OrganizationParent => "PARENT_OF",
+      ModelEdge::OrganizationChild => "CHILD_OF",
+      ModelEdge::OrganizationAddress => "LOCATED_AT",
+      ModelEdge::SubmissionOrganization => "SUBMITTED",
+      ModelEdge::SubmissionSender => "SENT_BY",
+      ModelEdge::SubmissionPerson => "SUBMITTED_BY",
+      ModelEdge::SubmissionLineItem => "LINE_ITEM",
+      ModelEdge::LineItemSubmission => "BELONGS_TO",
+      ModelEdge::InvoiceOrganization => "BILLED_TO",
+      ModelEdge::InvoiceLineItem => "BILLS",
+      ModelEdge::PaymentOrganization => "PAID_BY",
+      ModelEdge::PaymentInvoice => "PAYS",
+    }
+  }
+
+  /// Create a link of this type between two nodes
+  pub fn link(&self, source: NodeRef, target: NodeRef) -> Link {
+    Link::new(self.label(), source, target)
+  }
 }
 
 /// Containers for the objects defined in by the model
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value")]
 pub enum ModelValue {
   Organizations(Vec<Organization>),
+  Submissions(Vec<Submission>),
+  LineItems(Vec<LineItem>),
+  Invoices(Vec<Invoice>),
+  Payments(Vec<Payment>),
+  People(Vec<Person>),
+  Addresses(Vec<Address>),
   OrganizationParent(Organization, Organization),
 }
 
@@ -47,5 +134,12 @@ pub enum ModelValue {
 mod local {
   pub use super::Accessible;
 
+  pub use super::reference::{self, Referenced};
+  pub use super::ModelNode;
+  pub use crate::grapht::{GraphtNode, Properties};
+
+  pub use chrono::NaiveDate;
+  pub use rust_decimal::Decimal;
+  pub use serde::{Deserialize, Serialize};
   pub use uuid::Uuid;
 }
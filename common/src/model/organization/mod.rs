@@ -3,37 +3,19 @@
 use super::local::*;
 use std::sync::Arc;
 
-#[derive(Clone, Debug)]
+#[derive(Accessible, Clone, Debug, Deserialize, Serialize)]
 pub struct Organization {
   /// A globally unique identifier for the Organization
   pub guid: Uuid,
   pub source_id: i32,
   pub pretty_id: String,
   pub name: String,
+  #[serde(with = "reference::option")]
   pub parent: Option<Arc<Organization>>,
+  #[serde(with = "reference::vec")]
   pub children: Vec<Arc<Organization>>,
 }
 
-#[derive(Clone, Debug)]
-pub enum OrganizationField {
-  Guid,
-  SourceId,
-  PrettyId,
-  Name,
-  Parent,
-  Children,
-}
-
-#[derive(Clone, Debug)]
-pub enum OrganizationFieldValue {
-  Guid(Uuid),
-  SourceId(i32),
-  PrettyId(String),
-  Name(String),
-  Parent(Option<Arc<Organization>>),
-  Children(Vec<Arc<Organization>>),
-}
-
 impl Organization {
   pub fn sample(name: &str) -> Organization {
     Organization {
@@ -47,43 +29,42 @@ impl Organization {
   }
 }
 
-impl Accessible for Organization {
-  type Struct = Organization;
+impl Referenced for Organization {
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
 
-  type Field = OrganizationField;
+  fn reference(guid: Uuid) -> Organization {
+    Organization {
+      guid,
+      source_id: 0,
+      pretty_id: String::new(),
+      name: String::new(),
+      parent: None,
+      children: vec![],
+    }
+  }
 
-  type FieldValue = OrganizationFieldValue;
+  fn is_reference(&self) -> bool {
+    self.name.is_empty() && self.pretty_id.is_empty()
+  }
+}
 
-  fn fields() -> Vec<Self::Field> {
-    vec![
-      OrganizationField::Guid,
-      OrganizationField::SourceId,
-      OrganizationField::PrettyId,
-      OrganizationField::Name,
-      OrganizationField::Parent,
-      OrganizationField::Children,
-    ]
+impl GraphtNode for Organization {
+  fn label(&self) -> &'static str {
+    ModelNode::Organization.label()
   }
 
-  fn get(&self, field: Self::Field) -> Self::FieldValue {
-    match field {
-      OrganizationField::Guid => OrganizationFieldValue::Guid(self.guid.clone()),
-      OrganizationField::SourceId => OrganizationFieldValue::SourceId(self.source_id.clone()),
-      OrganizationField::PrettyId => OrganizationFieldValue::PrettyId(self.pretty_id.clone()),
-      OrganizationField::Name => OrganizationFieldValue::Name(self.name.clone()),
-      OrganizationField::Parent => OrganizationFieldValue::Parent(self.parent.clone()),
-      OrganizationField::Children => OrganizationFieldValue::Children(self.children.clone()),
-    }
+  fn guid(&self) -> Uuid {
+    self.guid
   }
 
-  fn set(&mut self, value: Self::FieldValue) {
-    match value {
-      OrganizationFieldValue::Guid(inner) => self.guid = inner.clone(),
-      OrganizationFieldValue::SourceId(inner) => self.source_id = inner.clone(),
-      OrganizationFieldValue::PrettyId(inner) => self.pretty_id = inner.clone(),
-      OrganizationFieldValue::Name(inner) => self.name = inner.clone(),
-      OrganizationFieldValue::Parent(inner) => self.parent = inner.clone(),
-      OrganizationFieldValue::Children(inner) => self.children = inner.clone(),
-    }
+  fn properties(&self) -> Properties {
+    Properties::from([
+      ("guid".to_string(), self.guid.into()),
+      ("source_id".to_string(), self.source_id.into()),
+      ("pretty_id".to_string(), self.pretty_id.clone().into()),
+      ("name".to_string(), self.name.clone().into()),
+    ])
   }
 }
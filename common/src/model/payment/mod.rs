@@ -0,0 +1,51 @@
+// This is the definition of a Payment: money (or credit) received from an Organization
+
+use super::{local::*, organization::Organization};
+use std::sync::Arc;
+
+/// The means by which a payment was made
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PaymentType {
+  Check,
+  Ach,
+  Cash,
+  /// A balance carried over from a previous overpayment
+  Credit,
+  CreditCard,
+  Discount,
+  /// Imported from a log that only recorded when the money came in
+  Unrecorded,
+}
+
+#[derive(Accessible, Clone, Debug, Deserialize, Serialize)]
+pub struct Payment {
+  /// A globally unique identifier for the Payment
+  pub guid: Uuid,
+  pub kind: PaymentType,
+  /// The organization that handles the accounting, which may be different from the one submitting work
+  #[serde(with = "reference::arc")]
+  pub payer: Arc<Organization>,
+  pub amount: Decimal,
+  pub received_on: Option<NaiveDate>,
+  pub deposited_on: Option<NaiveDate>,
+}
+
+impl GraphtNode for Payment {
+  fn label(&self) -> &'static str {
+    ModelNode::Payment.label()
+  }
+
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
+
+  fn properties(&self) -> Properties {
+    Properties::from([
+      ("guid".to_string(), self.guid.into()),
+      ("kind".to_string(), format!("{:?}", self.kind).into()),
+      ("amount".to_string(), self.amount.into()),
+      ("received_on".to_string(), self.received_on.into()),
+      ("deposited_on".to_string(), self.deposited_on.into()),
+    ])
+  }
+}
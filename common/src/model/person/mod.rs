@@ -0,0 +1,76 @@
+// This is the definition of a Person: an individual who submits work on behalf of an Organization
+
+use super::local::*;
+
+#[derive(Accessible, Clone, Debug, Deserialize, Serialize)]
+pub struct Person {
+  /// A globally unique identifier for the Person
+  pub guid: Uuid,
+  pub salutation: Option<String>,
+  pub first: Option<String>,
+  pub middle: Option<String>,
+  pub last: Option<String>,
+}
+
+impl Person {
+  /// Build a person from the free text name found in the submission log
+  pub fn from_name(name: &str) -> Person {
+    let mut parts = name.split_whitespace();
+    Person {
+      guid: Uuid::new_v4(),
+      salutation: None,
+      first: parts.next().map(|part| part.to_string()),
+      middle: None,
+      last: parts.last().map(|part| part.to_string()),
+    }
+  }
+
+  /// The name as it would be written on an invoice
+  pub fn full_name(&self) -> String {
+    [&self.salutation, &self.first, &self.middle, &self.last]
+      .iter()
+      .filter_map(|part| part.as_deref())
+      .collect::<Vec<&str>>()
+      .join(" ")
+  }
+}
+
+impl Referenced for Person {
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
+
+  fn reference(guid: Uuid) -> Person {
+    Person {
+      guid,
+      salutation: None,
+      first: None,
+      middle: None,
+      last: None,
+    }
+  }
+
+  fn is_reference(&self) -> bool {
+    self.full_name().is_empty()
+  }
+}
+
+impl GraphtNode for Person {
+  fn label(&self) -> &'static str {
+    ModelNode::Person.label()
+  }
+
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
+
+  fn properties(&self) -> Properties {
+    Properties::from([
+      ("guid".to_string(), self.guid.into()),
+      ("salutation".to_string(), self.salutation.clone().into()),
+      ("first".to_string(), self.first.clone().into()),
+      ("middle".to_string(), self.middle.clone().into()),
+      ("last".to_string(), self.last.clone().into()),
+    ])
+  }
+}
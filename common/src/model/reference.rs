@@ -0,0 +1,90 @@
+//! Serialize links between model objects by guid instead of embedding the linked object
+//!
+//! Links in the model are `Arc`s that may form cycles (an Organization's parent lists it as a child),
+//! so serializing them directly would recurse forever. Instead only the guid is written and the link
+//! is deserialized as a reference: a stub of the object holding the guid and nothing else. The full
+//! object can then be looked up and swapped in by whoever owns the collection.
+//!
+//! Use with serde's `with` attribute: `#[serde(with = "reference::option")]`
+
+use super::local::*;
+
+/// A model object that can be linked to by its guid
+pub trait Referenced {
+  /// The identifier written in place of the object
+  fn guid(&self) -> Uuid;
+
+  /// Create a stub containing only the guid
+  fn reference(guid: Uuid) -> Self;
+
+  /// Test whether this is a stub created by [Referenced::reference]
+  fn is_reference(&self) -> bool;
+}
+
+/// A required link: `Arc<T>`
+pub mod arc {
+  use super::*;
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+  use std::sync::Arc;
+
+  pub fn serialize<S: Serializer, T: Referenced>(
+    value: &Arc<T>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    value.guid().serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>, T: Referenced>(
+    deserializer: D,
+  ) -> Result<Arc<T>, D::Error> {
+    Ok(Arc::new(T::reference(Uuid::deserialize(deserializer)?)))
+  }
+}
+
+/// An optional link: `Option<Arc<T>>`
+pub mod option {
+  use super::*;
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+  use std::sync::Arc;
+
+  pub fn serialize<S: Serializer, T: Referenced>(
+    value: &Option<Arc<T>>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    value
+      .as_ref()
+      .map(|inner| inner.guid())
+      .serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>, T: Referenced>(
+    deserializer: D,
+  ) -> Result<Option<Arc<T>>, D::Error> {
+    Ok(Option::<Uuid>::deserialize(deserializer)?.map(|guid| Arc::new(T::reference(guid))))
+  }
+}
+
+/// A list of links: `Vec<Arc<T>>`
+pub mod vec {
+  use super::*;
+  use serde::{Deserialize, Deserializer, Serializer};
+  use std::sync::Arc;
+
+  pub fn serialize<S: Serializer, T: Referenced>(
+    value: &[Arc<T>],
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(value.iter().map(|inner| inner.guid()))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>, T: Referenced>(
+    deserializer: D,
+  ) -> Result<Vec<Arc<T>>, D::Error> {
+    Ok(
+      Vec::<Uuid>::deserialize(deserializer)?
+        .into_iter()
+        .map(|guid| Arc::new(T::reference(guid)))
+        .collect(),
+    )
+  }
+}
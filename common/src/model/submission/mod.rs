@@ -0,0 +1,59 @@
+// This is the definition of a Submission: a case sent in by an Organization for pathology work
+
+use super::{line_item::LineItem, local::*, organization::Organization, person::Person};
+use std::sync::Arc;
+
+#[derive(Accessible, Clone, Debug, Deserialize, Serialize)]
+pub struct Submission {
+  /// A globally unique identifier for the Submission
+  pub guid: Uuid,
+  /// The human readable case number printed on the reports
+  pub accession_number: String,
+  #[serde(with = "reference::arc")]
+  pub submitting_org: Arc<Organization>,
+  #[serde(with = "reference::option")]
+  pub submitted_by: Option<Arc<Person>>,
+  pub category: String,
+  pub species: String,
+  pub pet_name: Option<String>,
+  pub diagnosis: Option<String>,
+  pub line_items: Vec<Arc<LineItem>>,
+  /// The amount billed for the submission as recorded in the log
+  pub total: Decimal,
+  pub received_on: Option<NaiveDate>,
+  pub finalized_on: Option<NaiveDate>,
+}
+
+impl Submission {
+  /// The sum of all the line items, which should match the recorded total
+  pub fn line_item_total(&self) -> Decimal {
+    self.line_items.iter().map(|item| item.total()).sum()
+  }
+}
+
+impl GraphtNode for Submission {
+  fn label(&self) -> &'static str {
+    ModelNode::Submission.label()
+  }
+
+  fn guid(&self) -> Uuid {
+    self.guid
+  }
+
+  fn properties(&self) -> Properties {
+    Properties::from([
+      ("guid".to_string(), self.guid.into()),
+      (
+        "accession_number".to_string(),
+        self.accession_number.clone().into(),
+      ),
+      ("category".to_string(), self.category.clone().into()),
+      ("species".to_string(), self.species.clone().into()),
+      ("pet_name".to_string(), self.pet_name.clone().into()),
+      ("diagnosis".to_string(), self.diagnosis.clone().into()),
+      ("total".to_string(), self.total.into()),
+      ("received_on".to_string(), self.received_on.into()),
+      ("finalized_on".to_string(), self.finalized_on.into()),
+    ])
+  }
+}
@@ -0,0 +1,25 @@
+//! The JSON encoding used for passing calls and model values between the client and server
+
+use crate::local::*;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Encode a value as a JSON string
+pub fn to_string<T: Serialize>(value: &T) -> Result<String> {
+  serde_json::to_string(value).map_err(|err| {
+    let result: AllWhat<WranglerErrorKind> = SerializationError.into();
+    result
+      .set_context("Could not encode the value as JSON")
+      .set_dev_context(&format!("From <serde_json::Error>:\n{:#?}", err))
+  })
+}
+
+/// Decode a value from a JSON string
+pub fn from_str<T: DeserializeOwned>(value: &str) -> Result<T> {
+  serde_json::from_str(value).map_err(|err| {
+    let result: AllWhat<WranglerErrorKind> = SerializationError.into();
+    result
+      .set_context("Could not decode the JSON value")
+      .set_dev_context(&format!("From <serde_json::Error>:\n{:#?}", err))
+  })
+}
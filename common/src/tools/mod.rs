@@ -2,3 +2,6 @@
 //!
 //! These are functions and types implemented in a way to keep things consistent across the project.
 //! There are usually multiple ways to do something, so we want to choose one and stick with it.
+
+// Encoding for values sent over the wire
+pub mod json;
@@ -0,0 +1,45 @@
+//! Exercise the Accessible derive against the model and a struct using the field attributes
+
+use wrangler_common::model::{
+  organization::{Organization, OrganizationField, OrganizationFieldValue},
+  Accessible,
+};
+
+#[derive(Accessible, Clone, Debug)]
+pub struct Renamed {
+  pub name: String,
+  #[accessible(rename = "Kind")]
+  pub _type: String,
+  #[accessible(skip)]
+  pub cache: Vec<u8>,
+}
+
+#[test]
+fn organization_get_and_set() {
+  let mut org = Organization::sample("Test Org");
+  assert_eq!(Organization::fields().len(), 6);
+
+  org.set(OrganizationFieldValue::Name("Renamed Org".to_string()));
+  match org.get(OrganizationField::Name) {
+    OrganizationFieldValue::Name(name) => assert_eq!(name, "Renamed Org"),
+    other => panic!("Expected a name, got {:?}", other),
+  }
+}
+
+#[test]
+fn skip_and_rename() {
+  let mut value = Renamed {
+    name: "Name".to_string(),
+    _type: "Biopsy".to_string(),
+    cache: vec![1, 2, 3],
+  };
+
+  // The cache is skipped and the type is renamed
+  let fields = Renamed::fields();
+  assert_eq!(fields.len(), 2);
+  assert!(matches!(fields[1], RenamedField::Kind));
+
+  value.set(RenamedFieldValue::Kind("Necropsy".to_string()));
+  assert_eq!(value._type, "Necropsy");
+  assert_eq!(value.cache, vec![1, 2, 3]);
+}
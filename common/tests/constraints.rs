@@ -0,0 +1,120 @@
+//! Check paths and uris against their constraints
+
+use wrangler_common::configuration::{
+  apps::neo4j::Neo4jConfig,
+  primitives::{
+    password::{Password, PasswordConstraint},
+    path::{PathConfig, PathConstraint},
+    uri::{UriConfig, UriConstraint, HTTP_SCHEMES, NEO4J_SCHEMES},
+  },
+  traits::Configuration,
+};
+
+fn scratch_dir() -> std::path::PathBuf {
+  let dir = std::env::temp_dir().join(format!("wrangler-{}", uuid::Uuid::new_v4()));
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+#[test]
+fn path_constraints() {
+  let dir = scratch_dir();
+  let file = dir.join("notes.txt");
+  std::fs::write(&file, "notes").unwrap();
+  let (dir_name, file_name) = (dir.to_str().unwrap(), file.to_str().unwrap());
+  let missing = dir.join("missing").join("wrangler.log");
+  let missing = missing.to_str().unwrap();
+
+  PathConfig::new(file_name)
+    .constrain(vec![
+      PathConstraint::IsFile,
+      PathConstraint::Exists,
+      PathConstraint::Writable,
+    ])
+    .unwrap();
+  PathConfig::new(dir_name)
+    .constrain(vec![
+      PathConstraint::IsDirectory,
+      PathConstraint::Not(Box::new(PathConstraint::IsFile)),
+    ])
+    .unwrap();
+
+  // Missing paths are writable when they could be created
+  PathConfig::new(missing)
+    .constrain(vec![
+      PathConstraint::Not(Box::new(PathConstraint::Exists)),
+      PathConstraint::Writable,
+    ])
+    .unwrap();
+
+  let err = PathConfig::new(missing)
+    .constrain(vec![PathConstraint::Exists, PathConstraint::IsDirectory])
+    .unwrap_err();
+  assert_eq!(err.get_inner().len(), 2);
+  let report = format!("{:?}", err);
+  assert!(report.contains(&format!("'{}' must exist", missing)));
+  assert!(report.contains(&format!("'{}' must be a directory", missing)));
+
+  let err = PathConfig::new(file_name)
+    .constrain(vec![PathConstraint::Not(Box::new(PathConstraint::IsFile))])
+    .unwrap_err();
+  assert!(format!("{:?}", err).contains("must not be a file"));
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn uri_constraints() {
+  let constraints = vec![
+    UriConstraint::IsUrl,
+    UriConstraint::schemes(&HTTP_SCHEMES),
+    UriConstraint::HasHost,
+  ];
+  let valid = UriConfig::parse("https://example.com/submissions").unwrap();
+  assert!(valid.constrain(constraints.clone()).is_ok());
+
+  let err = UriConfig::parse("127.0.0.1:7687")
+    .unwrap()
+    .constrain(vec![
+      UriConstraint::IsUrl,
+      UriConstraint::schemes(&NEO4J_SCHEMES),
+      UriConstraint::HasPort,
+    ])
+    .unwrap_err();
+  assert_eq!(err.get_inner().len(), 2);
+  let report = format!("{:?}", err);
+  assert!(report.contains("must be a url with a scheme and host"));
+  assert!(report.contains("must use one of the schemes: bolt"));
+
+  let err = UriConfig::parse("ftp://example.com")
+    .unwrap()
+    .constrain(vec![UriConstraint::Not(Box::new(UriConstraint::HasHost))])
+    .unwrap_err();
+  assert!(format!("{:?}", err).contains("must not include a host"));
+}
+
+#[test]
+fn neo4j_needs_a_bolt_uri() {
+  let new = |uri: &str| Neo4jConfig::new(uri, "neo4j", Password::new("neo4j"));
+  assert!(new("127.0.0.1:7687").is_err());
+  assert!(new("http://127.0.0.1:7687").is_err());
+  assert!(new("neo4j+s://db.example.com").is_err());
+
+  let config = new("neo4j+s://db.example.com:7687").unwrap();
+  assert_eq!(config.get_uri(), "neo4j+s://db.example.com:7687/");
+  assert_eq!(Neo4jConfig::default().get_uri(), "bolt://127.0.0.1:7687/");
+}
+
+#[test]
+fn password_failures_do_not_leak_it() {
+  let password = Password::new("hunter2");
+  assert!(password.is_valid());
+  assert_eq!(password.value(), "hunter2");
+
+  let err = password
+    .constrain(vec![PasswordConstraint::MinLength(12)])
+    .unwrap_err();
+  let report = format!("{:?}", err);
+  assert!(report.contains("The password must be at least 12 characters long"));
+  assert!(!report.contains("hunter2"));
+}
@@ -0,0 +1,336 @@
+//! Run Cypher queries against the in-memory graph
+
+use wrangler_common::grapht::{
+  cypher,
+  prelude::*,
+  store::{EdgeData, NodeData},
+  Graph,
+};
+
+fn params(values: Vec<(&str, Value)>) -> Properties {
+  values
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect()
+}
+
+fn run(graph: &mut Graph, query: &str) -> Vec<Row> {
+  graph.execute(query, &Properties::new()).unwrap()
+}
+
+/// Three organizations in a line, each submitting a number of submissions
+fn sample() -> Graph {
+  let mut graph = Graph::new();
+  run(
+    &mut graph,
+    "CREATE (a:Organization {pretty_id: 'ROOT', name: 'Root'})
+     CREATE (b:Organization {pretty_id: 'MID', name: 'Middle'})
+     CREATE (c:Organization {pretty_id: 'LEAF', name: 'Leaf'})
+     CREATE (a)-[:PARENT_OF]->(b)-[:PARENT_OF]->(c)
+     CREATE (b)-[:SUBMITTED]->(:Submission {accession_number: 'A-1', total: 10.5})
+     CREATE (b)-[:SUBMITTED]->(:Submission {accession_number: 'A-2', total: 4})
+     CREATE (c)-[:SUBMITTED]->(:Submission {accession_number: 'A-3', total: 20})",
+  );
+  graph
+}
+
+#[test]
+fn match_where_order_limit() {
+  let mut graph = sample();
+  let rows = run(
+    &mut graph,
+    "MATCH (o:Organization)-[:SUBMITTED]->(s:Submission)
+     WHERE s.total > 5 AND o.pretty_id <> 'ROOT'
+     RETURN o.name AS org, s.accession_number
+     ORDER BY s.total DESC
+     LIMIT 1",
+  );
+
+  assert_eq!(rows.len(), 1);
+  assert_eq!(rows[0]["org"], Value::from("Leaf"));
+  assert_eq!(rows[0]["s.accession_number"], Value::from("A-3"));
+}
+
+#[test]
+fn bound_parameters_and_aggregates() {
+  let mut graph = sample();
+  let rows = graph
+    .execute(
+      "MATCH (o:Organization {pretty_id: $id})-[:SUBMITTED]->(s)
+       RETURN o.pretty_id AS org, count(s) AS subs, sum(s.total) AS total",
+      &params(vec![("id", Value::from("MID"))]),
+    )
+    .unwrap();
+
+  assert_eq!(rows.len(), 1);
+  assert_eq!(rows[0]["subs"], Value::Integer(2));
+  assert_eq!(rows[0]["total"], Value::Float(14.5));
+
+  // Aggregating nothing still returns a row
+  let rows = run(
+    &mut graph,
+    "MATCH (o:Organization {pretty_id: 'NONE'}) RETURN count(o) AS found",
+  );
+  assert_eq!(rows[0]["found"], Value::Integer(0));
+
+  let missing = graph.execute("MATCH (o {pretty_id: $id}) RETURN o", &Properties::new());
+  assert!(missing.is_err());
+}
+
+#[test]
+fn aggregates_inside_expressions() {
+  let mut graph = sample();
+  let rows = run(
+    &mut graph,
+    "MATCH (o:Organization)-[:SUBMITTED]->(s)
+     RETURN o.pretty_id AS org, count(s) + 1 AS more, toString(count(s)) AS text,
+            o.pretty_id + ': ' + count(*) AS label
+     ORDER BY org",
+  );
+
+  assert_eq!(rows.len(), 2);
+  assert_eq!(rows[0]["org"], Value::from("LEAF"));
+  assert_eq!(rows[0]["more"], Value::Integer(2));
+  assert_eq!(rows[1]["more"], Value::Integer(3));
+  assert_eq!(rows[1]["text"], Value::from("2"));
+  assert_eq!(rows[1]["label"], Value::from("MID: 2"));
+
+  // An empty match still gives one row when every column aggregates
+  let rows = run(
+    &mut graph,
+    "MATCH (o:Organization {pretty_id: 'NONE'}) RETURN count(o) * 10 + 1 AS found",
+  );
+  assert_eq!(rows.len(), 1);
+  assert_eq!(rows[0]["found"], Value::Integer(1));
+}
+
+#[test]
+fn relationship_directions_and_lengths() {
+  let mut graph = sample();
+
+  let rows = run(
+    &mut graph,
+    "MATCH (c:Organization {pretty_id: 'LEAF'})<-[:PARENT_OF]-(p) RETURN p.name AS name",
+  );
+  assert_eq!(rows.len(), 1);
+  assert_eq!(rows[0]["name"], Value::from("Middle"));
+
+  let rows = run(
+    &mut graph,
+    "MATCH (:Organization {pretty_id: 'ROOT'})-[:PARENT_OF*1..]->(d)
+     RETURN d.pretty_id AS id ORDER BY id",
+  );
+  let ids: Vec<&Value> = rows.iter().map(|row| &row["id"]).collect();
+  assert_eq!(ids, vec![&Value::from("LEAF"), &Value::from("MID")]);
+
+  let rows = run(
+    &mut graph,
+    "MATCH (:Organization {pretty_id: 'MID'})-[r]-(n) RETURN type(r) AS type",
+  );
+  assert_eq!(rows.len(), 4);
+}
+
+#[test]
+fn merge_only_creates_once() {
+  let mut graph = sample();
+  let merge = "MERGE (o:Organization {pretty_id: $id})
+               ON CREATE SET o.created = true
+               ON MATCH SET o.matched = true
+               RETURN o.created AS created, o.matched AS matched";
+
+  let first = graph
+    .execute(merge, &params(vec![("id", Value::from("NEW"))]))
+    .unwrap();
+  assert_eq!(first[0]["created"], Value::Bool(true));
+  assert_eq!(first[0]["matched"], Value::Null);
+
+  let second = graph
+    .execute(merge, &params(vec![("id", Value::from("NEW"))]))
+    .unwrap();
+  assert_eq!(second[0]["matched"], Value::Bool(true));
+  assert_eq!(graph.with_label("Organization").len(), 4);
+
+  let link = "MATCH (p:Organization {pretty_id: 'ROOT'}), (c:Organization {pretty_id: 'NEW'})
+              MERGE (p)-[r:PARENT_OF]->(c)
+              RETURN count(r) AS linked";
+  run(&mut graph, link);
+  let rows = run(&mut graph, link);
+  assert_eq!(rows[0]["linked"], Value::Integer(1));
+  assert_eq!(graph.edge_count(), 6);
+}
+
+#[test]
+fn set_and_unwind() {
+  let mut graph = sample();
+  let rows = vec![
+    Value::Map(params(vec![
+      ("id", Value::from("A-1")),
+      ("species", Value::from("Canine")),
+    ])),
+    Value::Map(params(vec![
+      ("id", Value::from("A-3")),
+      ("species", Value::from("Feline")),
+    ])),
+  ];
+
+  graph
+    .execute(
+      "UNWIND $rows AS row
+       MATCH (s:Submission {accession_number: row.id})
+       SET s += {species: row.species}, s:Reviewed",
+      &params(vec![("rows", Value::List(rows))]),
+    )
+    .unwrap();
+
+  let rows = run(
+    &mut graph,
+    "MATCH (s:Reviewed) RETURN s.accession_number AS id, s.species AS species ORDER BY id",
+  );
+  assert_eq!(rows.len(), 2);
+  assert_eq!(rows[1]["species"], Value::from("Feline"));
+
+  // Replacing the properties drops everything that isn't in the map
+  run(
+    &mut graph,
+    "MATCH (s:Submission {accession_number: 'A-2'}) SET s = {accession_number: 'A-2b'}",
+  );
+  let rows = run(
+    &mut graph,
+    "MATCH (s:Submission {accession_number: 'A-2b'}) RETURN s.total AS total",
+  );
+  assert_eq!(rows[0]["total"], Value::Null);
+}
+
+#[test]
+fn delete_and_optional_match() {
+  let mut graph = sample();
+
+  // Nodes with relationships can only be removed with DETACH
+  let err = graph.execute(
+    "MATCH (o:Organization {pretty_id: 'LEAF'}) DELETE o",
+    &Properties::new(),
+  );
+  assert!(err.is_err());
+  assert_eq!(graph.node_count(), 6);
+
+  run(
+    &mut graph,
+    "MATCH (o:Organization {pretty_id: 'LEAF'}) DETACH DELETE o",
+  );
+  assert_eq!(graph.node_count(), 5);
+
+  let rows = run(
+    &mut graph,
+    "MATCH (s:Submission)
+     OPTIONAL MATCH (o)-[:SUBMITTED]->(s)
+     RETURN s.accession_number AS id, o.pretty_id AS org
+     ORDER BY id",
+  );
+  assert_eq!(rows.len(), 3);
+  assert_eq!(rows[2]["org"], Value::Null);
+
+  run(&mut graph, "MATCH (n) DETACH DELETE n");
+  assert_eq!(graph.node_count(), 0);
+  assert_eq!(graph.edge_count(), 0);
+}
+
+#[test]
+fn failed_writes_leave_the_graph_unchanged() {
+  let mut graph = sample();
+  let result = graph.execute(
+    "CREATE (:Organization {pretty_id: 'TEMP'}) WITH 1 AS one RETURN one / 0",
+    &Properties::new(),
+  );
+  assert!(result.is_err());
+  assert_eq!(graph.node_count(), 6);
+
+  // Every kind of change is undone, including the indexes used to find nodes again
+  let nodes: Vec<NodeData> = graph.nodes().map(|(_, node)| node.clone()).collect();
+  let edges: Vec<EdgeData> = graph.edges().map(|(_, edge)| edge.clone()).collect();
+  let result = graph.execute(
+    "MATCH (mid:Organization {pretty_id: 'MID'})-[r:PARENT_OF]->(leaf)
+     SET mid.name = 'Renamed', mid:Archived, r.since = 2020
+     CREATE (leaf)-[:PARENT_OF]->(:Organization {pretty_id: 'NEW'})
+     DETACH DELETE mid
+     WITH 1 AS one RETURN one / 0",
+    &Properties::new(),
+  );
+  assert!(result.is_err());
+  assert_eq!(
+    graph
+      .nodes()
+      .map(|(_, node)| node.clone())
+      .collect::<Vec<_>>(),
+    nodes
+  );
+  assert_eq!(
+    graph
+      .edges()
+      .map(|(_, edge)| edge.clone())
+      .collect::<Vec<_>>(),
+    edges
+  );
+  assert!(graph.with_label("Archived").is_empty());
+  let rows = run(
+    &mut graph,
+    "MATCH (:Organization {pretty_id: 'MID'})-[:SUBMITTED]->(s) RETURN count(s) AS subs",
+  );
+  assert_eq!(rows[0]["subs"], Value::Integer(2));
+}
+
+#[test]
+fn integer_overflow_is_an_error() {
+  let mut graph = Graph::new();
+  let min = params(vec![("min", Value::Integer(i64::MIN))]);
+  assert!(graph.execute("RETURN -$min AS value", &min).is_err());
+  assert!(graph.execute("RETURN $min - 1 AS value", &min).is_err());
+}
+
+#[test]
+fn syntax_errors_report_the_position() {
+  let err = cypher::parse("MATCH (n:Organization\nRETURN n").unwrap_err();
+  assert!(format!("{:?}", err).contains("line 2"));
+
+  assert!(cypher::parse("MATCH (a)<-[:R]->(b) RETURN a").is_err());
+  assert!(cypher::parse("RETURN 1 RETURN 2").is_err());
+}
+
+#[test]
+fn neo4j_connection_queries() {
+  // The same statements the Neo4j connection builds for create and relate
+  let mut graph = Graph::new();
+  for id in ["PARENT", "CHILD"] {
+    graph
+      .execute(
+        "CREATE (n:Organization) SET n = $properties",
+        &params(vec![(
+          "properties",
+          Value::Map(params(vec![("guid", Value::from(id))])),
+        )]),
+      )
+      .unwrap();
+  }
+
+  let relate = "MATCH (a:Organization {guid: $source})
+                MATCH (b:Organization {guid: $target})
+                MERGE (a)-[r:PARENT_OF]->(b)
+                SET r = $properties
+                RETURN count(r) AS linked";
+  let linked = |graph: &mut Graph, target: &str| {
+    graph
+      .execute(
+        relate,
+        &params(vec![
+          ("source", Value::from("PARENT")),
+          ("target", Value::from(target)),
+          ("properties", Value::Map(Properties::new())),
+        ]),
+      )
+      .unwrap()[0]["linked"]
+      .clone()
+  };
+
+  assert_eq!(linked(&mut graph, "CHILD"), Value::Integer(1));
+  assert_eq!(linked(&mut graph, "MISSING"), Value::Integer(0));
+  assert_eq!(graph.edge_count(), 1);
+}
@@ -0,0 +1,58 @@
+//! AllWhat as a standard error, recording where it was raised
+
+use std::error::Error;
+
+use wrangler_common::prelude::*;
+
+fn parse(text: &str) -> Result<serde_json::Value> {
+  Ok(serde_json::from_str(text)?)
+}
+
+#[test]
+fn records_where_it_was_raised() {
+  let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+  let location = err.get_location().unwrap();
+  assert_eq!(location.file, file!());
+  assert_eq!(location.line, line!() - 3);
+
+  // Converting with ? points at the ?, not the conversion
+  let err = parse("{").unwrap_err();
+  assert!(matches!(err.get_kind(), SerializationError));
+  assert_eq!(err.get_location().unwrap().line, 8);
+  assert!(format!("{:?}", err).contains(&format!("(at {}:8:", file!())));
+
+  // Neither is sent to a client from a release build
+  let stripped = err.strip_dev_context();
+  assert!(stripped.get_location().is_none());
+  assert!(stripped.get_backtrace().is_none());
+}
+
+#[test]
+fn equality_ignores_where_it_was_raised() {
+  let first = AllWhat::from(IOError).set_context("Could not read 'a.tsv'");
+  let second = AllWhat::from(IOError).set_context("Could not read 'a.tsv'");
+  assert_ne!(first.get_location(), second.get_location());
+  assert_eq!(first, second);
+  assert_ne!(
+    first,
+    AllWhat::from(IOError).set_context("Could not read 'b.tsv'")
+  );
+}
+
+#[test]
+fn source_is_the_first_cause() {
+  let children: Vec<Result<()>> = vec![
+    Ok(()),
+    Err(AllWhat::from(IOError).set_context("Could not read 'a.tsv'")),
+    Err(AllWhat::from(IOError).set_context("Could not read 'b.tsv'")),
+  ];
+  let err = AllWhat::flatten(ValidationError, children)
+    .unwrap_err()
+    .set_context("The import failed");
+
+  let boxed: Box<dyn Error> = Box::new(err);
+  assert!(boxed.to_string().contains("The import failed"));
+  let source = boxed.source().unwrap();
+  assert_eq!(source.to_string(), "IOError: Could not read 'a.tsv'");
+  assert!(source.source().is_none());
+}
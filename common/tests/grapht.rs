@@ -0,0 +1,220 @@
+//! Exercise the in-memory graph store
+
+use std::{fs, path::PathBuf};
+
+use uuid::Uuid;
+use wrangler_common::{
+  grapht::{prelude::*, Direction, Graph},
+  model::{organization::Organization, ModelEdge},
+};
+
+/// A three level hierarchy: root -> middle -> leaf
+fn hierarchy() -> (Graph, Vec<Organization>) {
+  let orgs = vec![
+    Organization::sample("Root Org"),
+    Organization::sample("Middle Org"),
+    Organization::sample("Leaf Org"),
+  ];
+
+  let mut graph = Graph::new();
+  for org in &orgs {
+    graph.insert(org).unwrap();
+  }
+  for pair in orgs.windows(2) {
+    let link = ModelEdge::OrganizationParent.link(pair[0].node_ref(), pair[1].node_ref());
+    graph.relate(&link).unwrap();
+  }
+  (graph, orgs)
+}
+
+#[test]
+fn insert_and_find() {
+  let (graph, orgs) = hierarchy();
+  assert_eq!(graph.node_count(), 3);
+  assert_eq!(graph.with_label("Organization").len(), 3);
+
+  let id = graph.find_guid(&orgs[1].guid).unwrap();
+  let node = graph.node(id).unwrap();
+  assert_eq!(node.guid(), Some(orgs[1].guid));
+
+  let props = Properties::from([("name".to_string(), Value::from("Leaf Org"))]);
+  let found = graph.find_nodes(Some("Organization"), &props);
+  assert_eq!(found, vec![graph.find_ref(&orgs[2].node_ref()).unwrap()]);
+}
+
+#[test]
+fn duplicate_guid_is_rejected() {
+  let (mut graph, orgs) = hierarchy();
+  assert!(graph.insert(&orgs[0]).is_err());
+  assert_eq!(graph.node_count(), 3);
+}
+
+#[test]
+fn relate_merges_existing_edges() {
+  let (mut graph, orgs) = hierarchy();
+  assert_eq!(graph.edge_count(), 2);
+
+  let link = ModelEdge::OrganizationParent.link(orgs[0].node_ref(), orgs[1].node_ref());
+  graph.relate(&link).unwrap();
+  assert_eq!(graph.edge_count(), 2);
+
+  let missing = Organization::sample("Missing Org");
+  let link = ModelEdge::OrganizationParent.link(orgs[0].node_ref(), missing.node_ref());
+  assert!(graph.relate(&link).is_err());
+}
+
+#[test]
+fn traverse_follows_direction_and_depth() {
+  let (graph, orgs) = hierarchy();
+  let ids: Vec<_> = orgs
+    .iter()
+    .map(|org| graph.find_guid(&org.guid).unwrap())
+    .collect();
+
+  let descendants = graph.traverse(ids[0], Some("PARENT_OF"), Direction::Outgoing, 10);
+  assert_eq!(descendants, vec![ids[1], ids[2]]);
+
+  let children = graph.traverse(ids[0], Some("PARENT_OF"), Direction::Outgoing, 1);
+  assert_eq!(children, vec![ids[1]]);
+
+  let ancestors = graph.traverse(ids[2], None, Direction::Incoming, 10);
+  assert_eq!(ancestors, vec![ids[1], ids[0]]);
+}
+
+#[test]
+fn delete_requires_detach() {
+  let (mut graph, orgs) = hierarchy();
+  let middle = graph.find_guid(&orgs[1].guid).unwrap();
+
+  assert!(graph.remove_node(middle, false).is_err());
+  graph.remove_node(middle, true).unwrap();
+
+  assert_eq!(graph.node_count(), 2);
+  assert_eq!(graph.edge_count(), 0);
+  assert!(graph.find_guid(&orgs[1].guid).is_none());
+}
+
+/// A unique file in the temp directory, so tests can run in parallel
+fn snapshot_path() -> PathBuf {
+  std::env::temp_dir().join(format!("grapht-{}.jsonl", Uuid::new_v4()))
+}
+
+#[test]
+fn snapshot_round_trip() {
+  let (graph, orgs) = hierarchy();
+  let path = snapshot_path();
+  graph.save(&path).unwrap();
+
+  let loaded = Graph::load(&path).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(loaded.node_count(), 3);
+  assert_eq!(loaded.edge_count(), 2);
+  for org in &orgs {
+    let (before, after) = (
+      graph.node(graph.find_guid(&org.guid).unwrap()).unwrap(),
+      loaded.node(loaded.find_guid(&org.guid).unwrap()).unwrap(),
+    );
+    assert_eq!(before, after);
+  }
+
+  let root = loaded.find_guid(&orgs[0].guid).unwrap();
+  let descendants = loaded.traverse(root, Some("PARENT_OF"), Direction::Outgoing, 10);
+  assert_eq!(descendants.len(), 2);
+}
+
+#[test]
+fn snapshot_round_trips_every_value() {
+  let mut graph = Graph::new();
+  let properties: Properties = [
+    ("flag".to_string(), Value::Bool(true)),
+    ("count".to_string(), Value::Integer(-3)),
+    ("ratio".to_string(), Value::Float(0.1)),
+    ("name".to_string(), Value::String("Rex".to_string())),
+    ("empty".to_string(), Value::Null),
+    (
+      "scores".to_string(),
+      Value::List(vec![Value::Float(1.5), Value::Integer(2)]),
+    ),
+    (
+      "extra".to_string(),
+      Value::Map([("weight".to_string(), Value::Float(-12.25))].into()),
+    ),
+  ]
+  .into();
+  let first = graph
+    .add_node(vec!["Pet".to_string()], properties.clone())
+    .unwrap();
+  let second = graph
+    .add_node(vec!["Pet".to_string()], Properties::new())
+    .unwrap();
+  graph
+    .add_edge("SIBLING_OF", first, second, properties.clone())
+    .unwrap();
+
+  let path = snapshot_path();
+  graph.save(&path).unwrap();
+  let loaded = Graph::load(&path).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  let (_, node) = loaded
+    .nodes()
+    .find(|(_, node)| !node.properties.is_empty())
+    .unwrap();
+  assert_eq!(node.properties, properties);
+  let (_, edge) = loaded.edges().next().unwrap();
+  assert_eq!(edge.properties, properties);
+}
+
+#[test]
+fn snapshot_rejects_non_finite_floats() {
+  let path = snapshot_path();
+  for value in [
+    Value::Float(f64::NAN),
+    Value::List(vec![Value::Float(f64::INFINITY)]),
+  ] {
+    let mut graph = Graph::new();
+    graph
+      .add_node(
+        vec!["Pet".to_string()],
+        [("weight".to_string(), value)].into(),
+      )
+      .unwrap();
+    assert!(graph.save(&path).is_err());
+    assert!(!path.exists(), "nothing is written");
+  }
+}
+
+#[test]
+fn snapshot_rejects_corruption() {
+  let (graph, _) = hierarchy();
+  let path = snapshot_path();
+  let bytes = graph.to_snapshot().unwrap();
+
+  // Change a single character of an organization's name
+  let text = String::from_utf8(bytes.clone()).unwrap();
+  let tampered = text.replacen("Leaf Org", "Leaf Orc", 1);
+  assert!(Graph::from_snapshot(tampered.as_bytes(), &path).is_err());
+
+  // Drop the footer, as if the write was cut off
+  let truncated = &bytes[..text.trim_end().rfind('\n').unwrap()];
+  assert!(Graph::from_snapshot(truncated, &path).is_err());
+
+  assert!(Graph::from_snapshot(&bytes, &path).is_ok());
+}
+
+#[test]
+fn snapshot_rejects_other_versions() {
+  let path = snapshot_path();
+  let text = String::from_utf8(Graph::new().to_snapshot().unwrap()).unwrap();
+  let header = text.lines().next().unwrap();
+  let newer = header.replace("\"version\":1", "\"version\":99");
+
+  // Rebuild the footer so only the version is wrong
+  let footer = format!(
+    "{{\"type\":\"Footer\",\"nodes\":0,\"edges\":0,\"checksum\":\"{:08x}\"}}\n",
+    crc32fast::hash(format!("{}\n", newer).as_bytes())
+  );
+  let snapshot = format!("{}\n{}", newer, footer);
+  assert!(Graph::from_snapshot(snapshot.as_bytes(), &path).is_err());
+}
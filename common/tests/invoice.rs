@@ -0,0 +1,68 @@
+//! Paying off the line items of an invoice
+
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use wrangler_common::model::{invoice::Invoice, line_item::LineItem, organization::Organization};
+
+fn item(name: &str, price: &str) -> LineItem {
+  LineItem {
+    guid: Uuid::new_v4(),
+    name: name.to_string(),
+    quantity: Decimal::ONE,
+    price: price.parse().unwrap(),
+    started_on: None,
+    finished_on: None,
+    paid: false,
+  }
+}
+
+fn invoice(items: Vec<LineItem>) -> Invoice {
+  let mut invoice = Invoice {
+    guid: Uuid::new_v4(),
+    number: Some(12),
+    date: None,
+    total: Decimal::ZERO,
+    balance: Decimal::ZERO,
+    billed_to: Arc::new(Organization::sample("Acme Vets")),
+    items: vec![],
+  };
+  for item in items {
+    invoice.add(item);
+  }
+  invoice
+}
+
+#[test]
+fn credits_pay_items_in_order() {
+  let mut bill = invoice(vec![item("Necropsy", "50.00"), item("Histo", "10.00")]);
+  assert_eq!(
+    bill.apply("55.00".parse().unwrap()),
+    "5.00".parse().unwrap()
+  );
+  assert!(bill.items[0].paid);
+  assert!(!bill.items[1].paid);
+  assert_eq!(bill.balance, "10.00".parse().unwrap());
+
+  assert_eq!(bill.apply("10.00".parse().unwrap()), Decimal::ZERO);
+  assert!(bill.is_paid());
+}
+
+#[test]
+fn credits_stop_at_an_item_they_cannot_cover() {
+  let mut bill = invoice(vec![
+    item("Necropsy", "500.00"),
+    item("Histo", "10.00"),
+    item("Cytology", "20.00"),
+  ]);
+
+  // The cheaper items added later stay open until the first one is paid
+  assert_eq!(
+    bill.apply("100.00".parse().unwrap()),
+    "100.00".parse().unwrap()
+  );
+  assert!(bill.items.iter().all(|item| !item.paid));
+  assert_eq!(bill.balance, "530.00".parse().unwrap());
+  assert!(!bill.is_paid());
+}
@@ -0,0 +1,57 @@
+//! The error kinds and the codes that support notes refer to
+
+use std::collections::HashSet;
+
+use wrangler_common::errors::ErrorKind;
+use wrangler_common::prelude::*;
+
+#[test]
+fn codes_are_stable_and_unique() {
+  // Support notes quote these, so a failure here means a released code was changed
+  let expected = [
+    (UnrecognizedError, "WR-1000"),
+    (ValidationError, "WR-1001"),
+    (NotFound, "WR-1002"),
+    (Conflict, "WR-1003"),
+    (PermissionDenied, "WR-1004"),
+    (ConfigError, "WR-2000"),
+    (IOError, "WR-2001"),
+    (SerializationError, "WR-2002"),
+    (GraphDbError, "WR-2003"),
+    (TokioError, "WR-2004"),
+    (ImportRowError, "WR-3000"),
+    (BillingError, "WR-3001"),
+  ];
+  for (kind, reference) in &expected {
+    assert_eq!(&kind.reference(), reference);
+    assert_eq!(
+      WranglerErrorKind::from_code(kind.code()).as_ref(),
+      Some(kind)
+    );
+  }
+  assert_eq!(WranglerErrorKind::ALL.len(), expected.len());
+
+  let codes: HashSet<u32> = WranglerErrorKind::ALL
+    .iter()
+    .map(|kind| kind.code())
+    .collect();
+  assert_eq!(codes.len(), WranglerErrorKind::ALL.len());
+  assert_eq!(WranglerErrorKind::from_code(9999), None);
+}
+
+#[test]
+fn user_message_fills_the_template() {
+  let err: AllWhat<WranglerErrorKind> = NotFound.into();
+  assert_eq!(err.user_message(), "We could not find what was asked for.");
+
+  let err = err.set_context("There is no submission 'A-1042'");
+  assert_eq!(
+    err.user_message(),
+    "We could not find what was asked for. There is no submission 'A-1042'"
+  );
+
+  // Every template has somewhere to put the context
+  for kind in WranglerErrorKind::ALL {
+    assert!(kind.message().contains("{context}"), "{}", kind.name());
+  }
+}
@@ -0,0 +1,80 @@
+//! Passwords are never shown, and can be read from outside the configuration
+
+use wrangler_common::configuration::{
+  primitives::password::{Password, PasswordSource, REDACTED},
+  traits::Configuration,
+};
+
+#[test]
+fn never_printed_or_serialized() {
+  let password = Password::new("hunter2");
+  assert_eq!(format!("{}", password), REDACTED);
+  assert_eq!(format!("{:?}", password), REDACTED);
+  assert_eq!(
+    serde_json::to_string(&password).unwrap(),
+    format!("\"{}\"", REDACTED)
+  );
+
+  // Once its source is known, that is written instead so it can be found again
+  let password = password.set_source(PasswordSource::Env("NEO4J_PASSWORD".to_string()));
+  assert_eq!(
+    serde_json::to_string(&password).unwrap(),
+    r#"{"env":"NEO4J_PASSWORD"}"#
+  );
+
+  let read: Password = serde_json::from_str(&format!("\"{}\"", REDACTED)).unwrap();
+  assert!(read.is_redacted());
+}
+
+#[test]
+fn round_trips_through_a_saved_configuration() {
+  for source in [
+    PasswordSource::Env("NEO4J_PASSWORD".to_string()),
+    PasswordSource::File("/run/secrets/neo4j".to_string()),
+    PasswordSource::Store("neo4j".to_string()),
+  ] {
+    let saved =
+      serde_json::to_string(&Password::new("hunter2").set_source(source.clone())).unwrap();
+    let read: Password = serde_json::from_str(&saved).unwrap();
+    assert_eq!(read.get_source(), Some(&source));
+    assert!(read.is_redacted(), "only the source is written");
+    assert_eq!(serde_json::to_string(&read).unwrap(), saved);
+  }
+
+  let saved = serde_json::to_string(&Password::new("hunter2")).unwrap();
+  let read: Password = serde_json::from_str(&saved).unwrap();
+  assert!(read.get_source().is_none());
+  assert!(read.is_redacted());
+  assert_eq!(serde_json::to_string(&read).unwrap(), saved);
+
+  // A password typed into the file is still read as the password
+  let typed: Password = serde_json::from_str("\"hunter2\"").unwrap();
+  assert_eq!(typed.value(), "hunter2");
+}
+
+#[test]
+fn read_from_a_file_or_the_environment() {
+  let path = std::env::temp_dir().join(format!("wrangler-{}.pass", uuid::Uuid::new_v4()));
+  std::fs::write(&path, "hunter2\r\n").unwrap();
+  let path_name = path.to_str().unwrap();
+
+  let password = Password::from_file(path_name).unwrap();
+  assert_eq!(password.value(), "hunter2");
+  assert_eq!(
+    password.get_source(),
+    Some(&PasswordSource::File(path_name.to_string()))
+  );
+  std::fs::remove_file(&path).unwrap();
+  assert!(Password::from_file(path_name).is_err());
+
+  std::env::set_var("WRANGLER_TEST_PASSWORD", "from-env");
+  assert_eq!(
+    Password::from_env("WRANGLER_TEST_PASSWORD")
+      .unwrap()
+      .value(),
+    "from-env"
+  );
+
+  let err = Password::from_env("WRANGLER_TEST_PASSWORD_MISSING").unwrap_err();
+  assert!(format!("{}", err).contains("'WRANGLER_TEST_PASSWORD_MISSING' does not hold a password"));
+}
@@ -0,0 +1,152 @@
+//! Round trip the model and calls through their JSON representation
+
+use std::sync::Arc;
+
+use wrangler_common::{
+  calls::{
+    data::{Filter, Page, Query},
+    Call, Reply, Request, Response,
+  },
+  errors::{AllWhat, WranglerErrorKind},
+  model::{organization::Organization, reference::Referenced, ModelNode, ModelValue},
+  tools::json,
+};
+
+#[test]
+fn organization_links_by_guid() {
+  let parent = Arc::new(Organization::sample("Parent Org"));
+  let mut org = Organization::sample("Child Org");
+  let grandchild = Arc::new(Organization::sample("Grandchild Org"));
+  org.parent = Some(parent.clone());
+  org.children = vec![grandchild.clone()];
+
+  let encoded = json::to_string(&org).unwrap();
+  let value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
+  assert_eq!(value["parent"], parent.guid.to_string());
+  assert_eq!(value["children"][0], grandchild.guid.to_string());
+
+  let decoded: Organization = json::from_str(&encoded).unwrap();
+  assert_eq!(decoded.guid, org.guid);
+  assert_eq!(decoded.name, org.name);
+
+  // Links come back as references to be resolved by the caller
+  let decoded_parent = decoded.parent.unwrap();
+  assert_eq!(decoded_parent.guid, parent.guid);
+  assert!(decoded_parent.is_reference());
+  assert_eq!(decoded.children[0].guid, grandchild.guid);
+}
+
+#[test]
+fn model_value_round_trip() {
+  let orgs = ModelValue::Organizations(vec![
+    Organization::sample("First Org"),
+    Organization::sample("Second Org"),
+  ]);
+
+  let encoded = json::to_string(&orgs).unwrap();
+  match json::from_str(&encoded).unwrap() {
+    ModelValue::Organizations(decoded) => {
+      assert_eq!(decoded.len(), 2);
+      assert_eq!(decoded[1].name, "Second Org");
+    }
+    other => panic!("Decoded the wrong model value: {:?}", other),
+  }
+}
+
+#[test]
+fn call_round_trip() {
+  let retrieve = Call::Data(Query::Retrieve {
+    node: ModelNode::Organization,
+    filter: Filter::Equals {
+      field: "pretty_id".to_string(),
+      value: "WOW".to_string(),
+    },
+    page: Page::default(),
+  });
+  let encoded = json::to_string(&retrieve).unwrap();
+  assert_eq!(
+    encoded,
+    concat!(
+      r#"{"type":"Data","value":{"type":"Retrieve","node":"Organization","#,
+      r#""filter":{"type":"Equals","value":{"field":"pretty_id","value":"WOW"}},"#,
+      r#""page":{"offset":0,"limit":100}}}"#
+    )
+  );
+  match json::from_str(&encoded).unwrap() {
+    Call::Data(Query::Retrieve { node, filter, page }) => {
+      assert_eq!(node, ModelNode::Organization);
+      assert!(matches!(filter, Filter::Equals { .. }));
+      assert_eq!(page.limit, Some(100));
+    }
+    other => panic!("Decoded the wrong call: {:?}", other),
+  }
+
+  let encoded = json::to_string(&Call::Heartbeat).unwrap();
+  assert!(matches!(json::from_str(&encoded).unwrap(), Call::Heartbeat));
+
+  // Unknown calls are rejected rather than guessed at
+  assert!(json::from_str::<Call>(r#"{"type":"Reboot"}"#).is_err());
+}
+
+#[test]
+fn reply_matches_request() {
+  let request = Request::new(Call::Heartbeat);
+  let decoded: Request = json::from_str(&json::to_string(&request).unwrap()).unwrap();
+  assert_eq!(decoded.id, request.id);
+
+  let reply = decoded.reply(Response::Heartbeat);
+  assert_eq!(reply.id, request.id);
+  assert!(matches!(reply.response, Response::Heartbeat));
+}
+
+fn error_tree() -> AllWhat<WranglerErrorKind> {
+  let children: Vec<Result<(), AllWhat<WranglerErrorKind>>> = vec![
+    Err(
+      AllWhat::from(WranglerErrorKind::ValidationError)
+        .set_context("'x' must be a file")
+        .set_dev_context("Checked with std::fs"),
+    ),
+    Ok(()),
+    Err(WranglerErrorKind::IOError.into()),
+  ];
+  AllWhat::flatten(WranglerErrorKind::ValidationError, children)
+    .unwrap_err()
+    .set_context("The workspace configuration is invalid")
+}
+
+#[test]
+fn error_tree_round_trip() {
+  let err = error_tree();
+  let text = json::to_string(&err).unwrap();
+  let value: serde_json::Value = json::from_str(&text).unwrap();
+  assert_eq!(value["kind"], "ValidationError");
+  assert_eq!(value["context"], "The workspace configuration is invalid");
+  assert_eq!(value["location"]["file"], file!());
+  assert_eq!(value["inner"][0]["context"], "'x' must be a file");
+  assert_eq!(value["inner"][0]["dev_context"], "Checked with std::fs");
+  assert_eq!(value["inner"][1]["kind"], "IOError");
+  assert!(value["inner"][1].get("context").is_none());
+  assert!(value.get("backtrace").is_none());
+
+  let decoded: AllWhat<WranglerErrorKind> = json::from_str(&text).unwrap();
+  assert_eq!(decoded, err);
+
+  // The developer context is removed all the way down the tree
+  let stripped = err.strip_dev_context();
+  assert_eq!(stripped.get_inner()[0].get_dev_context(), None);
+  assert_eq!(
+    stripped.get_inner()[0].get_context(),
+    Some("'x' must be a file")
+  );
+
+  // Errors sent in a reply only keep the developer context in debug builds
+  let reply = Request::new(Call::Heartbeat).reply(Response::Error(error_tree()));
+  let decoded: Reply = json::from_str(&json::to_string(&reply).unwrap()).unwrap();
+  match decoded.response {
+    Response::Error(err) => assert_eq!(
+      err.get_inner()[0].get_dev_context().is_some(),
+      cfg!(debug_assertions)
+    ),
+    other => panic!("Expected an error, got {:?}", other),
+  }
+}
@@ -0,0 +1,451 @@
+//! The command line interface for loading the FHL submission log
+
+use std::{
+  collections::HashSet,
+  io::{BufRead, IsTerminal, Write},
+  path::PathBuf,
+  time::Instant,
+};
+
+use clap::{Args, Parser, Subcommand};
+use wrangler_common::{
+  configuration::{apps::neo4j::Neo4jConfig, primitives::password::Password},
+  prelude::{AllWhat, PermissionDenied, Result as AWResult, ResultPlus, WranglerErrorKind},
+};
+
+use crate::{
+  dates::DateOrder,
+  grapht::{Neo4jConnection, DEFAULT_BATCH_SIZE},
+  hierarchy,
+  mapping::Mapping,
+  reader,
+  report::{Checked, Report, ReportFormat},
+  writer,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+  name = "wrangler-importer",
+  version,
+  about = "Load the FHL submission log into Neo4j"
+)]
+pub struct Cli {
+  #[command(flatten)]
+  pub connection: ConnectionArgs,
+
+  #[command(subcommand)]
+  pub command: Command,
+}
+
+/// Where the database is and how to log in
+#[derive(Args, Debug)]
+pub struct ConnectionArgs {
+  /// The Neo4j server to load into
+  #[arg(
+    long,
+    global = true,
+    env = "WRANGLER_NEO4J_URI",
+    default_value = "bolt://127.0.0.1:7687"
+  )]
+  pub uri: String,
+
+  /// The user to log in as
+  #[arg(
+    long,
+    global = true,
+    env = "WRANGLER_NEO4J_USER",
+    default_value = "neo4j"
+  )]
+  pub user: String,
+
+  /// The environment variable holding the password
+  #[arg(long, global = true, default_value = "WRANGLER_NEO4J_PASSWORD")]
+  pub password_env: String,
+
+  /// Read the password from a file instead of the environment
+  #[arg(long, global = true)]
+  pub password_file: Option<PathBuf>,
+}
+
+impl ConnectionArgs {
+  pub fn config(&self) -> AWResult<Neo4jConfig> {
+    let password = match &self.password_file {
+      Some(path) => Password::from_file(&path.to_string_lossy())?,
+      None => Password::from_env(&self.password_env).set_context(&format!(
+        "Set {} to the password of the Neo4j database, or use --password-file",
+        self.password_env
+      ))?,
+    };
+    Neo4jConfig::new(&self.uri, &self.user, password)
+  }
+
+  pub async fn connect(&self) -> AWResult<Neo4jConnection> {
+    Neo4jConnection::connect(&self.config()?).await
+  }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  /// Load a file into the database
+  Import {
+    #[command(subcommand)]
+    what: ImportCommand,
+  },
+
+  /// Read the files and report any problems, without connecting to the database
+  Validate(ValidateArgs),
+
+  /// Delete everything in the database
+  Wipe(Guard),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportCommand {
+  /// The organizations and how they are related
+  Orgs(OrgArgs),
+
+  /// The submissions and their line items. The organizations need to be imported first. To start
+  /// the submissions over, import the organizations again with --replace.
+  Submissions(SubmissionArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+  /// The file to read
+  #[arg(long, short)]
+  pub file: PathBuf,
+
+  #[command(flatten)]
+  pub format: FileFormat,
+
+  /// Check the file and print the report without connecting to the database
+  #[arg(long)]
+  pub dry_run: bool,
+
+  /// How to print the problems found in the file
+  #[arg(long, value_enum, default_value_t)]
+  pub report: ReportFormat,
+
+  /// How many rows to send to the database in each transaction
+  #[arg(long, default_value_t = DEFAULT_BATCH_SIZE, value_parser = parse_batch_size)]
+  pub batch_size: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct OrgArgs {
+  #[command(flatten)]
+  pub import: ImportArgs,
+
+  /// Wipe the database before importing. The submissions are wiped too, since they can't be
+  /// linked to the organizations without them.
+  #[arg(long, conflicts_with = "dry_run")]
+  pub replace: bool,
+
+  #[command(flatten)]
+  pub guard: Guard,
+}
+
+#[derive(Args, Debug)]
+pub struct SubmissionArgs {
+  #[command(flatten)]
+  pub import: ImportArgs,
+
+  /// Check the submitting orgs against this organizations file instead of the database. Needed
+  /// for a dry run
+  #[arg(long, required_if_eq("dry_run", "true"))]
+  pub orgs: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+  /// The organizations file to check
+  #[arg(long, required_unless_present = "submissions")]
+  pub orgs: Option<PathBuf>,
+
+  /// The submissions file to check. With the organizations, unknown submitting orgs are found too
+  #[arg(long)]
+  pub submissions: Option<PathBuf>,
+
+  /// How to print the problems found in the files
+  #[arg(long, value_enum, default_value_t)]
+  pub report: ReportFormat,
+
+  #[command(flatten)]
+  pub format: FileFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct FileFormat {
+  /// The column separator: "tab", "comma" or any single character
+  #[arg(long, short, default_value = "tab", value_parser = parse_delimiter)]
+  pub delimiter: u8,
+
+  /// A TOML file naming the header of each column. Without it, the InvoicerUI column order is used
+  #[arg(long, short)]
+  pub mapping: Option<PathBuf>,
+
+  /// How to read dates like 3/10/2024. Without it, the order is worked out from the file
+  #[arg(long, value_enum)]
+  pub date_order: Option<DateOrder>,
+}
+
+impl FileFormat {
+  pub fn mapping(&self) -> AWResult<Mapping> {
+    match &self.mapping {
+      Some(path) => Mapping::load(path),
+      None => Ok(Mapping::default()),
+    }
+  }
+}
+
+fn parse_batch_size(value: &str) -> Result<usize, String> {
+  match value.parse() {
+    Ok(0) | Err(_) => Err(format!(
+      "'{}' is not a batch size. Use a whole number above 0",
+      value
+    )),
+    Ok(size) => Ok(size),
+  }
+}
+
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+  match value {
+    "tab" | "\\t" => Ok(b'\t'),
+    "comma" => Ok(b','),
+    other if other.len() == 1 => Ok(other.as_bytes()[0]),
+    other => Err(format!(
+      "'{}' is not a delimiter. Use \"tab\", \"comma\" or a single character",
+      other
+    )),
+  }
+}
+
+/// Protects operations that can't be undone
+#[derive(Args, Debug)]
+pub struct Guard {
+  /// Don't ask before deleting data. Required when not running in a terminal
+  #[arg(long, short)]
+  pub yes: bool,
+}
+
+impl Guard {
+  /// Ask on the terminal before going ahead with the action
+  pub fn confirm(&self, action: &str) -> AWResult<()> {
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    self.confirm_with(
+      action,
+      interactive,
+      &mut stdin.lock(),
+      &mut std::io::stderr(),
+    )
+  }
+
+  /// Only an answer of "yes" goes ahead. Anything else, including no terminal to ask on, refuses.
+  pub fn confirm_with(
+    &self,
+    action: &str,
+    interactive: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+  ) -> AWResult<()> {
+    let refused = |reason: &str| {
+      let err: AllWhat<WranglerErrorKind> = PermissionDenied.into();
+      Err(err.set_context(&format!("Did not {}: {}", action, reason)))
+    };
+
+    if self.yes {
+      return Ok(());
+    }
+    if !interactive {
+      return refused("pass --yes to confirm when not running in a terminal");
+    }
+
+    write!(output, "This will {}. Type 'yes' to continue: ", action)?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    match answer.trim() {
+      "yes" => Ok(()),
+      _ => refused("it was not confirmed"),
+    }
+  }
+}
+
+pub async fn run(cli: Cli) -> AWResult<()> {
+  let connection = &cli.connection;
+  match cli.command {
+    Command::Import { what } => match what {
+      ImportCommand::Orgs(OrgArgs {
+        import: args,
+        replace,
+        guard,
+      }) => {
+        let started = Instant::now();
+        let mapping = args.format.mapping()?;
+        let mut orgs =
+          reader::load_orgs(&args.file, args.format.delimiter, &mapping.organizations)?;
+        let hierarchy = hierarchy::check(&mut orgs);
+        let go_on = checked(&args, &orgs);
+        if args.dry_run && args.report == ReportFormat::Text {
+          print!("\n{}", hierarchy);
+        }
+        if !go_on.map_err(|err| stopped(err, 0))? {
+          return Ok(());
+        }
+        let conn = connect(connection, &args).await?;
+        if replace {
+          guard.confirm(&format!(
+            "delete everything in {} before importing '{}'",
+            connection.uri,
+            args.file.display()
+          ))?;
+          writer::wipe(&conn).await?;
+        }
+        let summary = writer::insert_orgs(&conn, &orgs.rows).await?;
+        writer::map_children(&conn, &hierarchy).await?;
+        print!("{}", summary);
+        finished(orgs.read, started);
+      }
+      ImportCommand::Submissions(SubmissionArgs { import: args, orgs }) => {
+        let started = Instant::now();
+        let mapping = args.format.mapping()?;
+        let subs = reader::Submissions::open(
+          &args.file,
+          args.format.delimiter,
+          &mapping.submissions,
+          args.format.date_order,
+        )?;
+
+        // Submissions from an unknown org would be written without a link to it
+        let conn = match args.dry_run {
+          true => None,
+          false => Some(connect(connection, &args).await?),
+        };
+        let known = match (&orgs, &conn) {
+          (Some(path), _) => {
+            reader::load_orgs(path, args.format.delimiter, &mapping.organizations)?.pretty_ids()
+          }
+          (None, Some(conn)) => writer::org_ids(conn).await?,
+          // A dry run is always given the organizations file
+          (None, None) => HashSet::new(),
+        };
+        let mut subs = subs.with_orgs(known);
+
+        // Once a row is rejected nothing more is written, but the rest of the file is still
+        // checked so the report lists every problem
+        let mut writing = conn.as_ref().map(writer::SubmissionWriter::new);
+        while let Some(sub) = subs.next() {
+          let sub = sub?;
+          if let (Some(writing), false) = (&mut writing, subs.has_errors()) {
+            writing.push(sub).await?;
+          }
+        }
+
+        let written = writing
+          .as_ref()
+          .map_or(0, writer::SubmissionWriter::written);
+        let go_on = checked(&args, &subs).map_err(|err| stopped(err, written));
+        let (Some(writing), true) = (writing, go_on?) else {
+          return Ok(());
+        };
+        for summary in writing.finish().await? {
+          print!("{}", summary);
+        }
+        finished(subs.read, started);
+      }
+    },
+
+    Command::Validate(args) => validate(&args)?,
+
+    Command::Wipe(guard) => {
+      guard.confirm(&format!("delete everything in {}", connection.uri))?;
+      writer::wipe(&connection.connect().await?).await?;
+      println!("Wiped {}", connection.uri);
+    }
+  }
+  Ok(())
+}
+
+/// How long the whole import took, from opening the file to the last write
+fn finished(rows: usize, started: Instant) {
+  let elapsed = started.elapsed().as_secs_f64();
+  println!(
+    "Imported {} rows in {:.2}s ({:.0} rows/s)",
+    rows,
+    elapsed,
+    rows as f64 / elapsed.max(f64::EPSILON)
+  );
+}
+
+/// Report the problems found in the file, and whether to go on with the import
+fn checked(args: &ImportArgs, file: &impl Checked) -> AWResult<bool> {
+  let mut report = Report::default();
+  report.add(&args.file, file);
+  if args.dry_run || !file.issues().is_empty() {
+    print!("{}", report.render(args.report)?);
+  }
+  report.check()?;
+  Ok(!args.dry_run)
+}
+
+/// Say how much of the file made it into the database before the problems in it stopped the import
+fn stopped(err: AllWhat<WranglerErrorKind>, written: usize) -> AllWhat<WranglerErrorKind> {
+  let problems = err.get_context().unwrap_or_default().to_string();
+  err.set_context(&match written {
+    0 => format!("{}. Nothing was written", problems),
+    written => format!(
+      "{}. The first {} submissions were written, and importing the fixed file carries on from there",
+      problems, written
+    ),
+  })
+}
+
+/// Open the database for an import, bringing the data of earlier imports up to date first
+async fn connect(connection: &ConnectionArgs, args: &ImportArgs) -> AWResult<Neo4jConnection> {
+  let conn = connection.connect().await?.with_batch_size(args.batch_size);
+  writer::migrate(&conn).await?;
+  Ok(conn)
+}
+
+/// Read the files and report every problem in them
+fn validate(args: &ValidateArgs) -> AWResult<()> {
+  let delimiter = args.format.delimiter;
+  let mapping = args.format.mapping()?;
+  let mut report = Report::default();
+
+  let mut tree = None;
+  let orgs = match &args.orgs {
+    Some(path) => {
+      let mut orgs = reader::load_orgs(path, delimiter, &mapping.organizations)?;
+      tree = Some(hierarchy::check(&mut orgs));
+      Some(orgs)
+    }
+    None => None,
+  };
+  if let (Some(path), Some(orgs)) = (&args.orgs, &orgs) {
+    report.add(path, orgs);
+  }
+
+  if let Some(path) = &args.submissions {
+    let mut subs = reader::Submissions::open(
+      path,
+      delimiter,
+      &mapping.submissions,
+      args.format.date_order,
+    )?;
+    if let Some(orgs) = &orgs {
+      subs = subs.with_orgs(orgs.pretty_ids());
+    }
+    for sub in subs.by_ref() {
+      sub?;
+    }
+    report.add(path, &subs);
+  }
+
+  print!("{}", report.render(args.report)?);
+  if let (Some(tree), ReportFormat::Text) = (&tree, args.report) {
+    print!("\n{}", tree);
+  }
+  report.check()
+}
@@ -0,0 +1,126 @@
+//! Read the dates in the historical exports
+//!
+//! The submission log has been kept in a few tools over the years, so a date can be written as
+//! `3/10/2024`, `2024-03-10` or an Excel serial like `45361`. Slashed dates are read month first
+//! like the InvoicerUI export, unless the file shows otherwise. In a file that has both orders, a
+//! date like `3/10/2024` is reported instead of picking one of its readings.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// The order of the day and month in a date written with slashes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+pub enum DateOrder {
+  MonthFirst,
+  DayFirst,
+}
+
+/// What a value in a date column turned out to be
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reading {
+  Date(NaiveDate),
+  /// The value is a date in either order, and nothing says which one was meant
+  Ambiguous(Vec<NaiveDate>),
+  Invalid,
+}
+
+/// Excel counts days from here, once past the 29th of February 1900 that it wrongly thinks exists
+const EXCEL_EPOCH: (i32, u32, u32) = (1899, 12, 30);
+
+/// The largest serial Excel shows as a date, 9999-12-31
+const EXCEL_LAST: f64 = 2_958_465.0;
+
+/// Read a date. Slashed dates use the given order, or are ambiguous when the order is unknown.
+pub fn parse(value: &str, order: Option<DateOrder>) -> Reading {
+  let value = value.trim();
+  if let Some(date) = iso(value) {
+    return Reading::Date(date);
+  }
+  if let Some((first, second, year)) = slashed(value) {
+    let month_first = NaiveDate::from_ymd_opt(year, first, second);
+    let day_first = NaiveDate::from_ymd_opt(year, second, first);
+    return match (order, month_first, day_first) {
+      (Some(DateOrder::MonthFirst), Some(date), _) | (Some(DateOrder::DayFirst), _, Some(date)) => {
+        Reading::Date(date)
+      }
+      (Some(_), _, _) => Reading::Invalid,
+      (None, Some(a), Some(b)) if a != b => Reading::Ambiguous(vec![a, b]),
+      (None, Some(date), _) | (None, None, Some(date)) => Reading::Date(date),
+      (None, None, None) => Reading::Invalid,
+    };
+  }
+  match excel(value) {
+    Some(date) => Reading::Date(date),
+    None => Reading::Invalid,
+  }
+}
+
+/// `2024-03-10`, with or without a time of day
+fn iso(value: &str) -> Option<NaiveDate> {
+  NaiveDate::parse_from_str(value, "%Y-%m-%d")
+    .ok()
+    .or_else(|| {
+      ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|datetime| datetime.date())
+    })
+}
+
+/// The parts of `3/10/2024` in the order they were written. Years need all four digits.
+fn slashed(value: &str) -> Option<(u32, u32, i32)> {
+  let mut parts = value.split('/');
+  let first = parts.next()?;
+  let second = parts.next()?;
+  let year = parts.next()?;
+  let digits = |part: &str, max: usize| {
+    !part.is_empty() && part.len() <= max && part.chars().all(|c| c.is_ascii_digit())
+  };
+  if parts.next().is_some() || !digits(first, 2) || !digits(second, 2) || year.len() != 4 {
+    return None;
+  }
+  Some((
+    first.parse().ok()?,
+    second.parse().ok()?,
+    year.parse().ok()?,
+  ))
+}
+
+/// A day counted the way Excel does. The time of day in the fraction is dropped.
+fn excel(value: &str) -> Option<NaiveDate> {
+  let serial: f64 = value.parse().ok()?;
+  // Serials up to 60 fall before Excel's phantom leap day and are a day off, so they aren't trusted
+  if !(61.0..=EXCEL_LAST).contains(&serial) {
+    return None;
+  }
+  let (year, month, day) = EXCEL_EPOCH;
+  NaiveDate::from_ymd_opt(year, month, day)?.checked_add_signed(Duration::days(serial as i64))
+}
+
+/// Works out the order of the slashed dates in a file from the ones that can only be read one way
+#[derive(Clone, Debug, Default)]
+pub struct Detector {
+  month_first: usize,
+  day_first: usize,
+}
+
+impl Detector {
+  pub fn see(&mut self, value: &str) {
+    if let Some((first, second, _)) = slashed(value.trim()) {
+      match (first > 12, second > 12) {
+        (false, true) => self.month_first += 1,
+        (true, false) => self.day_first += 1,
+        _ => (),
+      }
+    }
+  }
+
+  /// The order of the dates seen, or None when they were written both ways
+  pub fn order(&self) -> Option<DateOrder> {
+    match (self.month_first, self.day_first) {
+      (_, 0) => Some(DateOrder::MonthFirst),
+      (0, _) => Some(DateOrder::DayFirst),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,107 @@
+//! The connection to the Neo4j database being loaded
+
+use neo4rs::*;
+use std::{collections::HashMap, sync::Arc};
+use wrangler_common::{
+  configuration::{apps::neo4j::Neo4jConfig, traits::Configuration},
+  prelude::{AllWhat, Result as AWResult},
+};
+
+use crate::progress::{Progress, Throughput};
+
+/// How many rows are sent in each batch unless told otherwise
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+#[derive(Clone)]
+pub struct Neo4jConnection {
+  graph: Arc<Graph>,
+  batch_size: usize,
+}
+
+/// A row to be bound to `$rows` in an unwind query
+pub fn row<const N: usize>(fields: [(&str, BoltType); N]) -> BoltType {
+  let fields: HashMap<String, BoltType> = fields
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect();
+  fields.into()
+}
+
+impl Neo4jConnection {
+  pub async fn connect(config: &Neo4jConfig) -> AWResult<Neo4jConnection> {
+    let uri = config.get_uri();
+    let graph = Graph::new(&uri, config.get_username(), config.get_password().value())
+      .await
+      .map_err(|err| {
+        AllWhat::from(err).set_context(&format!("Could not connect to Neo4j at {}", uri))
+      })?;
+
+    Ok(Neo4jConnection {
+      graph: Arc::new(graph),
+      batch_size: DEFAULT_BATCH_SIZE,
+    })
+  }
+
+  /// Send the rows of unwind queries this many at a time
+  pub fn with_batch_size(mut self, batch_size: usize) -> Neo4jConnection {
+    self.batch_size = batch_size.max(1);
+    self
+  }
+
+  pub fn batch_size(&self) -> usize {
+    self.batch_size
+  }
+
+  /// Run the queries in a single transaction, rolling it back if any of them fail
+  pub async fn exec<Q: Into<Query>>(&self, queries: Vec<Q>) -> AWResult<()> {
+    let mut txn = self.graph.start_txn().await?;
+    if let Err(err) = txn.run_queries(queries).await {
+      let _ = txn.rollback().await;
+      return Err(AllWhat::from(err).set_context("A query failed, so nothing was written"));
+    }
+    Ok(txn.commit().await?)
+  }
+
+  /// Run a query starting with `UNWIND $rows AS row` over the rows, one batch at a time.
+  ///
+  /// Each batch is its own transaction, so a failure leaves the earlier batches written. As the
+  /// importer merges rather than creates, importing the file again carries on from there.
+  pub async fn unwind(
+    &self,
+    label: &str,
+    cypher: &str,
+    rows: Vec<BoltType>,
+  ) -> AWResult<Throughput> {
+    let mut progress = Progress::new(label, rows.len());
+    for (index, batch) in rows.chunks(self.batch_size).enumerate() {
+      let mut txn = self.graph.start_txn().await?;
+      if let Err(err) = txn.run(query(cypher).param("rows", batch)).await {
+        let _ = txn.rollback().await;
+        return Err(AllWhat::from(err).set_context(&format!(
+          "Batch {} of the {} rows failed. The {} rows before it were written",
+          index + 1,
+          label,
+          progress.done()
+        )));
+      }
+      txn.commit().await?;
+      progress.advance(batch.len());
+    }
+
+    let throughput = progress.finish();
+    if throughput.rows > 0 {
+      eprintln!("{}", throughput);
+    }
+    Ok(throughput)
+  }
+
+  /// Read every row returned by a query
+  pub async fn rows(&self, q: Query) -> AWResult<Vec<Row>> {
+    let mut result = self.graph.execute(q).await?;
+    let mut rows = Vec::new();
+    while let Some(row) = result.next().await? {
+      rows.push(row);
+    }
+    Ok(rows)
+  }
+}
@@ -0,0 +1,169 @@
+//! The parent/child tree of the organizations
+//!
+//! Each org lists the source ids of its children. The lists are checked before any edges are
+//! written: a child that isn't in the file is skipped, while an org listed under more than one
+//! parent or that ends up as its own ancestor stops the import.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::reader::{Loaded, Organization, Problem};
+
+/// The edges that can be written, along with what is needed to print them as a tree
+#[derive(Clone, Debug, Default)]
+pub struct Hierarchy {
+  /// The parent of each child, by source id
+  pub parents: BTreeMap<i32, i32>,
+  /// How each org is shown in the tree
+  labels: BTreeMap<i32, String>,
+}
+
+impl Hierarchy {
+  /// Each (parent, child) pair
+  pub fn edges(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+    self.parents.iter().map(|(child, parent)| (*parent, *child))
+  }
+
+  /// Every org placed in the tree, by source id
+  pub fn orgs(&self) -> impl Iterator<Item = i32> + '_ {
+    self.labels.keys().copied()
+  }
+
+  /// The orgs without a parent, in the order they are printed
+  pub fn roots(&self) -> Vec<i32> {
+    self.sorted(
+      self
+        .labels
+        .keys()
+        .filter(|id| !self.parents.contains_key(id))
+        .copied(),
+    )
+  }
+
+  pub fn children(&self, parent: i32) -> Vec<i32> {
+    self.sorted(
+      self
+        .edges()
+        .filter(|edge| edge.0 == parent)
+        .map(|edge| edge.1),
+    )
+  }
+
+  fn sorted(&self, ids: impl Iterator<Item = i32>) -> Vec<i32> {
+    let mut ids: Vec<i32> = ids.collect();
+    ids.sort_by_key(|id| &self.labels[id]);
+    ids
+  }
+
+  fn branch(&self, f: &mut std::fmt::Formatter<'_>, parent: i32, indent: &str) -> std::fmt::Result {
+    let children = self.children(parent);
+    for (index, child) in children.iter().enumerate() {
+      let last = index + 1 == children.len();
+      let (tee, rest) = match last {
+        true => ("└── ", "    "),
+        false => ("├── ", "│   "),
+      };
+      writeln!(f, "{}{}{}", indent, tee, self.labels[child])?;
+      self.branch(f, *child, &format!("{}{}", indent, rest))?;
+    }
+    Ok(())
+  }
+}
+
+impl std::fmt::Display for Hierarchy {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for root in self.roots() {
+      writeln!(f, "{}", self.labels[&root])?;
+      self.branch(f, root, "")?;
+    }
+    Ok(())
+  }
+}
+
+/// Check the children listed by each org, adding the problems to the issues and dropping the orgs
+/// that can't be placed in the tree
+pub fn check(orgs: &mut Loaded<i32, Organization>) -> Hierarchy {
+  let mut ids: Vec<i32> = orgs.rows.keys().copied().collect();
+  ids.sort();
+  let mut problems: Vec<(i32, Problem)> = Vec::new();
+
+  // Every org that lists each child
+  let mut listed: BTreeMap<i32, BTreeSet<i32>> = BTreeMap::new();
+  for id in &ids {
+    for child in &orgs.rows[id].children {
+      match orgs.rows.contains_key(child) {
+        true => {
+          listed.entry(*child).or_default().insert(*id);
+        }
+        false => problems.push((*id, Problem::MissingChild { value: *child })),
+      }
+    }
+  }
+
+  let mut rejected: BTreeSet<i32> = BTreeSet::new();
+  let mut parents: BTreeMap<i32, i32> = BTreeMap::new();
+  for (child, by) in listed {
+    match by.len() {
+      1 => {
+        parents.insert(child, *by.first().unwrap());
+      }
+      _ => {
+        let parents = by
+          .iter()
+          .map(|id| orgs.rows[id].pretty_id.clone())
+          .collect();
+        problems.push((child, Problem::MultipleParents { parents }));
+        rejected.insert(child);
+      }
+    }
+  }
+
+  // Walk up from each org. Coming back to an org seen on the same walk closes a loop
+  let mut done: BTreeSet<i32> = BTreeSet::new();
+  for id in &ids {
+    let mut walk: Vec<i32> = Vec::new();
+    let mut current = Some(*id);
+    while let Some(org) = current.filter(|org| !done.contains(org)) {
+      if let Some(start) = walk.iter().position(|seen| *seen == org) {
+        // From the top of the loop down, starting with the lowest id so each loop is reported once
+        let mut cycle: Vec<i32> = walk[start..].iter().rev().copied().collect();
+        let lowest = (0..cycle.len()).min_by_key(|index| cycle[*index]).unwrap();
+        cycle.rotate_left(lowest);
+
+        let path = cycle
+          .iter()
+          .map(|id| orgs.rows[id].pretty_id.clone())
+          .collect();
+        problems.push((cycle[0], Problem::Cycle { path }));
+        rejected.extend(cycle);
+        break;
+      }
+      walk.push(org);
+      current = parents.get(&org).copied();
+    }
+    done.extend(walk);
+  }
+
+  for (id, problem) in problems {
+    let org = &orgs.rows[&id];
+    let (line, key) = (org.line, org.source_id.to_string());
+    orgs.push(line, &key, vec![problem]);
+  }
+  orgs.issues.sort_by_key(|issue| issue.line);
+
+  orgs.rows.retain(|id, _| !rejected.contains(id));
+  parents.retain(|child, parent| orgs.rows.contains_key(child) && orgs.rows.contains_key(parent));
+  let guids: BTreeMap<i32, uuid::Uuid> =
+    orgs.rows.iter().map(|(id, org)| (*id, org.guid)).collect();
+  for (id, org) in orgs.rows.iter_mut() {
+    org.parent = parents.get(id).map(|parent| guids[parent]);
+  }
+
+  Hierarchy {
+    parents,
+    labels: orgs
+      .rows
+      .iter()
+      .map(|(id, org)| (*id, format!("{} ({})", org.pretty_id, org.name)))
+      .collect(),
+  }
+}
@@ -0,0 +1,13 @@
+//! A port of the internal database used by InvoicerUI. This is used to build an explicit graph from
+//! the FHL submission log.
+
+pub mod cli;
+pub mod dates;
+pub mod grapht;
+pub mod hierarchy;
+pub mod mapping;
+pub mod merge;
+pub mod progress;
+pub mod reader;
+pub mod report;
+pub mod writer;
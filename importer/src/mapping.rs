@@ -0,0 +1,166 @@
+//! Which column of an input file holds each field
+//!
+//! A mapping file names the header of each column, so a reshuffled export still loads the right
+//! values. Each section is optional, and falls back to the column positions of the InvoicerUI
+//! export when missing:
+//!
+//! ```toml
+//! [organizations]
+//! source_id = "Id"
+//! pretty_id = "Short Name"
+//! name = "Name"
+//! children = "Children"
+//! ```
+//!
+//! A column can also be given as its position, counting from 0.
+
+use std::path::Path;
+
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+use wrangler_common::prelude::{
+  AllWhat, ConfigError, Result as AWResult, ValidationError, WranglerErrorKind,
+};
+
+/// Where to find a field in a file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Column {
+  Index(usize),
+  Header(String),
+}
+
+impl Column {
+  /// The position of the column in a file with the given headers
+  pub fn find(&self, field: &str, headers: &StringRecord) -> AWResult<usize> {
+    let missing = |msg: String| {
+      let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+      Err(err.set_context(&format!("'{}' {}", field, msg)))
+    };
+
+    match self {
+      Column::Index(index) if *index < headers.len() => Ok(*index),
+      Column::Index(index) => missing(format!(
+        "is mapped to column {}, but there are only {} columns",
+        index,
+        headers.len()
+      )),
+      Column::Header(name) => {
+        let found: Vec<usize> = headers
+          .iter()
+          .enumerate()
+          .filter(|(_, header)| header.trim() == name)
+          .map(|(index, _)| index)
+          .collect();
+        match found[..] {
+          [index] => Ok(index),
+          [] => missing(format!(
+            "is mapped to the header '{}', which is not in the file",
+            name
+          )),
+          _ => missing(format!(
+            "is mapped to the header '{}', which appears {} times",
+            name,
+            found.len()
+          )),
+        }
+      }
+    }
+  }
+}
+
+/// Declare the fields read from a file, along with their position in the InvoicerUI export
+macro_rules! columns {
+  (
+    $(#[$meta:meta])*
+    $name:ident { $($field:ident = $index:expr),* $(,)? }
+  ) => {
+    $(#[$meta])*
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct $name<C = Column> {
+      $(pub $field: C,)*
+    }
+
+    impl Default for $name {
+      fn default() -> Self {
+        $name { $($field: Column::Index($index),)* }
+      }
+    }
+
+    impl $name {
+      /// Find every column in the headers, reporting all of the ones that can't be found
+      pub fn resolve(&self, headers: &StringRecord) -> AWResult<$name<usize>> {
+        $(let $field = self.$field.find(stringify!($field), headers);)*
+        AllWhat::flatten(ValidationError, vec![$($field.clone()),*])?;
+        Ok($name { $($field: $field?,)* })
+      }
+    }
+  };
+}
+
+columns! {
+  /// The columns of the organizations file
+  OrgColumns {
+    source_id = 0,
+    pretty_id = 1,
+    name = 2,
+    children = 4,
+  }
+}
+
+columns! {
+  /// The columns of the submissions file
+  SubmissionColumns {
+    accession_number = 0,
+    submitting_org = 2,
+    submitted_by = 3,
+    category = 4,
+    line_items = 5,
+    species = 6,
+    pet_name = 8,
+    received_on = 9,
+    finalized_on = 10,
+    diagnosis = 17,
+    total = 19,
+    billed_on = 21,
+    paid_on = 23,
+    deposited_on = 25,
+    invoice_number = 27,
+  }
+}
+
+/// The columns for each kind of file
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+  #[serde(default)]
+  pub organizations: OrgColumns,
+
+  #[serde(default)]
+  pub submissions: SubmissionColumns,
+}
+
+impl Mapping {
+  pub fn parse(text: &str) -> AWResult<Mapping> {
+    toml::from_str(text).map_err(|err| {
+      let result: AllWhat<WranglerErrorKind> = ConfigError.into();
+      result
+        .set_context(&format!(
+          "Could not read the column mapping: {}",
+          err.message()
+        ))
+        .set_dev_context(&format!("From <toml>:\n{}", err))
+    })
+  }
+
+  pub fn load(path: &Path) -> AWResult<Mapping> {
+    let text = std::fs::read_to_string(path).map_err(|err| {
+      AllWhat::from(err).set_context(&format!(
+        "Could not read the column mapping '{}'",
+        path.display()
+      ))
+    })?;
+    Mapping::parse(&text)
+  }
+}
@@ -0,0 +1,317 @@
+//! Re-import the same files without losing the edits made in the app
+//!
+//! Every record gets a guid derived from its id in the source, so importing a row again finds the
+//! node made the last time. Each node keeps a copy of the values it was last imported with, which
+//! tells apart a field edited in the app from a field changed in the source:
+//!
+//! | In the database | In the file   | Result                               |
+//! |-----------------|---------------|--------------------------------------|
+//! | as imported     | as imported   | unchanged                            |
+//! | as imported     | changed       | updated                              |
+//! | edited          | as imported   | unchanged, keeping the edit          |
+//! | edited          | changed       | conflict, keeping the edit           |
+
+use std::collections::{BTreeMap, HashMap};
+
+use neo4rs::BoltType;
+use serde_json::Value;
+use uuid::Uuid;
+use wrangler_common::{
+  model::ModelNode,
+  prelude::{AllWhat, Result as AWResult},
+};
+
+use crate::grapht::{row, Neo4jConnection};
+
+/// The property holding the values a node was last imported with, as JSON
+pub const IMPORTED: &str = "imported";
+
+// The labels of the nodes the importer writes, which also keep their guids apart
+pub const ORGANIZATION: &str = ModelNode::Organization.label();
+pub const SUBMISSION: &str = ModelNode::Submission.label();
+pub const SERVICE: &str = ModelNode::LineItem.label();
+pub const INVOICE: &str = ModelNode::Invoice.label();
+pub const PAYMENT: &str = ModelNode::Payment.label();
+
+/// All the guids made by the importer are in this namespace
+fn namespace(label: &str) -> Uuid {
+  let root = Uuid::new_v5(
+    &Uuid::NAMESPACE_URL,
+    b"https://github.com/The-Process-Foundry/SubmissionWrangler",
+  );
+  Uuid::new_v5(&root, label.as_bytes())
+}
+
+/// The same guid every time a record with this id is imported
+pub fn guid(label: &str, source_id: &str) -> Uuid {
+  Uuid::new_v5(&namespace(label), source_id.as_bytes())
+}
+
+pub type Fields = BTreeMap<String, Value>;
+
+/// A row to be written as a node
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+  pub guid: Uuid,
+  /// How the row is known in the source, for the summary
+  pub key: String,
+  pub fields: Fields,
+}
+
+/// A node already in the database
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Existing {
+  pub properties: Fields,
+  /// The fields as they were last imported, if the node was made by the importer
+  pub imported: Option<Fields>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+  Created,
+  Updated,
+  Unchanged,
+  Conflict,
+}
+
+/// A field that was edited in the app and changed in the file since the last import
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+  pub key: String,
+  pub field: String,
+  pub in_database: Value,
+  pub in_file: Value,
+}
+
+/// What importing a record will do
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+  pub status: Status,
+  /// The fields to write
+  pub changes: Fields,
+  /// The values to remember as imported
+  pub imported: Fields,
+  pub conflicts: Vec<Conflict>,
+}
+
+impl Outcome {
+  /// Whether anything needs to be written
+  pub fn writes(&self, existing: Option<&Existing>) -> bool {
+    !self.changes.is_empty()
+      || existing.and_then(|node| node.imported.as_ref()) != Some(&self.imported)
+  }
+}
+
+/// Compare a record from the file with the node from the last import
+pub fn diff(record: &Record, existing: Option<&Existing>) -> Outcome {
+  let existing = match existing {
+    Some(existing) => existing,
+    None => {
+      return Outcome {
+        status: Status::Created,
+        changes: record.fields.clone(),
+        imported: record.fields.clone(),
+        conflicts: Vec::new(),
+      }
+    }
+  };
+
+  let mut outcome = Outcome {
+    status: Status::Unchanged,
+    changes: Fields::new(),
+    imported: Fields::new(),
+    conflicts: Vec::new(),
+  };
+  for (field, in_file) in &record.fields {
+    let in_database = existing.properties.get(field).unwrap_or(&Value::Null);
+    // A node made before the importer kept its values is treated as never edited
+    let base = match &existing.imported {
+      Some(imported) => imported.get(field).unwrap_or(&Value::Null),
+      None => in_database,
+    };
+
+    let edited = in_database != base;
+    let changed = in_file != base;
+    let remembered = match (in_file == in_database, edited, changed) {
+      (true, _, _) | (false, true, false) => in_file,
+      (false, false, _) => {
+        outcome.changes.insert(field.clone(), in_file.clone());
+        in_file
+      }
+      (false, true, true) => {
+        outcome.conflicts.push(Conflict {
+          key: record.key.clone(),
+          field: field.clone(),
+          in_database: in_database.clone(),
+          in_file: in_file.clone(),
+        });
+        // Keep reporting the conflict until someone settles it
+        base
+      }
+    };
+    outcome.imported.insert(field.clone(), remembered.clone());
+  }
+
+  outcome.status = match (outcome.conflicts.is_empty(), outcome.changes.is_empty()) {
+    (false, _) => Status::Conflict,
+    (true, false) => Status::Updated,
+    (true, true) => Status::Unchanged,
+  };
+  outcome
+}
+
+/// How many records ended up in each state, and the conflicts that need a person to look at them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+  pub label: String,
+  pub counts: BTreeMap<Status, usize>,
+  pub conflicts: Vec<Conflict>,
+}
+
+impl Summary {
+  pub fn new(label: &str) -> Summary {
+    Summary {
+      label: label.to_string(),
+      ..Default::default()
+    }
+  }
+
+  pub fn count(&self, status: Status) -> usize {
+    self.counts.get(&status).copied().unwrap_or(0)
+  }
+
+  pub fn add(&mut self, outcome: &Outcome) {
+    *self.counts.entry(outcome.status).or_default() += 1;
+    self.conflicts.extend(outcome.conflicts.iter().cloned());
+  }
+}
+
+impl std::fmt::Display for Summary {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      "{}: {} created, {} updated, {} unchanged, {} conflicts",
+      self.label,
+      self.count(Status::Created),
+      self.count(Status::Updated),
+      self.count(Status::Unchanged),
+      self.count(Status::Conflict),
+    )?;
+    for conflict in &self.conflicts {
+      writeln!(
+        f,
+        "  {} '{}': kept {} from the app instead of {} from the file",
+        conflict.key, conflict.field, conflict.in_database, conflict.in_file
+      )?;
+    }
+    Ok(())
+  }
+}
+
+fn bolt(value: &Value) -> BoltType {
+  match value {
+    Value::Bool(value) => (*value).into(),
+    Value::Number(number) => match number.as_i64() {
+      Some(value) => value.into(),
+      None => number.as_f64().unwrap_or_default().into(),
+    },
+    Value::String(value) => value.as_str().into(),
+    // Nodes only hold the simple values written by the importer
+    Value::Null | Value::Array(_) | Value::Object(_) => None::<String>.into(),
+  }
+}
+
+/// The nodes with the given guids, keyed by guid. They are looked up a batch at a time.
+async fn fetch(
+  conn: &Neo4jConnection,
+  label: &str,
+  guids: Vec<String>,
+) -> AWResult<HashMap<String, Existing>> {
+  let mut rows = Vec::new();
+  for batch in guids.chunks(conn.batch_size()) {
+    let query = neo4rs::query(&format!(
+      "MATCH (n:{}) WHERE n.guid IN $guids RETURN n.guid AS guid, properties(n) AS properties",
+      label
+    ))
+    .param("guids", batch);
+    rows.extend(conn.rows(query).await?);
+  }
+
+  rows
+    .into_iter()
+    .map(|row| {
+      let guid: String = row.get("guid")?;
+      let mut properties: Fields = row.get("properties")?;
+      let imported = match properties.remove(IMPORTED) {
+        Some(Value::String(json)) => Some(serde_json::from_str(&json).map_err(|err| {
+          AllWhat::from(err).set_context(&format!(
+            "The imported values of {} {} could not be read",
+            label, guid
+          ))
+        })?),
+        _ => None,
+      };
+      Ok((
+        guid,
+        Existing {
+          properties,
+          imported,
+        },
+      ))
+    })
+    .collect()
+}
+
+/// Work out what importing each record will do, adding the outcomes to the summary. Returns the
+/// rows for the [upsert] query, leaving out the nodes that are already up to date.
+pub async fn plan(
+  conn: &Neo4jConnection,
+  label: &str,
+  records: &[Record],
+  summary: &mut Summary,
+) -> AWResult<Vec<BoltType>> {
+  let guids = records
+    .iter()
+    .map(|record| record.guid.to_string())
+    .collect();
+  let existing = fetch(conn, label, guids).await?;
+
+  let mut writes: Vec<BoltType> = Vec::new();
+  for record in records {
+    let guid = record.guid.to_string();
+    let node = existing.get(&guid);
+    let outcome = diff(record, node);
+    summary.add(&outcome);
+    if !outcome.writes(node) {
+      continue;
+    }
+
+    let changes: HashMap<String, BoltType> = outcome
+      .changes
+      .iter()
+      .map(|(field, value)| (field.clone(), bolt(value)))
+      .collect();
+    writes.push(row([
+      ("guid", guid.into()),
+      ("changes", changes.into()),
+      ("imported", serde_json::to_string(&outcome.imported)?.into()),
+    ]));
+  }
+  Ok(writes)
+}
+
+/// Write the rows worked out by [plan]
+pub fn upsert(label: &str) -> String {
+  format!(
+    "UNWIND $rows AS row MERGE (n:{} {{guid: row.guid}}) SET n += row.changes, n.{} = row.imported",
+    label, IMPORTED
+  )
+}
+
+/// Create or update a node for each record, leaving the fields edited in the app alone
+pub async fn merge(conn: &Neo4jConnection, label: &str, records: &[Record]) -> AWResult<Summary> {
+  let mut summary = Summary::new(label);
+  let writes = plan(conn, label, records, &mut summary).await?;
+  conn.unwind(label, &upsert(label), writes).await?;
+  Ok(summary)
+}
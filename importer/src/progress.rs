@@ -0,0 +1,110 @@
+//! Show how far along a write is, and how fast it went
+
+use std::{
+  io::{IsTerminal, Write},
+  time::{Duration, Instant},
+};
+
+/// The rows written in one step of an import and the time they took
+#[derive(Clone, Debug, PartialEq)]
+pub struct Throughput {
+  pub label: String,
+  pub rows: usize,
+  pub batches: usize,
+  pub elapsed: Duration,
+}
+
+impl Throughput {
+  pub fn per_second(&self) -> f64 {
+    match self.elapsed.as_secs_f64() {
+      secs if secs > 0.0 => self.rows as f64 / secs,
+      _ => 0.0,
+    }
+  }
+}
+
+impl std::fmt::Display for Throughput {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}: {} rows in {} batches, {:.2}s ({:.0} rows/s)",
+      self.label,
+      self.rows,
+      self.batches,
+      self.elapsed.as_secs_f64(),
+      self.per_second()
+    )
+  }
+}
+
+/// A line on stderr counting up the rows written. It is only drawn when stderr is a terminal, so
+/// logs don't fill up with partial lines.
+pub struct Progress {
+  label: String,
+  /// The rows to write, unless they are still being read
+  total: Option<usize>,
+  done: usize,
+  batches: usize,
+  started: Instant,
+  visible: bool,
+}
+
+impl Progress {
+  pub fn new(label: &str, total: usize) -> Progress {
+    Progress {
+      label: label.to_string(),
+      total: Some(total),
+      done: 0,
+      batches: 0,
+      started: Instant::now(),
+      visible: std::io::stderr().is_terminal(),
+    }
+  }
+
+  /// For rows written as they are read from a file, before the total is known
+  pub fn open_ended(label: &str) -> Progress {
+    Progress {
+      total: None,
+      ..Progress::new(label, 0)
+    }
+  }
+
+  /// The number of rows written so far
+  pub fn done(&self) -> usize {
+    self.done
+  }
+
+  pub fn advance(&mut self, rows: usize) {
+    self.done += rows;
+    self.batches += 1;
+    if self.visible {
+      match self.total {
+        Some(total) => {
+          let percent = match total {
+            0 => 100,
+            total => self.done * 100 / total,
+          };
+          eprint!(
+            "\r{}: {}/{} rows ({}%)",
+            self.label, self.done, total, percent
+          );
+        }
+        None => eprint!("\r{}: {} rows", self.label, self.done),
+      }
+      let _ = std::io::stderr().flush();
+    }
+  }
+
+  pub fn finish(self) -> Throughput {
+    if self.visible {
+      // Clear the progress line so the summary replaces it
+      eprint!("\r\x1b[2K");
+    }
+    Throughput {
+      label: self.label,
+      rows: self.done,
+      batches: self.batches,
+      elapsed: self.started.elapsed(),
+    }
+  }
+}
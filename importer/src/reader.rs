@@ -0,0 +1,827 @@
+//! Temporary flattened model used for reading/writing csv data
+
+use std::{
+  collections::{HashMap, HashSet, VecDeque},
+  path::Path,
+  sync::OnceLock,
+};
+
+use chrono::NaiveDate;
+use csv::StringRecord;
+use regex::Regex;
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use wrangler_common::prelude::{AllWhat, Result as AWResult, ResultPlus};
+
+use crate::{
+  dates::{self, DateOrder, Detector, Reading},
+  mapping::{OrgColumns, SubmissionColumns},
+  merge,
+};
+
+#[derive(Clone, Debug)]
+pub struct Organization {
+  pub guid: uuid::Uuid,
+  pub source_id: i32,
+  pub pretty_id: String,
+  pub name: String,
+  /// Filled in once the hierarchy has been checked
+  pub parent: Option<uuid::Uuid>,
+  /// The source ids of the child organizations
+  pub children: Vec<i32>,
+  pub raw: String,
+  /// Where the row is in the file
+  pub line: u64,
+}
+
+#[derive(Debug)]
+pub struct Submission {
+  pub guid: uuid::Uuid,
+  pub accession_number: String,
+  pub submitting_org: String,
+  pub submitted_by: String,
+  pub category: String,
+  pub line_items: Vec<LineItemRow>,
+  pub species: String,
+  pub pet_name: Option<String>,
+  pub diagnosis: Option<String>,
+  pub total: Decimal,
+  pub received_on: Option<NaiveDate>,
+  pub finalized_on: Option<NaiveDate>,
+  pub billed_on: Option<NaiveDate>,
+  pub paid_on: Option<NaiveDate>,
+  pub deposited_on: Option<NaiveDate>,
+  pub invoice_number: Option<i32>,
+  /// Where the row is in the file
+  pub line: u64,
+}
+
+/// A single service listed in the line items of a submission
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineItemRow {
+  pub name: String,
+  pub quantity: Decimal,
+  pub price: Decimal,
+}
+
+/// Something wrong with a row of an input file
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum Problem {
+  /// The row doesn't have the same number of columns as the header
+  WrongLength {
+    expected: u64,
+    found: u64,
+  },
+  MissingColumn {
+    field: String,
+  },
+  BadNumber {
+    field: String,
+    value: String,
+  },
+  BadDecimal {
+    field: String,
+    value: String,
+  },
+  /// An amount of money with a fraction of a cent, which would have to be rounded to be stored
+  FractionOfCent {
+    field: String,
+    value: String,
+  },
+  BadDate {
+    field: String,
+    value: String,
+  },
+  /// Part of the line items isn't written as `["name", quantity, price]`
+  BadLineItem {
+    value: String,
+  },
+  /// A cell isn't valid UTF-8, so the row can't be read
+  NotText {
+    column: u64,
+  },
+  /// The date reads both ways in a file where the day and month aren't always in the same order
+  AmbiguousDate {
+    field: String,
+    value: String,
+    readings: Vec<NaiveDate>,
+  },
+  /// The key was already used by an earlier row
+  Duplicate {
+    field: String,
+    value: String,
+    first_line: u64,
+  },
+  /// The submitting org is not in the organizations file or the database
+  UnknownOrg {
+    value: String,
+  },
+  /// A child listed by the org is not in the file. The rest of the org is still imported.
+  MissingChild {
+    value: i32,
+  },
+  /// More than one org lists this one as a child
+  MultipleParents {
+    parents: Vec<String>,
+  },
+  /// The org is its own ancestor, through the orgs listed from the top of the loop
+  Cycle {
+    path: Vec<String>,
+  },
+  /// The line items add up to a different amount than the total. The row is still imported.
+  LineItemTotal {
+    total: Decimal,
+    line_items: Decimal,
+  },
+}
+
+impl Problem {
+  /// Whether the problem stops the row from being imported
+  pub fn is_error(&self) -> bool {
+    !matches!(
+      self,
+      Problem::LineItemTotal { .. } | Problem::MissingChild { .. }
+    )
+  }
+}
+
+impl std::fmt::Display for Problem {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Problem::WrongLength { expected, found } => {
+        write!(f, "Expected {} columns but found {}", expected, found)
+      }
+      Problem::MissingColumn { field } => write!(f, "The '{}' column is missing", field),
+      Problem::BadNumber { field, value } => {
+        write!(f, "'{}' is not a valid whole number for {}", value, field)
+      }
+      Problem::BadDecimal { field, value } => {
+        write!(f, "'{}' is not a valid amount for {}", value, field)
+      }
+      Problem::FractionOfCent { field, value } => write!(
+        f,
+        "'{}' for {} is not a whole number of cents",
+        value, field
+      ),
+      Problem::BadDate { field, value } => {
+        write!(f, "'{}' is not a valid date for {}", value, field)
+      }
+      Problem::BadLineItem { value } => write!(
+        f,
+        "'{}' is not a line item. Write them as [\"name\", quantity, price]",
+        value
+      ),
+      Problem::NotText { column } => write!(f, "Column {} is not valid UTF-8 text", column),
+      Problem::AmbiguousDate {
+        field,
+        value,
+        readings,
+      } => {
+        let readings: Vec<String> = readings.iter().map(NaiveDate::to_string).collect();
+        write!(
+          f,
+          "'{}' could be {} for {}, as the file has dates in both orders. Pass --date-order to pick one",
+          value,
+          readings.join(" or "),
+          field
+        )
+      }
+      Problem::Duplicate {
+        field,
+        value,
+        first_line,
+      } => write!(
+        f,
+        "The {} '{}' was already used on line {}",
+        field, value, first_line
+      ),
+      Problem::UnknownOrg { value } => write!(f, "There is no organization '{}'", value),
+      Problem::MissingChild { value } => {
+        write!(f, "The child organization {} is not in the file", value)
+      }
+      Problem::MultipleParents { parents } => write!(
+        f,
+        "The organization is a child of each of {}, but can only have one parent",
+        parents.join(", ")
+      ),
+      Problem::Cycle { path } => write!(
+        f,
+        "The organization is its own ancestor: {} -> {}",
+        path.join(" -> "),
+        path.first().map(String::as_str).unwrap_or_default()
+      ),
+      Problem::LineItemTotal { total, line_items } => write!(
+        f,
+        "The line items add up to {}, but the total is {}",
+        line_items, total
+      ),
+    }
+  }
+}
+
+/// A problem and the row it was found on
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Issue {
+  pub line: u64,
+  /// The id or accession number of the row, when it could be read
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub key: Option<String>,
+  pub problem: Problem,
+}
+
+impl std::fmt::Display for Issue {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.key {
+      Some(key) => write!(f, "Line {} ({}): {}", self.line, key, self.problem),
+      None => write!(f, "Line {}: {}", self.line, self.problem),
+    }
+  }
+}
+
+/// The rows that could be read from a file, and everything wrong with the ones that couldn't
+#[derive(Debug)]
+pub struct Loaded<K, T> {
+  pub rows: HashMap<K, T>,
+  /// The number of rows in the file, including the rejected ones
+  pub read: usize,
+  pub issues: Vec<Issue>,
+}
+
+impl<K, T> Loaded<K, T> {
+  pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+    self.issues.iter().filter(|issue| issue.problem.is_error())
+  }
+
+  pub(crate) fn push(&mut self, line: u64, key: &str, problems: Vec<Problem>) {
+    self
+      .issues
+      .extend(problems.into_iter().map(|problem| Issue {
+        line,
+        key: Some(key.to_string()),
+        problem,
+      }));
+  }
+}
+
+/// Reads the fields of a record, collecting the problems instead of stopping at the first one
+struct Row<'a> {
+  record: &'a StringRecord,
+  problems: Vec<Problem>,
+}
+
+impl<'a> Row<'a> {
+  fn new(record: &'a StringRecord) -> Row<'a> {
+    Row {
+      record,
+      problems: Vec::new(),
+    }
+  }
+
+  fn text(&mut self, index: usize, field: &str) -> &'a str {
+    match self.record.get(index) {
+      Some(value) => value,
+      None => {
+        self.problems.push(Problem::MissingColumn {
+          field: field.to_string(),
+        });
+        ""
+      }
+    }
+  }
+
+  /// A column where an empty cell means there is no value
+  fn optional(&mut self, index: usize, field: &str) -> Option<&'a str> {
+    match self.text(index, field) {
+      "" => None,
+      value => Some(value),
+    }
+  }
+
+  fn number<T: FromStr>(&mut self, value: &str, field: &str) -> Option<T> {
+    let parsed = value.trim().parse().ok();
+    if parsed.is_none() {
+      self.problems.push(Problem::BadNumber {
+        field: field.to_string(),
+        value: value.to_string(),
+      });
+    }
+    parsed
+  }
+
+  fn decimal(&mut self, value: &str, field: &str) -> Option<Decimal> {
+    let parsed = Decimal::from_str(value.trim()).ok();
+    if parsed.is_none() {
+      self.problems.push(Problem::BadDecimal {
+        field: field.to_string(),
+        value: value.to_string(),
+      });
+    }
+    parsed
+  }
+
+  /// An amount of money, kept to the cent
+  fn amount(&mut self, value: &str, field: &str) -> Option<Decimal> {
+    let mut amount = self.decimal(value, field)?;
+    if amount.round_dp(2) != amount {
+      self.problems.push(Problem::FractionOfCent {
+        field: field.to_string(),
+        value: value.to_string(),
+      });
+      return None;
+    }
+    amount.rescale(2);
+    Some(amount)
+  }
+
+  /// An optional date, with slashed dates read in the given order
+  fn date(&mut self, index: usize, field: &str, order: Option<DateOrder>) -> Option<NaiveDate> {
+    let value = self.optional(index, field)?;
+    let problem = match dates::parse(value, order) {
+      Reading::Date(date) => return Some(date),
+      Reading::Ambiguous(readings) => Problem::AmbiguousDate {
+        field: field.to_string(),
+        value: value.to_string(),
+        readings,
+      },
+      Reading::Invalid => Problem::BadDate {
+        field: field.to_string(),
+        value: value.to_string(),
+      },
+    };
+    self.problems.push(problem);
+    None
+  }
+
+  /// The child ids, written as a list like `[12, 31]`
+  fn children(&mut self, value: &str) -> Vec<i32> {
+    let value = value.trim();
+    let inner = value
+      .strip_prefix('[')
+      .and_then(|value| value.strip_suffix(']'))
+      .unwrap_or(value);
+    inner
+      .split(',')
+      .map(str::trim)
+      .filter(|child| !child.is_empty())
+      .filter_map(|child| self.number(child, "children"))
+      .collect()
+  }
+
+  /// The line items in the cell. Any text that isn't part of one is reported rather than skipped.
+  fn line_items(&mut self, value: &str) -> Vec<LineItemRow> {
+    let mut items = Vec::new();
+    let mut read = 0;
+    for captures in line_item_pattern().captures_iter(value) {
+      let whole = captures.get(0).unwrap();
+      self.unread(&value[read..whole.start()]);
+      read = whole.end();
+
+      let (_, [name, quantity, price]) = captures.extract();
+      let quantity = self.decimal(quantity, &format!("the quantity of '{}'", name));
+      let price = self.amount(price, &format!("the price of '{}'", name));
+      if let (Some(quantity), Some(price)) = (quantity, price) {
+        items.push(LineItemRow {
+          name: name.to_string(),
+          quantity,
+          price,
+        });
+      }
+    }
+    self.unread(&value[read..]);
+    items
+  }
+
+  /// Text left over between the line items
+  fn unread(&mut self, text: &str) {
+    let text = text.trim_matches(|c: char| c.is_whitespace() || c == ',');
+    if !text.is_empty() {
+      self.problems.push(Problem::BadLineItem {
+        value: text.to_string(),
+      });
+    }
+  }
+
+  fn has_errors(&self) -> bool {
+    self.problems.iter().any(Problem::is_error)
+  }
+}
+
+/// Line items are written as a list of `["name", quantity, price]`. The amounts are checked once
+/// they are found, so a bad one is reported as a bad amount.
+fn line_item_pattern() -> &'static Regex {
+  static PATTERN: OnceLock<Regex> = OnceLock::new();
+  PATTERN.get_or_init(|| {
+    Regex::new(
+      r#"(?x)
+        \[
+          \s*"(?<name>[^"]+)"
+          \s*,\s*
+          (?<quantity>[^,\[\]"]+?)
+          \s*,\s*
+          (?<price>[^,\[\]"]+?)
+          \s*
+        \]\s*,?\s*
+      "#,
+    )
+    .unwrap()
+  })
+}
+
+/// Open the file and check its headers against the mapping before any rows are read
+fn open<C: Clone>(
+  path: &Path,
+  delimiter: u8,
+  resolve: impl FnOnce(&StringRecord) -> AWResult<C>,
+) -> AWResult<(csv::Reader<std::fs::File>, C)> {
+  let mut rdr = csv::ReaderBuilder::new()
+    .delimiter(delimiter)
+    .has_headers(true)
+    .from_path(path)
+    .map_err(|err| {
+      AllWhat::from(err).set_context(&format!("Could not open '{}'", path.display()))
+    })?;
+  let columns = resolve(rdr.headers()?).set_context(&format!(
+    "The headers of '{}' don't match the column mapping",
+    path.display()
+  ))?;
+  Ok((rdr, columns))
+}
+
+/// What reading the next record of a file found
+enum Next {
+  /// A record was read into the buffer, from this line
+  Row(u64),
+  /// The row couldn't be read, and was added to the issues
+  Unreadable,
+  End,
+}
+
+/// Read the next record into a reused buffer, turning rows with the wrong number of columns or
+/// that aren't text into issues
+fn next_record(
+  rdr: &mut csv::Reader<std::fs::File>,
+  record: &mut StringRecord,
+  issues: &mut Vec<Issue>,
+) -> AWResult<Next> {
+  let err = match rdr.read_record(record) {
+    Ok(false) => return Ok(Next::End),
+    Ok(true) => {
+      return Ok(Next::Row(
+        record.position().map(|pos| pos.line()).unwrap_or(0),
+      ))
+    }
+    Err(err) => err,
+  };
+  let (pos, problem) = match err.kind() {
+    csv::ErrorKind::UnequalLengths {
+      pos,
+      expected_len,
+      len,
+    } => (
+      pos,
+      Problem::WrongLength {
+        expected: *expected_len,
+        found: *len,
+      },
+    ),
+    csv::ErrorKind::Utf8 { pos, err } => (
+      pos,
+      Problem::NotText {
+        column: err.field() as u64 + 1,
+      },
+    ),
+    _ => return Err(err.into()),
+  };
+  issues.push(Issue {
+    line: pos.as_ref().map(|pos| pos.line()).unwrap_or(0),
+    key: None,
+    problem,
+  });
+  Ok(Next::Unreadable)
+}
+
+/// Read the records one at a time into a reused buffer, keeping the rows that pass in memory
+fn read_rows<K, T>(
+  rdr: &mut csv::Reader<std::fs::File>,
+  mut each: impl FnMut(&mut Loaded<K, T>, &StringRecord, u64),
+) -> AWResult<Loaded<K, T>> {
+  let mut loaded = Loaded {
+    rows: HashMap::new(),
+    read: 0,
+    issues: Vec::new(),
+  };
+  let mut record = StringRecord::new();
+  loop {
+    match next_record(rdr, &mut record, &mut loaded.issues)? {
+      Next::End => break,
+      Next::Unreadable => loaded.read += 1,
+      Next::Row(line) => {
+        loaded.read += 1;
+        each(&mut loaded, &record, line);
+      }
+    }
+  }
+  Ok(loaded)
+}
+
+/// The whole file is kept, as the hierarchy can only be checked once every org is known
+pub fn load_orgs(
+  path: &Path,
+  delimiter: u8,
+  columns: &OrgColumns,
+) -> AWResult<Loaded<i32, Organization>> {
+  let (mut rdr, cols) = open(path, delimiter, |headers| columns.resolve(headers))?;
+  // The line each id was first used on, even when that row was rejected
+  let mut seen = HashMap::new();
+
+  read_rows(
+    &mut rdr,
+    |loaded: &mut Loaded<i32, Organization>, record, line| {
+      let mut row = Row::new(record);
+      let raw_id = row.text(cols.source_id, "source_id");
+      let source_id = row.number(raw_id, "source_id");
+      let org = Organization {
+        guid: merge::guid(merge::ORGANIZATION, raw_id.trim()),
+        source_id: source_id.unwrap_or_default(),
+        pretty_id: row.text(cols.pretty_id, "pretty_id").to_string(),
+        name: row.text(cols.name, "name").to_string(),
+        parent: None,
+        children: {
+          let raw_children = row.text(cols.children, "children");
+          row.children(raw_children)
+        },
+        raw: format!("{:#?}", record),
+        line,
+      };
+
+      if let Some(id) = source_id {
+        let first_line = *seen.entry(id).or_insert(line);
+        if first_line != line {
+          row.problems.push(Problem::Duplicate {
+            field: "source_id".to_string(),
+            value: raw_id.to_string(),
+            first_line,
+          });
+        }
+      }
+
+      let failed = row.has_errors();
+      loaded.push(line, raw_id, row.problems);
+      if !failed {
+        loaded.rows.insert(org.source_id, org);
+      }
+    },
+  )
+}
+
+impl Loaded<i32, Organization> {
+  /// The ids submissions use to name the org that sent them
+  pub fn pretty_ids(&self) -> HashSet<String> {
+    self
+      .rows
+      .values()
+      .map(|org| org.pretty_id.clone())
+      .collect()
+  }
+}
+
+/// Read the submissions one row at a time, so a log of any size can be imported in batches.
+///
+/// Each row is checked as it is read, and only the ones that can be imported come out of the
+/// iterator. The problems are gathered in [Submissions::issues]. Apart from those, only the
+/// accession numbers and the ids of the known orgs are kept, along with the rows that have to wait
+/// for the end of the file to know the order of their dates.
+pub struct Submissions {
+  rdr: csv::Reader<std::fs::File>,
+  cols: SubmissionColumns<usize>,
+  /// The order of slashed dates, once it is known
+  order: Option<DateOrder>,
+  detector: Detector,
+  /// The pretty ids of the orgs that can send submissions, when they are checked
+  known: Option<HashSet<String>>,
+  record: StringRecord,
+  /// The line each accession number was first used on, even when that row was rejected
+  seen: HashMap<String, u64>,
+  /// Rows with a date that reads either way, along with their line, read once the whole file has
+  /// shown which order it uses
+  held: VecDeque<(StringRecord, u64)>,
+  ended: bool,
+  /// The number of rows in the file so far, including the rejected ones
+  pub read: usize,
+  /// The rows that can be imported, including the ones with warnings
+  pub accepted: usize,
+  rejected: usize,
+  pub issues: Vec<Issue>,
+}
+
+impl Submissions {
+  /// Slashed dates are read in the given order, or the order used in the file
+  pub fn open(
+    path: &Path,
+    delimiter: u8,
+    columns: &SubmissionColumns,
+    dates: Option<DateOrder>,
+  ) -> AWResult<Submissions> {
+    let (rdr, cols) = open(path, delimiter, |headers| columns.resolve(headers))?;
+    Ok(Submissions {
+      rdr,
+      cols,
+      order: dates,
+      detector: Detector::default(),
+      known: None,
+      record: StringRecord::new(),
+      seen: HashMap::new(),
+      held: VecDeque::new(),
+      ended: false,
+      read: 0,
+      accepted: 0,
+      rejected: 0,
+      issues: Vec::new(),
+    })
+  }
+
+  /// Reject the submissions sent by an org that isn't one of these pretty ids
+  pub fn with_orgs(mut self, known: HashSet<String>) -> Submissions {
+    self.known = Some(known);
+    self
+  }
+
+  /// Whether a row has been rejected so far
+  pub fn has_errors(&self) -> bool {
+    self.rejected > 0
+  }
+
+  /// The values in the date columns of a record
+  fn dates<'a>(&self, record: &'a StringRecord) -> impl Iterator<Item = &'a str> {
+    let cols = &self.cols;
+    [
+      cols.received_on,
+      cols.finalized_on,
+      cols.billed_on,
+      cols.paid_on,
+      cols.deposited_on,
+    ]
+    .into_iter()
+    .filter_map(|index| record.get(index))
+  }
+
+  /// Check the record and turn it into a submission, unless it has to be rejected
+  fn check(&mut self, record: &StringRecord, line: u64) -> Option<Submission> {
+    let cols = &self.cols;
+    let order = self.order;
+    let mut row = Row::new(record);
+    let accession_number = row.text(cols.accession_number, "accession_number");
+    let raw_total = row.text(cols.total, "total");
+    let total = row.amount(raw_total, "total");
+    let raw_items = row.text(cols.line_items, "line_items");
+    let line_items = row.line_items(raw_items);
+    let invoice_number = row
+      .optional(cols.invoice_number, "invoice_number")
+      .and_then(|value| row.number(value, "invoice_number"));
+
+    let sub = Submission {
+      guid: merge::guid(merge::SUBMISSION, accession_number),
+      accession_number: accession_number.to_string(),
+      submitting_org: row.text(cols.submitting_org, "submitting_org").to_string(),
+      submitted_by: row.text(cols.submitted_by, "submitted_by").to_string(),
+      category: row.text(cols.category, "category").to_string(),
+      line_items,
+      species: row.text(cols.species, "species").to_string(),
+      pet_name: row.optional(cols.pet_name, "pet_name").map(str::to_string),
+      diagnosis: row
+        .optional(cols.diagnosis, "diagnosis")
+        .map(str::to_string),
+      total: total.unwrap_or_default(),
+      received_on: row.date(cols.received_on, "received_on", order),
+      finalized_on: row.date(cols.finalized_on, "finalized_on", order),
+      billed_on: row.date(cols.billed_on, "billed_on", order),
+      paid_on: row.date(cols.paid_on, "paid_on", order),
+      deposited_on: row.date(cols.deposited_on, "deposited_on", order),
+      invoice_number,
+      line,
+    };
+
+    // Verify individual items total the submission total
+    let items_total: Decimal = sub
+      .line_items
+      .iter()
+      .map(|item| item.quantity * item.price)
+      .sum();
+    if total.is_some() && items_total != sub.total {
+      row.problems.push(Problem::LineItemTotal {
+        total: sub.total,
+        line_items: items_total,
+      });
+    }
+
+    let first_line = self.seen.get(accession_number).copied().unwrap_or(line);
+    if first_line != line {
+      row.problems.push(Problem::Duplicate {
+        field: "accession_number".to_string(),
+        value: accession_number.to_string(),
+        first_line,
+      });
+    }
+
+    if let Some(known) = &self.known {
+      if !known.contains(&sub.submitting_org) {
+        row.problems.push(Problem::UnknownOrg {
+          value: sub.submitting_org.clone(),
+        });
+      }
+    }
+
+    let failed = row.has_errors();
+    self
+      .issues
+      .extend(row.problems.into_iter().map(|problem| Issue {
+        line,
+        key: Some(accession_number.to_string()),
+        problem,
+      }));
+    match failed {
+      true => {
+        self.rejected += 1;
+        None
+      }
+      false => {
+        self.accepted += 1;
+        Some(sub)
+      }
+    }
+  }
+
+  /// Read the next row of the file, returning its submission if it can be imported now
+  fn read_row(&mut self, record: &mut StringRecord) -> AWResult<Option<Submission>> {
+    let line = match next_record(&mut self.rdr, record, &mut self.issues)? {
+      Next::End => {
+        self.ended = true;
+        self.order = self.order.or_else(|| self.detector.order());
+        return Ok(None);
+      }
+      Next::Unreadable => {
+        self.read += 1;
+        self.rejected += 1;
+        return Ok(None);
+      }
+      Next::Row(line) => line,
+    };
+    self.read += 1;
+
+    // The accession number is claimed by the first row using it, even one that is held back
+    if let Some(accession_number) = record.get(self.cols.accession_number) {
+      self
+        .seen
+        .entry(accession_number.to_string())
+        .or_insert(line);
+    }
+
+    // Every date in the file counts towards its order, but only a date that reads either way needs
+    // the order to be read
+    let mut ambiguous = false;
+    for value in self.dates(record) {
+      self.detector.see(value);
+      ambiguous |=
+        self.order.is_none() && matches!(dates::parse(value, None), Reading::Ambiguous(_));
+    }
+    if ambiguous {
+      self.held.push_back((record.clone(), line));
+      return Ok(None);
+    }
+    Ok(self.check(record, line))
+  }
+}
+
+impl Iterator for Submissions {
+  type Item = AWResult<Submission>;
+
+  fn next(&mut self) -> Option<AWResult<Submission>> {
+    loop {
+      if self.ended {
+        let (record, line) = match self.held.pop_front() {
+          Some(held) => held,
+          None => {
+            // The held rows were read last, so put their problems back in line order
+            self.issues.sort_by_key(|issue| issue.line);
+            return None;
+          }
+        };
+        match self.check(&record, line) {
+          Some(sub) => return Some(Ok(sub)),
+          None => continue,
+        }
+      }
+
+      // The buffer is taken out while a row is read into it, as checking the row needs the rest
+      let mut record = std::mem::take(&mut self.record);
+      let read = self.read_row(&mut record);
+      self.record = record;
+      match read {
+        Ok(Some(sub)) => return Some(Ok(sub)),
+        Ok(None) => continue,
+        Err(err) => return Some(Err(err)),
+      }
+    }
+  }
+}
@@ -0,0 +1,134 @@
+//! Everything found wrong with the input files, and how many of their rows can be imported
+
+use std::{fmt::Display, path::Path};
+
+use serde::Serialize;
+use wrangler_common::prelude::{AllWhat, ImportRowError, Result as AWResult, WranglerErrorKind};
+
+use crate::reader::{Issue, Loaded, Submissions};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+  /// One line per problem, for people
+  #[default]
+  Text,
+  /// For other tools
+  Json,
+}
+
+/// A file that has been read through, and what was found in it
+pub trait Checked {
+  /// The number of rows in the file, including the rejected ones
+  fn read(&self) -> usize;
+  /// The rows that can be imported, including the ones with warnings
+  fn accepted(&self) -> usize;
+  fn issues(&self) -> &[Issue];
+}
+
+impl<K, T> Checked for Loaded<K, T> {
+  fn read(&self) -> usize {
+    self.read
+  }
+
+  fn accepted(&self) -> usize {
+    self.rows.len()
+  }
+
+  fn issues(&self) -> &[Issue] {
+    &self.issues
+  }
+}
+
+impl Checked for Submissions {
+  fn read(&self) -> usize {
+    self.read
+  }
+
+  fn accepted(&self) -> usize {
+    self.accepted
+  }
+
+  fn issues(&self) -> &[Issue] {
+    &self.issues
+  }
+}
+
+/// The rows read from a single file
+#[derive(Clone, Debug, Serialize)]
+pub struct FileReport {
+  pub path: String,
+  pub rows: usize,
+  /// The rows that can be imported, including the ones with warnings
+  pub accepted: usize,
+  pub errors: usize,
+  pub warnings: usize,
+  pub issues: Vec<Issue>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+  pub files: Vec<FileReport>,
+}
+
+impl Report {
+  pub fn add(&mut self, path: &Path, file: &impl Checked) {
+    let issues = file.issues();
+    let errors = issues
+      .iter()
+      .filter(|issue| issue.problem.is_error())
+      .count();
+    self.files.push(FileReport {
+      path: path.display().to_string(),
+      rows: file.read(),
+      accepted: file.accepted(),
+      errors,
+      warnings: issues.len() - errors,
+      issues: issues.to_vec(),
+    });
+  }
+
+  pub fn errors(&self) -> usize {
+    self.files.iter().map(|file| file.errors).sum()
+  }
+
+  pub fn render(&self, format: ReportFormat) -> AWResult<String> {
+    match format {
+      ReportFormat::Text => Ok(self.to_string()),
+      ReportFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+    }
+  }
+
+  /// Refuse to import anything when a row was rejected, so a file is never partly loaded
+  pub fn check(&self) -> AWResult<()> {
+    match self.errors() {
+      0 => Ok(()),
+      count => {
+        let err: AllWhat<WranglerErrorKind> = ImportRowError.into();
+        Err(err.set_context(&format!(
+          "{} problems stop the files from being imported",
+          count
+        )))
+      }
+    }
+  }
+}
+
+impl Display for Report {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for file in &self.files {
+      writeln!(
+        f,
+        "'{}': {} of {} rows can be imported, with {} errors and {} warnings",
+        file.path, file.accepted, file.rows, file.errors, file.warnings
+      )?;
+      for issue in &file.issues {
+        let severity = match issue.problem.is_error() {
+          true => "error",
+          false => "warning",
+        };
+        writeln!(f, "  {:<8}{}", severity, issue)?;
+      }
+    }
+    Ok(())
+  }
+}
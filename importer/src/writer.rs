@@ -0,0 +1,669 @@
+//! Write the rows read from the files into the database
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use chrono::NaiveDate;
+use neo4rs::query;
+use rust_decimal::Decimal;
+use serde_json::json;
+use wrangler_common::{
+  model::{line_item::LineItem, payment::PaymentType, ModelEdge, ModelNode},
+  prelude::{Result as AWResult, ResultPlus},
+};
+
+use crate::{
+  grapht::{row, Neo4jConnection},
+  hierarchy::Hierarchy,
+  merge,
+  progress::Progress,
+  reader,
+};
+
+/// Remove every node and relationship from the database
+pub async fn wipe(conn: &Neo4jConnection) -> AWResult<()> {
+  conn
+    .exec(vec!["MATCH (n) DETACH DELETE n".to_string()])
+    .await
+}
+
+/// The relationship types written by earlier versions of the importer, and the model's type for each
+pub const RENAMED_EDGES: [(&str, ModelEdge); 4] = [
+  ("Submitted", ModelEdge::SubmissionOrganization),
+  ("SubmittedBy", ModelEdge::SubmissionSender),
+  ("LineItem", ModelEdge::SubmissionLineItem),
+  ("BelongsTo", ModelEdge::LineItemSubmission),
+];
+
+/// The queries renaming the relationships written by earlier versions of the importer to the types
+/// of the model. They change nothing once everything has been renamed.
+pub fn migrations() -> Vec<String> {
+  RENAMED_EDGES
+    .iter()
+    .map(|(old, edge)| {
+      format!(
+        "MATCH (a)-[old:{}]->(b)
+         MERGE (a)-[new:{}]->(b)
+         SET new += properties(old)
+         DELETE old",
+        old,
+        edge.label()
+      )
+    })
+    .collect()
+}
+
+/// Bring the relationships of earlier imports up to date, so the server finds them
+pub async fn migrate(conn: &Neo4jConnection) -> AWResult<()> {
+  conn.exec(migrations()).await
+}
+
+/// The pretty ids of the organizations already in the database, which submissions are linked by
+pub async fn org_ids(conn: &Neo4jConnection) -> AWResult<HashSet<String>> {
+  let query = format!(
+    "MATCH (o:{}) WHERE o.pretty_id IS NOT NULL RETURN o.pretty_id AS pretty_id",
+    ModelNode::Organization.label()
+  );
+  let rows = conn.rows(neo4rs::query(&query)).await?;
+  rows.iter().map(|row| Ok(row.get("pretty_id")?)).collect()
+}
+
+/// The fields of an organization that come from the file
+pub fn org_record(org: &reader::Organization) -> merge::Record {
+  let fields = [
+    ("source_id", json!(org.source_id)),
+    ("pretty_id", json!(org.pretty_id)),
+    ("name", json!(org.name)),
+  ];
+  merge::Record {
+    guid: org.guid,
+    key: org.pretty_id.clone(),
+    fields: fields
+      .into_iter()
+      .map(|(field, value)| (field.to_string(), value))
+      .collect(),
+  }
+}
+
+/// Create the organizations that are new and update the ones that changed since the last import
+pub async fn insert_orgs(
+  conn: &Neo4jConnection,
+  orgs: &HashMap<i32, reader::Organization>,
+) -> AWResult<merge::Summary> {
+  let records: Vec<merge::Record> = orgs.values().map(org_record).collect();
+  merge::merge(conn, merge::ORGANIZATION, &records).await
+}
+
+/// Link each org to its parent, removing the links made by an earlier import that the file no
+/// longer lists
+pub async fn map_children(conn: &Neo4jConnection, hierarchy: &Hierarchy) -> AWResult<()> {
+  println!("Adding in relationships to the orgs");
+
+  let parents = hierarchy
+    .orgs()
+    .map(|child| {
+      let parent: Vec<i32> = hierarchy.parents.get(&child).copied().into_iter().collect();
+      row([("child", child.into()), ("parents", parent.into())])
+    })
+    .collect::<Vec<_>>();
+  let links = hierarchy
+    .edges()
+    .map(|(parent, child)| row([("parent", parent.into()), ("child", child.into())]))
+    .collect();
+
+  // Only the links this importer made are marked, so the ones added in the app stay
+  let removed = [
+    format!(
+      "UNWIND $rows AS row
+       MATCH (p:{org})-[r:{parent_of}]->(:{org} {{source_id: row.child}})
+       WHERE r.{imported} IS NOT NULL AND NOT p.source_id IN row.parents
+       DELETE r",
+      org = ModelNode::Organization.label(),
+      parent_of = ModelEdge::OrganizationParent.label(),
+      imported = merge::IMPORTED
+    ),
+    format!(
+      "UNWIND $rows AS row
+       MATCH (:{org} {{source_id: row.child}})-[r:{child_of}]->(p:{org})
+       WHERE r.{imported} IS NOT NULL AND NOT p.source_id IN row.parents
+       DELETE r",
+      org = ModelNode::Organization.label(),
+      child_of = ModelEdge::OrganizationChild.label(),
+      imported = merge::IMPORTED
+    ),
+  ];
+  for cypher in removed {
+    conn
+      .unwind("Removed organization links", &cypher, parents.clone())
+      .await?;
+  }
+  conn
+    .unwind(
+      "Organization links",
+      &format!(
+        " UNWIND $rows AS row
+          MATCH (p:{org} {{source_id: row.parent}})
+          MATCH (c:{org} {{source_id: row.child}})
+          MERGE (p)-[down:{parent_of}]->(c)
+          ON CREATE SET down.{imported} = true
+          MERGE (c)-[up:{child_of}]->(p)
+          ON CREATE SET up.{imported} = true
+        ",
+        org = ModelNode::Organization.label(),
+        parent_of = ModelEdge::OrganizationParent.label(),
+        child_of = ModelEdge::OrganizationChild.label(),
+        imported = merge::IMPORTED
+      ),
+      links,
+    )
+    .await?;
+  Ok(())
+}
+
+/// The fields of a submission that come from the file
+pub fn sub_record(sub: &reader::Submission) -> merge::Record {
+  let fields = [
+    ("accession_number", json!(sub.accession_number)),
+    ("category", json!(sub.category)),
+    ("submitted_by", json!(sub.submitted_by)),
+    ("species", json!(sub.species)),
+    ("pet_name", json!(sub.pet_name)),
+    ("diagnosis", json!(sub.diagnosis)),
+    ("total", amount(sub.total)),
+    ("received_on", json!(sub.received_on)),
+    ("finalized_on", json!(sub.finalized_on)),
+    ("billed_on", json!(sub.billed_on)),
+    ("paid_on", json!(sub.paid_on)),
+    ("deposited_on", json!(sub.deposited_on)),
+    ("invoice_number", json!(sub.invoice_number)),
+  ];
+  merge::Record {
+    guid: sub.guid,
+    key: sub.accession_number.clone(),
+    fields: fields
+      .into_iter()
+      .map(|(field, value)| (field.to_string(), value))
+      .collect(),
+  }
+}
+
+/// Amounts are stored as decimal strings, the same as the model, so no cents are lost
+fn amount(value: Decimal) -> serde_json::Value {
+  json!(value.to_string())
+}
+
+/// The services performed for a submission, in the order they were listed
+pub fn line_items(sub: &reader::Submission) -> Vec<LineItem> {
+  sub
+    .line_items
+    .iter()
+    .enumerate()
+    .map(|(index, row)| LineItem {
+      guid: merge::guid(
+        merge::SERVICE,
+        &format!("{}/{}", sub.accession_number, index),
+      ),
+      name: row.name.clone(),
+      quantity: row.quantity,
+      price: row.price,
+      started_on: sub.received_on,
+      finished_on: sub.finalized_on,
+      paid: sub.paid_on.is_some(),
+    })
+    .collect()
+}
+
+/// The fields of a line item that come from the file
+pub fn service_record(sub: &reader::Submission, index: usize, item: &LineItem) -> merge::Record {
+  let fields = [
+    ("name", json!(item.name)),
+    ("quantity", amount(item.quantity)),
+    ("price", amount(item.price)),
+    ("started_on", json!(item.started_on)),
+    ("finished_on", json!(item.finished_on)),
+    ("paid", json!(item.paid)),
+  ];
+  merge::Record {
+    guid: item.guid,
+    key: format!("{} #{}", sub.accession_number, index + 1),
+    fields: fields
+      .into_iter()
+      .map(|(field, value)| (field.to_string(), value))
+      .collect(),
+  }
+}
+
+/// The queries run on each batch of submissions after they are written. They link each submission
+/// to the org that sent it, remove the services dropped from its row since the last import and
+/// link it to its services.
+fn submission_steps() -> [String; 3] {
+  [
+    format!(
+      "UNWIND $rows AS row
+       MATCH (o:{org} {{pretty_id: row.org}})
+       MATCH (s:{sub} {{guid: row.guid}})
+       MERGE (o)-[:{submitted}]->(s)
+       MERGE (s)-[:{sent_by}]->(o)
+      ",
+      org = ModelNode::Organization.label(),
+      sub = ModelNode::Submission.label(),
+      submitted = ModelEdge::SubmissionOrganization.label(),
+      sent_by = ModelEdge::SubmissionSender.label()
+    ),
+    // Only the services made by the importer go, so the ones added in the app stay
+    format!(
+      "UNWIND $rows AS row
+       MATCH (:{sub} {{guid: row.sub}})-[:{line_item}]->(l:{service})
+       WHERE l.{imported} IS NOT NULL AND NOT l.guid IN row.services
+       DETACH DELETE l",
+      sub = ModelNode::Submission.label(),
+      service = ModelNode::LineItem.label(),
+      line_item = ModelEdge::SubmissionLineItem.label(),
+      imported = merge::IMPORTED
+    ),
+    format!(
+      "UNWIND $rows AS row
+       MATCH (s:{sub} {{guid: row.sub}})
+       UNWIND row.services AS service
+       MATCH (l:{service} {{guid: service}})
+       MERGE (s)-[:{line_item}]->(l)
+       MERGE (l)-[:{belongs_to}]->(s)
+      ",
+      sub = ModelNode::Submission.label(),
+      service = ModelNode::LineItem.label(),
+      line_item = ModelEdge::SubmissionLineItem.label(),
+      belongs_to = ModelEdge::LineItemSubmission.label()
+    ),
+  ]
+}
+
+/// Writes the submissions a batch at a time as they are read from the file. The invoices are added
+/// up along the way, and written once every submission is in.
+///
+/// Each batch is its own transaction, so a failure leaves the earlier batches written. As the
+/// importer merges rather than creates, importing the file again carries on from there.
+pub struct SubmissionWriter<'a> {
+  conn: &'a Neo4jConnection,
+  batch: Vec<reader::Submission>,
+  invoices: Invoices,
+  progress: Progress,
+  submissions: merge::Summary,
+  services: merge::Summary,
+}
+
+impl<'a> SubmissionWriter<'a> {
+  pub fn new(conn: &'a Neo4jConnection) -> SubmissionWriter<'a> {
+    SubmissionWriter {
+      conn,
+      batch: Vec::with_capacity(conn.batch_size()),
+      invoices: Invoices::default(),
+      progress: Progress::open_ended("Submissions"),
+      submissions: merge::Summary::new(merge::SUBMISSION),
+      services: merge::Summary::new(merge::SERVICE),
+    }
+  }
+
+  /// The number of submissions written so far
+  pub fn written(&self) -> usize {
+    self.progress.done()
+  }
+
+  /// Add a submission to the batch, writing the batch once it is full
+  pub async fn push(&mut self, sub: reader::Submission) -> AWResult<()> {
+    self.invoices.add(&sub);
+    self.batch.push(sub);
+    if self.batch.len() >= self.conn.batch_size() {
+      self.flush().await?;
+    }
+    Ok(())
+  }
+
+  /// Write the submissions in the batch along with their line items and links, in one transaction
+  async fn flush(&mut self) -> AWResult<()> {
+    if self.batch.is_empty() {
+      return Ok(());
+    }
+
+    let records: Vec<merge::Record> = self.batch.iter().map(sub_record).collect();
+    let mut services = Vec::new();
+    let mut sent = Vec::new();
+    let mut listed = Vec::new();
+    for sub in &self.batch {
+      let items = line_items(sub);
+      for (index, item) in items.iter().enumerate() {
+        services.push(service_record(sub, index, item));
+      }
+
+      let guids: Vec<String> = items.iter().map(|item| item.guid.to_string()).collect();
+      sent.push(row([
+        ("org", sub.submitting_org.as_str().into()),
+        ("guid", sub.guid.to_string().into()),
+      ]));
+      listed.push(row([
+        ("sub", sub.guid.to_string().into()),
+        ("services", guids.into()),
+      ]));
+    }
+
+    let conn = self.conn;
+    let subs = merge::plan(conn, merge::SUBMISSION, &records, &mut self.submissions).await?;
+    let services = merge::plan(conn, merge::SERVICE, &services, &mut self.services).await?;
+    let [links, removed, linked] = submission_steps();
+    let queries = vec![
+      query(&merge::upsert(merge::SUBMISSION)).param("rows", subs),
+      query(&links).param("rows", sent),
+      query(&merge::upsert(merge::SERVICE)).param("rows", services),
+      query(&removed).param("rows", listed.clone()),
+      query(&linked).param("rows", listed),
+    ];
+    conn.exec(queries).await.set_context(&format!(
+      "A batch of submissions failed. The {} submissions before it were written",
+      self.written()
+    ))?;
+
+    self.progress.advance(self.batch.len());
+    self.batch.clear();
+    Ok(())
+  }
+
+  /// Write what is left of the last batch, then the invoices and payments of every submission
+  pub async fn finish(mut self) -> AWResult<Vec<merge::Summary>> {
+    self.flush().await?;
+    let throughput = self.progress.finish();
+    if throughput.rows > 0 {
+      eprintln!("{}", throughput);
+    }
+
+    let mut summaries = vec![self.submissions, self.services];
+    summaries.extend(map_invoices(self.conn, self.invoices.rows()).await?);
+    Ok(summaries)
+  }
+}
+
+/// The submissions billed on one invoice, rebuilt from the columns of the log
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvoiceRow {
+  pub number: i32,
+  /// The earliest billing date of the submissions
+  pub billed_on: Option<NaiveDate>,
+  pub total: Decimal,
+  /// What is still owed for the submissions that were never paid
+  pub balance: Decimal,
+  /// The guids of the submissions, in order of accession number
+  pub submissions: Vec<uuid::Uuid>,
+  /// The organizations that sent the submissions
+  pub orgs: Vec<String>,
+  /// In the order they were paid
+  pub payments: Vec<PaymentRow>,
+}
+
+/// The submissions on an invoice that were paid and deposited on the same days
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentRow {
+  pub paid_on: NaiveDate,
+  pub deposited_on: Option<NaiveDate>,
+  pub amount: Decimal,
+}
+
+impl InvoiceRow {
+  pub fn guid(&self) -> uuid::Uuid {
+    merge::guid(merge::INVOICE, &self.number.to_string())
+  }
+}
+
+impl PaymentRow {
+  pub fn guid(&self, invoice: i32) -> uuid::Uuid {
+    let deposited_on = self
+      .deposited_on
+      .map(|date| date.to_string())
+      .unwrap_or_default();
+    merge::guid(
+      merge::PAYMENT,
+      &format!("{}/{}/{}", invoice, self.paid_on, deposited_on),
+    )
+  }
+}
+
+/// Adds up the submissions on each invoice as they are read. Only what the invoices need is kept,
+/// rather than the submissions themselves.
+#[derive(Clone, Debug, Default)]
+pub struct Invoices {
+  billed: BTreeMap<i32, Billed>,
+}
+
+/// The submissions on one invoice so far
+#[derive(Clone, Debug, Default)]
+struct Billed {
+  billed_on: Option<NaiveDate>,
+  total: Decimal,
+  balance: Decimal,
+  /// The guid of each submission, by accession number
+  submissions: BTreeMap<String, uuid::Uuid>,
+  orgs: BTreeSet<String>,
+  payments: Vec<PaymentRow>,
+}
+
+impl Invoices {
+  /// Count the submission towards its invoice. Submissions that were never invoiced are left out.
+  pub fn add(&mut self, sub: &reader::Submission) {
+    let Some(number) = sub.invoice_number else {
+      return;
+    };
+    let billed = self.billed.entry(number).or_default();
+    billed.billed_on = billed.billed_on.into_iter().chain(sub.billed_on).min();
+    billed.total += sub.total;
+    billed
+      .submissions
+      .insert(sub.accession_number.clone(), sub.guid);
+    billed.orgs.insert(sub.submitting_org.clone());
+
+    let Some(paid_on) = sub.paid_on else {
+      billed.balance += sub.total;
+      return;
+    };
+    match billed
+      .payments
+      .iter_mut()
+      .find(|payment| payment.paid_on == paid_on && payment.deposited_on == sub.deposited_on)
+    {
+      Some(payment) => payment.amount += sub.total,
+      None => billed.payments.push(PaymentRow {
+        paid_on,
+        deposited_on: sub.deposited_on,
+        amount: sub.total,
+      }),
+    }
+  }
+
+  /// The invoices, in order of their number
+  pub fn rows(self) -> Vec<InvoiceRow> {
+    self
+      .billed
+      .into_iter()
+      .map(|(number, billed)| {
+        let mut payments = billed.payments;
+        payments.sort_by_key(|payment| (payment.paid_on, payment.deposited_on));
+        InvoiceRow {
+          number,
+          billed_on: billed.billed_on,
+          total: billed.total,
+          balance: billed.balance,
+          submissions: billed.submissions.into_values().collect(),
+          orgs: billed.orgs.into_iter().collect(),
+          payments,
+        }
+      })
+      .collect()
+  }
+}
+
+fn record(guid: uuid::Uuid, key: String, fields: Vec<(&str, serde_json::Value)>) -> merge::Record {
+  merge::Record {
+    guid,
+    key,
+    fields: fields
+      .into_iter()
+      .map(|(field, value)| (field.to_string(), value))
+      .collect(),
+  }
+}
+
+/// The fields of an invoice that come from the file
+pub fn invoice_record(invoice: &InvoiceRow) -> merge::Record {
+  record(
+    invoice.guid(),
+    format!("Invoice {}", invoice.number),
+    vec![
+      ("number", json!(invoice.number)),
+      ("date", json!(invoice.billed_on)),
+      ("total", amount(invoice.total)),
+      ("balance", amount(invoice.balance)),
+    ],
+  )
+}
+
+/// The fields of a payment that come from the file. The log doesn't say how it was paid.
+pub fn payment_record(invoice: &InvoiceRow, payment: &PaymentRow) -> merge::Record {
+  record(
+    payment.guid(invoice.number),
+    format!("Invoice {} paid on {}", invoice.number, payment.paid_on),
+    vec![
+      ("kind", json!(format!("{:?}", PaymentType::Unrecorded))),
+      ("amount", amount(payment.amount)),
+      ("received_on", json!(payment.paid_on)),
+      ("deposited_on", json!(payment.deposited_on)),
+    ],
+  )
+}
+
+/// Write an invoice for each invoice number in the log, billed to the parent organization that
+/// handles the money, along with the payments made on it. The services have to be written first.
+pub async fn map_invoices(
+  conn: &Neo4jConnection,
+  invoices: Vec<InvoiceRow>,
+) -> AWResult<Vec<merge::Summary>> {
+  println!("Rebuilding the invoices and payments");
+
+  let mut invoice_records = Vec::new();
+  let mut payment_records = Vec::new();
+  let mut links = Vec::new();
+  for invoice in &invoices {
+    invoice_records.push(invoice_record(invoice));
+    let guid = invoice.guid().to_string();
+    let submissions: Vec<String> = invoice
+      .submissions
+      .iter()
+      .map(|guid| guid.to_string())
+      .collect();
+    let payments: Vec<String> = invoice
+      .payments
+      .iter()
+      .map(|payment| {
+        payment_records.push(payment_record(invoice, payment));
+        payment.guid(invoice.number).to_string()
+      })
+      .collect();
+
+    links.push(row([
+      ("invoice", guid.into()),
+      ("submissions", submissions.into()),
+      ("orgs", invoice.orgs.clone().into()),
+      ("payments", payments.into()),
+    ]));
+  }
+
+  let summaries = vec![
+    merge::merge(conn, merge::INVOICE, &invoice_records).await?,
+    merge::merge(conn, merge::PAYMENT, &payment_records).await?,
+  ];
+
+  for (label, cypher) in invoice_steps() {
+    conn.unwind(label, &cypher, links.clone()).await?;
+  }
+  Ok(summaries)
+}
+
+/// The queries linking each invoice to its services, payer and payments, in the order they run.
+/// Each takes rows with the invoice's guid and the guids of its submissions and payments, along
+/// with the pretty ids of the orgs that sent the submissions.
+pub fn invoice_steps() -> [(&'static str, String); 4] {
+  [
+    (
+      "Invoice services",
+      format!(
+        "UNWIND $rows AS row
+         MATCH (i:{invoice} {{guid: row.invoice}})
+         UNWIND row.submissions AS sub
+         MATCH (:{sub} {{guid: sub}})-[:{line_item}]->(l:{service})
+         MERGE (i)-[:{bills}]->(l)
+        ",
+        invoice = ModelNode::Invoice.label(),
+        sub = ModelNode::Submission.label(),
+        service = ModelNode::LineItem.label(),
+        line_item = ModelEdge::SubmissionLineItem.label(),
+        bills = ModelEdge::InvoiceLineItem.label()
+      ),
+    ),
+    // Bill the top of each submitting org's hierarchy, or the org itself when it has no parent. The
+    // payer is worked out again each time, so a change to the hierarchy moves the invoice.
+    (
+      "Invoice payers",
+      format!(
+        "UNWIND $rows AS row
+         MATCH (i:{invoice} {{guid: row.invoice}})
+         OPTIONAL MATCH (i)-[old:{billed_to}]->(:{org})
+         DELETE old
+         WITH DISTINCT row, i
+         UNWIND row.orgs AS org
+         MATCH (o:{org} {{pretty_id: org}})
+         OPTIONAL MATCH (root:{org})-[:{parent_of}*]->(o)
+         OPTIONAL MATCH (above:{org})-[:{parent_of}]->(root)
+         WITH i, o, root, above
+         WHERE above IS NULL
+         WITH DISTINCT i, coalesce(root, o) AS payer
+         MERGE (i)-[:{billed_to}]->(payer)
+        ",
+        invoice = ModelNode::Invoice.label(),
+        org = ModelNode::Organization.label(),
+        parent_of = ModelEdge::OrganizationParent.label(),
+        billed_to = ModelEdge::InvoiceOrganization.label()
+      ),
+    ),
+    // Payments whose dates changed in the log since the last import are replaced
+    (
+      "Removed payments",
+      format!(
+        "UNWIND $rows AS row
+         MATCH (p:{payment})-[:{pays}]->(:{invoice} {{guid: row.invoice}})
+         WHERE p.{imported} IS NOT NULL AND NOT p.guid IN row.payments
+         DETACH DELETE p",
+        payment = ModelNode::Payment.label(),
+        invoice = ModelNode::Invoice.label(),
+        pays = ModelEdge::PaymentInvoice.label(),
+        imported = merge::IMPORTED
+      ),
+    ),
+    (
+      "Payment links",
+      format!(
+        "UNWIND $rows AS row
+         MATCH (i:{invoice} {{guid: row.invoice}})
+         UNWIND row.payments AS payment
+         MATCH (p:{payment} {{guid: payment}})
+         MERGE (p)-[:{pays}]->(i)
+         WITH i, p
+         OPTIONAL MATCH (p)-[old:{paid_by}]->(:{org})
+         DELETE old
+         WITH DISTINCT i, p
+         MATCH (i)-[:{billed_to}]->(o:{org})
+         MERGE (p)-[:{paid_by}]->(o)
+        ",
+        invoice = ModelNode::Invoice.label(),
+        payment = ModelNode::Payment.label(),
+        org = ModelNode::Organization.label(),
+        pays = ModelEdge::PaymentInvoice.label(),
+        billed_to = ModelEdge::InvoiceOrganization.label(),
+        paid_by = ModelEdge::PaymentOrganization.label()
+      ),
+    ),
+  ]
+}
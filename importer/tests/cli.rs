@@ -0,0 +1,130 @@
+//! Parsing the command line and guarding the destructive commands
+
+use clap::Parser;
+use wrangler_common::prelude::*;
+use wrangler_importer::cli::{Cli, Command, Guard, ImportCommand};
+
+#[test]
+fn parses_the_subcommands() {
+  let cli = Cli::try_parse_from([
+    "wrangler-importer",
+    "import",
+    "orgs",
+    "--file",
+    "orgs.csv",
+    "--delimiter",
+    "comma",
+    "--uri",
+    "neo4j://db.example.com:7687",
+  ])
+  .unwrap();
+  assert_eq!(cli.connection.uri, "neo4j://db.example.com:7687");
+  match cli.command {
+    Command::Import {
+      what: ImportCommand::Orgs(args),
+    } => {
+      assert_eq!(args.import.file.to_str(), Some("orgs.csv"));
+      assert_eq!(args.import.format.delimiter, b',');
+      assert!(!args.replace);
+      assert_eq!(args.import.batch_size, 500);
+    }
+    other => panic!("Parsed the wrong command: {:?}", other),
+  }
+
+  // Batches need at least one row
+  let import = |size: &str| {
+    Cli::try_parse_from([
+      "wrangler-importer",
+      "import",
+      "submissions",
+      "-f",
+      "subs.tsv",
+      "--batch-size",
+      size,
+    ])
+  };
+  assert!(import("0").is_err());
+  assert!(matches!(
+    import("2000").unwrap().command,
+    Command::Import { what: ImportCommand::Submissions(args) } if args.import.batch_size == 2000
+  ));
+
+  // Wiping the database would take the orgs the submissions are linked to along with it
+  let err = Cli::try_parse_from([
+    "wrangler-importer",
+    "import",
+    "submissions",
+    "-f",
+    "subs.tsv",
+    "--replace",
+  ])
+  .unwrap_err();
+  assert_eq!(err.kind(), clap::error::ErrorKind::UnknownArgument);
+  assert!(Cli::try_parse_from([
+    "wrangler-importer",
+    "import",
+    "orgs",
+    "-f",
+    "orgs.tsv",
+    "--replace",
+    "--dry-run",
+  ])
+  .is_err());
+
+  // A dry run can't look up the orgs in the database, so it needs the organizations file
+  let dry_run = |orgs: &[&str]| {
+    let mut args = vec![
+      "wrangler-importer",
+      "import",
+      "submissions",
+      "-f",
+      "subs.tsv",
+      "--dry-run",
+    ];
+    args.extend(orgs);
+    Cli::try_parse_from(args)
+  };
+  assert!(dry_run(&[]).is_err());
+  assert!(matches!(
+    dry_run(&["--orgs", "orgs.tsv"]).unwrap().command,
+    Command::Import { what: ImportCommand::Submissions(args) } if args.orgs.is_some()
+  ));
+
+  // Validate needs something to check, and delimiters are a single character
+  assert!(Cli::try_parse_from(["wrangler-importer", "validate"]).is_err());
+  assert!(
+    Cli::try_parse_from(["wrangler-importer", "validate", "--orgs", "a", "-d", "::"]).is_err()
+  );
+  let cli =
+    Cli::try_parse_from(["wrangler-importer", "validate", "--orgs", "a", "-d", "|"]).unwrap();
+  assert!(matches!(cli.command, Command::Validate(args) if args.format.delimiter == b'|'));
+}
+
+#[test]
+fn destructive_commands_need_confirmation() {
+  let guard = Guard { yes: false };
+  let mut output = Vec::new();
+
+  // Nobody to ask
+  let err = guard
+    .confirm_with("wipe", false, &mut "yes\n".as_bytes(), &mut output)
+    .unwrap_err();
+  assert!(matches!(err.get_kind(), PermissionDenied));
+  assert!(err.get_context().unwrap().contains("--yes"));
+
+  // Only a clear yes goes ahead
+  assert!(guard
+    .confirm_with("wipe", true, &mut "y\n".as_bytes(), &mut output)
+    .is_err());
+  assert!(guard
+    .confirm_with("wipe", true, &mut "yes\n".as_bytes(), &mut output)
+    .is_ok());
+  assert!(String::from_utf8(output)
+    .unwrap()
+    .contains("This will wipe"));
+
+  let guard = Guard { yes: true };
+  assert!(guard
+    .confirm_with("wipe", false, &mut "".as_bytes(), &mut Vec::new())
+    .is_ok());
+}
@@ -0,0 +1,111 @@
+//! Files and mappings shared by the tests. Each test file only uses some of them.
+#![allow(dead_code)]
+
+use std::{
+  collections::HashSet,
+  path::{Path, PathBuf},
+};
+
+use wrangler_importer::{
+  dates::DateOrder,
+  mapping::{Mapping, OrgColumns},
+  reader,
+};
+
+/// Name every submission column after its field, in a different order than InvoicerUI
+pub const MAPPING: &str = r#"
+[submissions]
+accession_number = "accession_number"
+submitting_org = "submitting_org"
+submitted_by = "submitted_by"
+category = "category"
+line_items = "line_items"
+species = "species"
+pet_name = "pet_name"
+received_on = "received_on"
+finalized_on = "finalized_on"
+diagnosis = "diagnosis"
+total = "total"
+billed_on = "billed_on"
+paid_on = "paid_on"
+deposited_on = "deposited_on"
+invoice_number = "invoice_number"
+"#;
+
+/// The header line of a submissions file read with [MAPPING]
+pub const HEADERS: &str = "accession_number\tsubmitting_org\tsubmitted_by\tcategory\tline_items\tspecies\tpet_name\treceived_on\tfinalized_on\tdiagnosis\ttotal\tbilled_on\tpaid_on\tdeposited_on\tinvoice_number";
+
+/// The header line of an organizations file in the InvoicerUI order
+pub const ORG_HEADERS: &str = "id\tpretty_id\tname\tparent\tchildren";
+
+/// A file in the temp directory, deleted when dropped so a failed assertion doesn't leave it behind
+pub struct ScratchFile {
+  path: PathBuf,
+}
+
+impl ScratchFile {
+  pub fn new(contents: impl AsRef<[u8]>) -> ScratchFile {
+    let path = std::env::temp_dir().join(format!("wrangler-{}.tsv", uuid::Uuid::new_v4()));
+    std::fs::write(&path, contents).unwrap();
+    ScratchFile { path }
+  }
+
+  /// The header followed by each of the rows
+  pub fn rows(header: &str, rows: &[&str]) -> ScratchFile {
+    let mut contents = vec![header];
+    contents.extend(rows);
+    ScratchFile::new(contents.join("\n"))
+  }
+}
+
+impl std::ops::Deref for ScratchFile {
+  type Target = Path;
+
+  fn deref(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for ScratchFile {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_file(&self.path);
+  }
+}
+
+/// Read every submission in a file written with [MAPPING], keeping the accepted ones by accession
+/// number. Without the known orgs, any org is accepted.
+pub fn read_subs(
+  path: &Path,
+  order: Option<DateOrder>,
+  known: Option<HashSet<String>>,
+) -> reader::Loaded<String, reader::Submission> {
+  let mapping = Mapping::parse(MAPPING).unwrap();
+  let mut subs = reader::Submissions::open(path, b'\t', &mapping.submissions, order).unwrap();
+  if let Some(known) = known {
+    subs = subs.with_orgs(known);
+  }
+  let rows = subs
+    .by_ref()
+    .map(|sub| sub.unwrap())
+    .map(|sub| (sub.accession_number.clone(), sub))
+    .collect();
+  reader::Loaded {
+    rows,
+    read: subs.read,
+    issues: subs.issues,
+  }
+}
+
+/// Read the submission rows, written under [HEADERS]
+pub fn load_subs(
+  rows: &[&str],
+  order: Option<DateOrder>,
+) -> reader::Loaded<String, reader::Submission> {
+  read_subs(&ScratchFile::rows(HEADERS, rows), order, None)
+}
+
+/// Read the organization rows, written under [ORG_HEADERS]
+pub fn load_orgs(rows: &[&str]) -> reader::Loaded<i32, reader::Organization> {
+  let file = ScratchFile::rows(ORG_HEADERS, rows);
+  reader::load_orgs(&file, b'\t', &OrgColumns::default()).unwrap()
+}
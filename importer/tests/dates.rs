@@ -0,0 +1,167 @@
+//! Reading the dates written by the different tools the log was kept in
+
+mod common;
+
+use chrono::NaiveDate;
+use wrangler_importer::{
+  dates::{self, DateOrder, Detector, Reading},
+  mapping::Mapping,
+  reader::{self, Problem},
+};
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+  NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[test]
+fn reads_every_format() {
+  let month_first = Some(DateOrder::MonthFirst);
+  for value in [
+    "3/10/2024",
+    "03/10/2024",
+    "2024-03-10",
+    "2024-03-10T09:30:00",
+    "45361",
+    "45361.75",
+  ] {
+    assert_eq!(
+      dates::parse(value, month_first),
+      Reading::Date(date(2024, 3, 10)),
+      "{}",
+      value
+    );
+  }
+  assert_eq!(
+    dates::parse("3/10/2024", Some(DateOrder::DayFirst)),
+    Reading::Date(date(2024, 10, 3))
+  );
+
+  // Two digit years, serials before Excel's phantom leap day and dates that don't exist
+  for value in [
+    "3/10/24",
+    "60",
+    "2/30/2024",
+    "13/10/2024",
+    "Jan 3rd",
+    "3-10-2024",
+  ] {
+    assert_eq!(
+      dates::parse(value, month_first),
+      Reading::Invalid,
+      "{}",
+      value
+    );
+  }
+}
+
+#[test]
+fn reports_ambiguous_dates() {
+  assert_eq!(
+    dates::parse("3/10/2024", None),
+    Reading::Ambiguous(vec![date(2024, 3, 10), date(2024, 10, 3)])
+  );
+  // Only one reading is a real date, or both are the same
+  assert_eq!(
+    dates::parse("3/13/2024", None),
+    Reading::Date(date(2024, 3, 13))
+  );
+  assert_eq!(
+    dates::parse("13/3/2024", None),
+    Reading::Date(date(2024, 3, 13))
+  );
+  assert_eq!(
+    dates::parse("5/5/2024", None),
+    Reading::Date(date(2024, 5, 5))
+  );
+
+  let mut detector = Detector::default();
+  assert_eq!(detector.order(), Some(DateOrder::MonthFirst));
+  detector.see("13/3/2024");
+  detector.see("3/10/2024");
+  assert_eq!(detector.order(), Some(DateOrder::DayFirst));
+  detector.see("3/13/2024");
+  assert_eq!(detector.order(), None);
+}
+
+#[test]
+fn detects_the_order_of_a_file() {
+  // The billing date can only be read day first, so the whole file is
+  let day_first =
+    ["A-1\tACME\tDr. Vet\tNecropsy\t\tCanine\tRex\t3/10/2024\t\t\t0\t20/10/2024\t\t\t"];
+  let subs = common::load_subs(&day_first, None);
+  assert!(subs.issues.is_empty());
+  assert_eq!(subs.rows["A-1"].received_on, Some(date(2024, 10, 3)));
+
+  let subs = common::load_subs(&day_first, Some(DateOrder::MonthFirst));
+  assert_eq!(
+    subs.issues[0].problem,
+    Problem::BadDate {
+      field: "billed_on".to_string(),
+      value: "20/10/2024".to_string(),
+    }
+  );
+
+  // With both orders in the file, a date that reads either way is not guessed
+  let mixed = common::load_subs(
+    &[
+      "A-1\tACME\tDr. Vet\tNecropsy\t\tCanine\tRex\t3/10/2024\t\t\t0\t20/10/2024\t\t\t",
+      "A-2\tACME\tDr. Vet\tNecropsy\t\tCanine\tRex\t10/20/2024\t\t\t0\t\t\t\t",
+    ],
+    None,
+  );
+  assert_eq!(
+    mixed.issues[0].problem,
+    Problem::AmbiguousDate {
+      field: "received_on".to_string(),
+      value: "3/10/2024".to_string(),
+      readings: vec![date(2024, 3, 10), date(2024, 10, 3)],
+    }
+  );
+  assert_eq!(
+    mixed.issues[0].to_string(),
+    "Line 2 (A-1): '3/10/2024' could be 2024-03-10 or 2024-10-03 for received_on, as the file has \
+     dates in both orders. Pass --date-order to pick one"
+  );
+  assert_eq!(mixed.rows["A-2"].received_on, Some(date(2024, 10, 20)));
+}
+
+#[test]
+fn holds_back_rows_until_the_order_is_known() {
+  let file = common::ScratchFile::rows(
+    common::HEADERS,
+    &[
+      "A-1\tACME\tDr. Vet\tNecropsy\t\tCanine\tRex\t3/10/2024\t\t\t0\t\t\t\t",
+      "A-2\tACME\tDr. Vet\tNecropsy\t\tCanine\tRex\t2024-10-05\t\t\t0\t\t\t\t",
+      "A-1\tACME\tDr. Vet\tNecropsy\t\tCanine\tRex\t20/10/2024\t\t\t0\t\t\t\t",
+    ],
+  );
+  let mapping = Mapping::parse(common::MAPPING).unwrap();
+  let mut subs = reader::Submissions::open(&file, b'\t', &mapping.submissions, None).unwrap();
+
+  // The first row waits for the end of the file, which shows the days come first
+  let read: Vec<(String, Option<NaiveDate>)> = subs
+    .by_ref()
+    .map(|sub| sub.unwrap())
+    .map(|sub| (sub.accession_number, sub.received_on))
+    .collect();
+  assert_eq!(
+    read,
+    vec![
+      ("A-2".to_string(), Some(date(2024, 10, 5))),
+      ("A-1".to_string(), Some(date(2024, 10, 3))),
+    ]
+  );
+
+  // It still came first, so the later row is the duplicate
+  assert_eq!(subs.read, 3);
+  assert_eq!(subs.accepted, 2);
+  assert_eq!(
+    subs.issues[0].problem,
+    Problem::Duplicate {
+      field: "accession_number".to_string(),
+      value: "A-1".to_string(),
+      first_line: 2,
+    }
+  );
+  assert_eq!(subs.issues[0].line, 4);
+}
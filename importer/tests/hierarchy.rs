@@ -0,0 +1,93 @@
+//! Checking the parent/child lists of the organizations before the tree is written
+
+mod common;
+
+use wrangler_importer::{hierarchy, reader::Problem};
+
+#[test]
+fn prints_the_tree() {
+  let mut orgs = common::load_orgs(&[
+    "1\tACME\tAcme Vets\t\t[3, 2]",
+    "2\tEAST\tAcme East\t\t[4]",
+    "3\tWEST\tAcme West\t\t[]",
+    "4\tNORTH\tAcme North\t\t",
+    "5\tSOLO\tSolo Clinic\t\t[]",
+  ]);
+  let tree = hierarchy::check(&mut orgs);
+  assert!(orgs.issues.is_empty());
+
+  assert_eq!(tree.parents[&4], 2);
+  assert_eq!(orgs.rows[&4].parent, Some(orgs.rows[&2].guid));
+  assert_eq!(orgs.rows[&1].parent, None);
+  assert_eq!(
+    tree.to_string(),
+    "ACME (Acme Vets)\n\
+     ├── EAST (Acme East)\n\
+     │   └── NORTH (Acme North)\n\
+     └── WEST (Acme West)\n\
+     SOLO (Solo Clinic)\n"
+  );
+}
+
+#[test]
+fn finds_every_problem_in_the_tree() {
+  let mut orgs = common::load_orgs(&[
+    "1\tACME\tAcme Vets\t\t[2, 3, 99]",
+    "2\tEAST\tAcme East\t\t[4]",
+    "3\tWEST\tAcme West\t\t[4]",
+    "4\tNORTH\tAcme North\t\t[]",
+    "5\tLOOP-A\tLoop A\t\t[6]",
+    "6\tLOOP-B\tLoop B\t\t[5]",
+    "7\tSELF\tSelf Parent\t\t[7]",
+    "8\tBAD\tBad List\t\t[x]",
+  ]);
+  let tree = hierarchy::check(&mut orgs);
+
+  let found: Vec<(u64, &Problem)> = orgs
+    .issues
+    .iter()
+    .map(|issue| (issue.line, &issue.problem))
+    .collect();
+  assert_eq!(
+    found,
+    vec![
+      (2, &Problem::MissingChild { value: 99 }),
+      (
+        5,
+        &Problem::MultipleParents {
+          parents: vec!["EAST".to_string(), "WEST".to_string()],
+        }
+      ),
+      (
+        6,
+        &Problem::Cycle {
+          path: vec!["LOOP-A".to_string(), "LOOP-B".to_string()],
+        }
+      ),
+      (
+        8,
+        &Problem::Cycle {
+          path: vec!["SELF".to_string()],
+        }
+      ),
+      (
+        9,
+        &Problem::BadNumber {
+          field: "children".to_string(),
+          value: "x".to_string(),
+        }
+      ),
+    ]
+  );
+  assert_eq!(
+    orgs.issues[2].to_string(),
+    "Line 6 (5): The organization is its own ancestor: LOOP-A -> LOOP-B -> LOOP-A"
+  );
+
+  // A missing child is only a warning, the rest are left out of the tree
+  assert_eq!(orgs.errors().count(), 4);
+  let mut kept: Vec<i32> = orgs.rows.keys().copied().collect();
+  kept.sort();
+  assert_eq!(kept, vec![1, 2, 3]);
+  assert_eq!(tree.edges().collect::<Vec<_>>(), vec![(1, 2), (1, 3)]);
+}
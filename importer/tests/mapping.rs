@@ -0,0 +1,86 @@
+//! Finding the columns of a file by their headers
+
+mod common;
+
+use common::ScratchFile;
+use csv::StringRecord;
+use wrangler_common::prelude::*;
+use wrangler_importer::{
+  mapping::{Column, Mapping},
+  reader,
+};
+
+const MAPPING: &str = r#"
+[organizations]
+source_id = "Id"
+pretty_id = "Short Name"
+name = "Name"
+children = 3
+"#;
+
+#[test]
+fn reads_a_mapping_file() {
+  let mapping = Mapping::parse(MAPPING).unwrap();
+  assert_eq!(
+    mapping.organizations.pretty_id,
+    Column::Header("Short Name".to_string())
+  );
+  assert_eq!(mapping.organizations.children, Column::Index(3));
+
+  // Missing sections use the InvoicerUI order
+  assert_eq!(mapping.submissions, Default::default());
+
+  // Every field of a section has to be mapped, and misspelled ones aren't ignored
+  let err = Mapping::parse("[organizations]\nsource_id = \"Id\"").unwrap_err();
+  assert!(matches!(err.get_kind(), ConfigError));
+  assert!(Mapping::parse(&format!("{}\nparent = \"Parent\"", MAPPING)).is_err());
+}
+
+#[test]
+fn reports_every_mismatched_header() {
+  let mapping = Mapping::parse(MAPPING).unwrap();
+  let headers = StringRecord::from(vec!["Id", "Name", "Name", "Kids"]);
+
+  let err = mapping.organizations.resolve(&headers).unwrap_err();
+  let failures: Vec<&str> = err
+    .get_inner()
+    .iter()
+    .filter_map(|inner| inner.get_context())
+    .collect();
+  assert_eq!(
+    failures,
+    vec![
+      "'pretty_id' is mapped to the header 'Short Name', which is not in the file",
+      "'name' is mapped to the header 'Name', which appears 2 times",
+    ]
+  );
+
+  let headers = StringRecord::from(vec!["Id", "Name"]);
+  let err = Column::Index(3).find("children", &headers).unwrap_err();
+  assert_eq!(
+    err.get_context(),
+    Some("'children' is mapped to column 3, but there are only 2 columns")
+  );
+}
+
+#[test]
+fn loads_a_reshuffled_file() {
+  let mapping = Mapping::parse(MAPPING).unwrap();
+  let path = ScratchFile::new("Name,Short Name,Id,Children\nAcme Vets,ACME,7,[]\n");
+  let orgs = reader::load_orgs(&path, b',', &mapping.organizations)
+    .unwrap()
+    .rows;
+
+  assert_eq!(orgs[&7].pretty_id, "ACME");
+  assert_eq!(orgs[&7].name, "Acme Vets");
+  assert!(orgs[&7].children.is_empty());
+
+  // The headers are checked before any of the rows
+  let path = ScratchFile::new("Name,Short Id,Id,Children\nAcme Vets,ACME,not a number,[]\n");
+  let err = reader::load_orgs(&path, b',', &mapping.organizations).unwrap_err();
+  assert!(matches!(err.get_kind(), ValidationError));
+  assert!(err
+    .get_context()
+    .unwrap()
+    .contains("don't match the column mapping"));
+}
@@ -0,0 +1,118 @@
+//! Importing the same rows again without clobbering the edits made in the app
+
+use serde_json::{json, Value};
+use wrangler_importer::merge::{self, diff, Existing, Fields, Record, Status, Summary};
+
+fn fields(values: &[(&str, Value)]) -> Fields {
+  values
+    .iter()
+    .map(|(field, value)| (field.to_string(), value.clone()))
+    .collect()
+}
+
+fn record(name: &str, total: &str) -> Record {
+  Record {
+    guid: merge::guid(merge::SUBMISSION, "A-1"),
+    key: "A-1".to_string(),
+    fields: fields(&[("name", json!(name)), ("total", json!(total))]),
+  }
+}
+
+/// A node last imported as `imported`, and now holding `properties`
+fn node(properties: &Record, imported: &Record) -> Existing {
+  Existing {
+    properties: properties.fields.clone(),
+    imported: Some(imported.fields.clone()),
+  }
+}
+
+#[test]
+fn guids_are_stable() {
+  let first = merge::guid(merge::SUBMISSION, "A-1");
+  assert_eq!(first, merge::guid(merge::SUBMISSION, "A-1"));
+  assert_ne!(first, merge::guid(merge::SUBMISSION, "A-2"));
+  // An organization and a submission with the same id are different nodes
+  assert_ne!(first, merge::guid(merge::ORGANIZATION, "A-1"));
+  assert_eq!(first.get_version_num(), 5);
+}
+
+#[test]
+fn only_writes_what_changed() {
+  let first = record("Rex", "50.00");
+
+  let created = diff(&first, None);
+  assert_eq!(created.status, Status::Created);
+  assert_eq!(created.changes, first.fields);
+
+  // Importing the same file twice writes nothing
+  let existing = node(&first, &first);
+  let unchanged = diff(&first, Some(&existing));
+  assert_eq!(unchanged.status, Status::Unchanged);
+  assert!(!unchanged.writes(Some(&existing)));
+
+  let second = record("Rex", "75.00");
+  let updated = diff(&second, Some(&existing));
+  assert_eq!(updated.status, Status::Updated);
+  assert_eq!(updated.changes, fields(&[("total", json!("75.00"))]));
+  assert_eq!(updated.imported, second.fields);
+}
+
+#[test]
+fn keeps_the_edits_made_in_the_app() {
+  let imported = record("Rex", "50.00");
+  let edited = node(&record("Rexy", "50.00"), &imported);
+
+  // The file still has the old name, so the edit stays
+  let kept = diff(&imported, Some(&edited));
+  assert_eq!(kept.status, Status::Unchanged);
+  assert!(kept.changes.is_empty());
+
+  // The total changed in the file and nobody touched it in the app
+  let updated = diff(&record("Rex", "75.00"), Some(&edited));
+  assert_eq!(updated.status, Status::Updated);
+  assert_eq!(updated.changes, fields(&[("total", json!("75.00"))]));
+
+  // Both sides changed the name, so a person has to pick one
+  let renamed = record("Max", "50.00");
+  let conflict = diff(&renamed, Some(&edited));
+  assert_eq!(conflict.status, Status::Conflict);
+  assert!(conflict.changes.is_empty());
+  assert_eq!(conflict.conflicts[0].field, "name");
+  assert_eq!(conflict.conflicts[0].in_database, json!("Rexy"));
+  assert_eq!(conflict.conflicts[0].in_file, json!("Max"));
+
+  // The conflict is still reported the next time, until it is settled in the app
+  let again = node(
+    &record("Rexy", "50.00"),
+    &Record {
+      fields: conflict.imported.clone(),
+      ..renamed.clone()
+    },
+  );
+  assert_eq!(diff(&renamed, Some(&again)).status, Status::Conflict);
+  let settled = node(
+    &renamed,
+    &Record {
+      fields: conflict.imported,
+      ..renamed.clone()
+    },
+  );
+  assert_eq!(diff(&renamed, Some(&settled)).status, Status::Unchanged);
+}
+
+#[test]
+fn summarizes_the_import() {
+  let imported = record("Rex", "50.00");
+  let edited = node(&record("Rexy", "50.00"), &imported);
+
+  let mut summary = Summary::new(merge::SUBMISSION);
+  summary.add(&diff(&imported, None));
+  summary.add(&diff(&imported, Some(&node(&imported, &imported))));
+  summary.add(&diff(&record("Max", "50.00"), Some(&edited)));
+
+  assert_eq!(
+    summary.to_string(),
+    "Submission: 1 created, 0 updated, 1 unchanged, 1 conflicts\n  \
+     A-1 'name': kept \"Rexy\" from the app instead of \"Max\" from the file\n"
+  );
+}
@@ -0,0 +1,35 @@
+//! Counting the rows written and how fast they went
+
+use std::time::Duration;
+use wrangler_importer::progress::{Progress, Throughput};
+
+#[test]
+fn measures_the_throughput() {
+  let mut progress = Progress::new("Submission", 1200);
+  progress.advance(500);
+  progress.advance(500);
+  progress.advance(200);
+  assert_eq!(progress.done(), 1200);
+
+  let throughput = progress.finish();
+  assert_eq!(throughput.rows, 1200);
+  assert_eq!(throughput.batches, 3);
+
+  // Rows streamed from a file are counted without knowing how many there will be
+  let mut progress = Progress::open_ended("Submission");
+  progress.advance(500);
+  progress.advance(20);
+  assert_eq!(progress.finish().rows, 520);
+
+  let throughput = Throughput {
+    label: "Submission".to_string(),
+    rows: 12000,
+    batches: 24,
+    elapsed: Duration::from_millis(1500),
+  };
+  assert_eq!(throughput.per_second(), 8000.0);
+  assert_eq!(
+    throughput.to_string(),
+    "Submission: 12000 rows in 24 batches, 1.50s (8000 rows/s)"
+  );
+}
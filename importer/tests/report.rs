@@ -0,0 +1,259 @@
+//! Collecting every problem in the input files
+
+mod common;
+
+use common::{ScratchFile, HEADERS};
+use wrangler_common::prelude::*;
+use wrangler_importer::{
+  reader::Problem,
+  report::{Report, ReportFormat},
+};
+
+fn submission(accession: &str, org: &str, items: &str, total: &str, received: &str) -> String {
+  format!(
+    "{}\t{}\tDr. Vet\tNecropsy\t{}\tCanine\tRex\t{}\t\t\t{}\t\t\t\t",
+    accession, org, items, received, total
+  )
+}
+
+#[test]
+fn finds_every_problem() {
+  let orgs = common::load_orgs(&["1\tACME\tAcme Vets\t\t[]"]);
+  let subs = ScratchFile::new(
+    [
+      HEADERS.to_string(),
+      submission("A-1", "ACME", r#"["Necropsy", 1, 50.00]"#, "50", "1/2/2023"),
+      submission("A-2", "ACME", r#"["Histo", 2, 10]"#, "25.00", "2023-01-03"),
+      submission("A-3", "ACME", "", "fifty", "Jan 3rd"),
+      submission("A-1", "ACME", "", "0", ""),
+      submission("A-4", "NOPE", "", "0", ""),
+      "A-5\ttoo short".to_string(),
+    ]
+    .join("\n"),
+  );
+
+  let subs_loaded = common::read_subs(&subs, None, Some(orgs.pretty_ids()));
+
+  let found: Vec<(u64, &Problem)> = subs_loaded
+    .issues
+    .iter()
+    .map(|issue| (issue.line, &issue.problem))
+    .collect();
+  assert_eq!(
+    found,
+    vec![
+      (
+        3,
+        &Problem::LineItemTotal {
+          total: "25.00".parse().unwrap(),
+          line_items: "20".parse().unwrap(),
+        }
+      ),
+      (
+        4,
+        &Problem::BadDecimal {
+          field: "total".to_string(),
+          value: "fifty".to_string(),
+        }
+      ),
+      (
+        4,
+        &Problem::BadDate {
+          field: "received_on".to_string(),
+          value: "Jan 3rd".to_string(),
+        }
+      ),
+      (
+        5,
+        &Problem::Duplicate {
+          field: "accession_number".to_string(),
+          value: "A-1".to_string(),
+          first_line: 2,
+        }
+      ),
+      (
+        6,
+        &Problem::UnknownOrg {
+          value: "NOPE".to_string(),
+        }
+      ),
+      (
+        7,
+        &Problem::WrongLength {
+          expected: 15,
+          found: 2,
+        }
+      ),
+    ]
+  );
+
+  // A mismatched total is only a warning
+  assert_eq!(subs_loaded.read, 6);
+  let mut accepted: Vec<&str> = subs_loaded.rows.keys().map(|key| key.as_str()).collect();
+  accepted.sort();
+  assert_eq!(accepted, vec!["A-1", "A-2"]);
+
+  let mut report = Report::default();
+  report.add(&subs, &subs_loaded);
+  assert_eq!(report.errors(), 5);
+  let err = report.check().unwrap_err();
+  assert!(matches!(err.get_kind(), ImportRowError));
+}
+
+#[test]
+fn reports_what_it_cannot_read() {
+  let mut contents = [
+    HEADERS.to_string(),
+    submission(
+      "A-1",
+      "ACME",
+      r#"["Stain", 1, $45], ["Recheck", 1, -5]"#,
+      "40",
+      "",
+    ),
+    submission(
+      "A-2",
+      "ACME",
+      r#"["Necropsy", 1, 50], ["12" biopsy", 1, 5]"#,
+      "55",
+      "",
+    ),
+    submission("A-3", "ACME", "", "0", ""),
+  ]
+  .join("\n")
+  .into_bytes();
+  // A pet name saved in another encoding
+  contents.extend(b"\nA-4\tACME\tDr. Vet\tNecropsy\t\tCanine\tR\xe9x\t\t\t\t0\t\t\t\t");
+  let file = ScratchFile::new(contents);
+
+  let loaded = common::read_subs(&file, None, None);
+
+  let found: Vec<(u64, &Problem)> = loaded
+    .issues
+    .iter()
+    .map(|issue| (issue.line, &issue.problem))
+    .collect();
+  assert_eq!(
+    found,
+    vec![
+      (
+        2,
+        &Problem::BadDecimal {
+          field: "the price of 'Stain'".to_string(),
+          value: "$45".to_string(),
+        }
+      ),
+      // The negative amount is read, while the line item with a bad price is left out of the sum
+      (
+        2,
+        &Problem::LineItemTotal {
+          total: "40.00".parse().unwrap(),
+          line_items: "-5".parse().unwrap(),
+        }
+      ),
+      (
+        3,
+        &Problem::BadLineItem {
+          value: r#"["12" biopsy", 1, 5]"#.to_string(),
+        }
+      ),
+      (
+        3,
+        &Problem::LineItemTotal {
+          total: "55.00".parse().unwrap(),
+          line_items: "50".parse().unwrap(),
+        }
+      ),
+      (5, &Problem::NotText { column: 7 }),
+    ]
+  );
+  assert_eq!(loaded.read, 4);
+  assert_eq!(loaded.rows.keys().collect::<Vec<_>>(), vec!["A-3"]);
+}
+
+#[test]
+fn rejected_rows_still_claim_their_key() {
+  let subs = ScratchFile::new(
+    [
+      HEADERS.to_string(),
+      submission("A-1", "ACME", "", "oops", ""),
+      submission("A-1", "ACME", "", "0", ""),
+      submission("A-2", "ACME", r#"["Histo", 1, 10.005]"#, "10.005", ""),
+      submission("A-3", "ACME", r#"["Histo", 2, 10.50]"#, "21.000", ""),
+    ]
+    .join("\n"),
+  );
+  let loaded = common::read_subs(&subs, None, None);
+
+  let found: Vec<(u64, &Problem)> = loaded
+    .issues
+    .iter()
+    .map(|issue| (issue.line, &issue.problem))
+    .collect();
+  assert_eq!(
+    found,
+    vec![
+      (
+        2,
+        &Problem::BadDecimal {
+          field: "total".to_string(),
+          value: "oops".to_string(),
+        }
+      ),
+      // The first A-1 couldn't be imported, but the second one is still a duplicate of it
+      (
+        3,
+        &Problem::Duplicate {
+          field: "accession_number".to_string(),
+          value: "A-1".to_string(),
+          first_line: 2,
+        }
+      ),
+      // Amounts aren't rounded to the cent
+      (
+        4,
+        &Problem::FractionOfCent {
+          field: "total".to_string(),
+          value: "10.005".to_string(),
+        }
+      ),
+      (
+        4,
+        &Problem::FractionOfCent {
+          field: "the price of 'Histo'".to_string(),
+          value: "10.005".to_string(),
+        }
+      ),
+    ]
+  );
+  assert_eq!(loaded.rows.keys().collect::<Vec<_>>(), vec!["A-3"]);
+  assert_eq!(loaded.rows["A-3"].total.to_string(), "21.00");
+}
+
+#[test]
+fn renders_the_report() {
+  let subs = ScratchFile::new(
+    [
+      HEADERS.to_string(),
+      submission("A-1", "ACME", "", "oops", ""),
+    ]
+    .join("\n"),
+  );
+  let loaded = common::read_subs(&subs, None, None);
+
+  let mut report = Report::default();
+  report.add(&subs, &loaded);
+
+  let text = report.render(ReportFormat::Text).unwrap();
+  assert!(text.contains("0 of 1 rows can be imported, with 1 errors and 0 warnings"));
+  assert!(text.contains("  error   Line 2 (A-1): 'oops' is not a valid amount for total"));
+
+  let json: serde_json::Value =
+    serde_json::from_str(&report.render(ReportFormat::Json).unwrap()).unwrap();
+  let issue = &json["files"][0]["issues"][0];
+  assert_eq!(json["files"][0]["errors"], 1);
+  assert_eq!(issue["line"], 2);
+  assert_eq!(issue["key"], "A-1");
+  assert_eq!(issue["problem"]["type"], "BadDecimal");
+  assert_eq!(issue["problem"]["value"]["value"], "oops");
+}
@@ -0,0 +1,202 @@
+//! Turning the rows of the submission log into the records written to the database
+
+mod common;
+
+use chrono::NaiveDate;
+use serde_json::json;
+use wrangler_common::{
+  grapht::{Graph, Properties, Value},
+  model::ModelEdge,
+};
+use wrangler_importer::{merge, reader, writer};
+
+fn load(rows: &[&str]) -> reader::Loaded<String, reader::Submission> {
+  common::load_subs(rows, None)
+}
+
+/// The invoices the submissions add up to, whatever order they are read in
+fn rebuild(subs: &reader::Loaded<String, reader::Submission>) -> Vec<writer::InvoiceRow> {
+  let mut invoices = writer::Invoices::default();
+  for sub in subs.rows.values() {
+    invoices.add(sub);
+  }
+  invoices.rows()
+}
+
+#[test]
+fn writes_line_items_as_services() {
+  let subs = load(&[
+    "A-1\tACME\tDr. Vet\tNecropsy\t[\"Necropsy\", 1, 50.00], [\"Histo\", 2.5, 10.10]\tCanine\tRex\t1/2/2023\t1/9/2023\t\t75.25\t\t1/20/2023\t\t",
+  ]);
+  let sub = &subs.rows["A-1"];
+
+  let items = writer::line_items(sub);
+  assert_eq!(items.len(), 2);
+  assert_eq!(items[1].name, "Histo");
+  assert_eq!(items[1].quantity, "2.5".parse().unwrap());
+  assert_eq!(items[1].price, "10.10".parse().unwrap());
+  assert_eq!(items[1].finished_on, NaiveDate::from_ymd_opt(2023, 1, 9));
+  assert!(items[1].paid);
+
+  // Importing the row again finds the same services
+  let again = writer::line_items(sub);
+  assert_eq!(items[0].guid, again[0].guid);
+  assert_ne!(items[0].guid, items[1].guid);
+  assert_eq!(
+    items[0].guid,
+    merge::guid(merge::SERVICE, "A-1/0"),
+    "services are numbered within their submission"
+  );
+
+  let record = writer::service_record(sub, 1, &items[1]);
+  assert_eq!(record.key, "A-1 #2");
+  assert_eq!(record.fields["quantity"], json!("2.5"));
+  assert_eq!(record.fields["price"], json!("10.10"));
+  assert_eq!(record.fields["started_on"], json!("2023-01-02"));
+}
+
+#[test]
+fn rebuilds_invoices_and_payments() {
+  let subs = load(&[
+    "A-1\tACME\tDr. Vet\tNecropsy\t\tCanine\tRex\t\t\t\t50.00\t2/1/2023\t2/20/2023\t2/21/2023\t12",
+    "A-2\tACME-EAST\tDr. Vet\tNecropsy\t\tFeline\tTom\t\t\t\t20.50\t1/31/2023\t2/20/2023\t2/21/2023\t12",
+    "A-3\tACME\tDr. Vet\tBiopsy\t\tCanine\tRex\t\t\t\t10.00\t2/1/2023\t3/5/2023\t\t12",
+    "A-4\tACME\tDr. Vet\tBiopsy\t\tCanine\tRex\t\t\t\t30.00\t2/1/2023\t\t\t12",
+    "A-5\tACME\tDr. Vet\tBiopsy\t\tCanine\tRex\t\t\t\t15.00\t\t\t\t",
+  ]);
+
+  // Submissions without an invoice number were never billed
+  let invoices = rebuild(&subs);
+  assert_eq!(invoices.len(), 1);
+  let invoice = &invoices[0];
+  assert_eq!(invoice.number, 12);
+  assert_eq!(invoice.billed_on, NaiveDate::from_ymd_opt(2023, 1, 31));
+  assert_eq!(invoice.total, "110.50".parse().unwrap());
+  assert_eq!(invoice.balance, "30.00".parse().unwrap());
+  assert_eq!(invoice.orgs, vec!["ACME", "ACME-EAST"]);
+  assert_eq!(invoice.submissions[0], subs.rows["A-1"].guid);
+
+  // Submissions paid and deposited together are one payment
+  let payments: Vec<(String, Option<String>, String)> = invoice
+    .payments
+    .iter()
+    .map(|payment| {
+      (
+        payment.paid_on.to_string(),
+        payment.deposited_on.map(|date| date.to_string()),
+        payment.amount.to_string(),
+      )
+    })
+    .collect();
+  assert_eq!(
+    payments,
+    vec![
+      (
+        "2023-02-20".to_string(),
+        Some("2023-02-21".to_string()),
+        "70.50".to_string()
+      ),
+      ("2023-03-05".to_string(), None, "10.00".to_string()),
+    ]
+  );
+
+  let record = writer::payment_record(invoice, &invoice.payments[1]);
+  assert_eq!(record.key, "Invoice 12 paid on 2023-03-05");
+  assert_eq!(record.fields["kind"], json!("Unrecorded"));
+  assert_eq!(record.fields["deposited_on"], json!(null));
+  assert_eq!(record.guid, rebuild(&subs)[0].payments[1].guid(12));
+  assert_eq!(
+    writer::invoice_record(invoice).fields["total"],
+    json!("110.50")
+  );
+}
+
+#[test]
+fn migrates_the_links_of_earlier_imports() {
+  let mut graph = Graph::new();
+  let none = Properties::new();
+  graph
+    .execute(
+      "CREATE (o:Organization {pretty_id: 'ACME'})-[:Submitted]->(s:Submission {accession_number: 'A-1'})
+       CREATE (s)-[:SubmittedBy]->(o)
+       CREATE (s)-[:LineItem {imported: true}]->(l:Service {name: 'Necropsy'})
+       CREATE (l)-[:BelongsTo]->(s)",
+      &none,
+    )
+    .unwrap();
+
+  for _ in 0..2 {
+    for cypher in writer::migrations() {
+      graph.execute(&cypher, &none).unwrap();
+    }
+  }
+
+  let mut labels: Vec<String> = graph.edges().map(|(_, edge)| edge.label.clone()).collect();
+  labels.sort();
+  assert_eq!(
+    labels,
+    vec!["BELONGS_TO", "LINE_ITEM", "SENT_BY", "SUBMITTED"]
+  );
+  let rows = graph
+    .execute(
+      &format!(
+        "MATCH (:Submission)-[r:{}]->(:Service) RETURN r.imported AS imported",
+        ModelEdge::SubmissionLineItem.label()
+      ),
+      &none,
+    )
+    .unwrap();
+  assert_eq!(
+    rows[0]["imported"],
+    Value::Bool(true),
+    "properties are kept"
+  );
+}
+
+#[test]
+fn moves_an_invoice_to_its_new_payer() {
+  let mut graph = Graph::new();
+  let none = Properties::new();
+  graph
+    .execute(
+      "CREATE (root:Organization {pretty_id: 'ROOT'})-[:PARENT_OF]->(:Organization {pretty_id: 'MID'})
+       CREATE (old:Organization {pretty_id: 'OLD'})
+       CREATE (i:Invoice {guid: 'i-1'})-[:BILLED_TO]->(old)
+       CREATE (p:Payment {guid: 'p-1'})-[:PAYS]->(i)
+       CREATE (p)-[:PAID_BY]->(old)
+       CREATE (:Submission {guid: 's-1'})-[:LINE_ITEM]->(:Service {guid: 'l-1'})",
+      &none,
+    )
+    .unwrap();
+
+  // MID now sends the submission, so its top org pays instead
+  let row: Properties = [
+    ("invoice", Value::from("i-1")),
+    ("submissions", Value::List(vec![Value::from("s-1")])),
+    ("orgs", Value::List(vec![Value::from("MID")])),
+    ("payments", Value::List(vec![Value::from("p-1")])),
+  ]
+  .into_iter()
+  .map(|(key, value)| (key.to_string(), value))
+  .collect();
+  let params: Properties = [("rows".to_string(), Value::List(vec![Value::Map(row)]))].into();
+  for (_, cypher) in writer::invoice_steps() {
+    graph.execute(&cypher, &params).unwrap();
+  }
+
+  let rows = graph
+    .execute(
+      &format!(
+        "MATCH (:Invoice)-[:{}]->(invoice:Organization)
+         MATCH (:Payment)-[:{}]->(payment:Organization)
+         RETURN invoice.pretty_id AS invoice, payment.pretty_id AS payment",
+        ModelEdge::InvoiceOrganization.label(),
+        ModelEdge::PaymentOrganization.label()
+      ),
+      &none,
+    )
+    .unwrap();
+  assert_eq!(rows.len(), 1, "the old payer is no longer linked");
+  assert_eq!(rows[0]["invoice"], Value::from("ROOT"));
+  assert_eq!(rows[0]["payment"], Value::from("ROOT"));
+}
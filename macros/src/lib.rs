@@ -0,0 +1,184 @@
+//! Derive macros used to cut down on the boilerplate of the wrangler data model
+//!
+//! `#[derive(Accessible)]` reads the named fields of a struct and generates:
+//!
+//! - `{Struct}Field`: a unit enum with one variant per field
+//! - `{Struct}FieldValue`: a tuple enum wrapping the value of each field
+//! - An implementation of `wrangler_common::model::Accessible` tying the two together
+//!
+//! Fields can be adjusted with the `accessible` attribute:
+//!
+//! - `#[accessible(skip)]`: leave the field out of the generated enums and accessors
+//! - `#[accessible(rename = "Name")]`: use the given variant name instead of the CamelCased field
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident, LitStr};
+
+#[proc_macro_derive(Accessible, attributes(accessible))]
+pub fn derive_accessible(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  match expand(input) {
+    Ok(tokens) => tokens.into(),
+    Err(err) => err.to_compile_error().into(),
+  }
+}
+
+/// A single field that will be exposed through the Accessible enums
+struct AccessibleField {
+  ident: Ident,
+  variant: Ident,
+  ty: syn::Type,
+  docs: Vec<Attribute>,
+}
+
+/// The options set by an `#[accessible(...)]` attribute on a field
+#[derive(Default)]
+struct FieldOptions {
+  skip: bool,
+  rename: Option<LitStr>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+  let name = &input.ident;
+  let vis = &input.vis;
+
+  if !input.generics.params.is_empty() {
+    return Err(syn::Error::new_spanned(
+      &input.generics,
+      "Accessible cannot be derived for generic structs",
+    ));
+  }
+
+  let fields = match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(named) => &named.named,
+      _ => {
+        return Err(syn::Error::new_spanned(
+          name,
+          "Accessible can only be derived for structs with named fields",
+        ))
+      }
+    },
+    _ => {
+      return Err(syn::Error::new_spanned(
+        name,
+        "Accessible can only be derived for structs",
+      ))
+    }
+  };
+
+  let mut accessible = Vec::new();
+  for field in fields {
+    let options = parse_options(&field.attrs)?;
+    if options.skip {
+      continue;
+    }
+
+    let ident = field.ident.clone().expect("Named fields always have an ident");
+    let variant = match options.rename {
+      Some(lit) => lit.parse::<Ident>()?,
+      None => Ident::new(&to_camel_case(&ident.to_string()), Span::call_site()),
+    };
+
+    accessible.push(AccessibleField {
+      ident,
+      variant,
+      ty: field.ty.clone(),
+      docs: field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .cloned()
+        .collect(),
+    });
+  }
+
+  let field_enum = format_ident!("{}Field", name);
+  let value_enum = format_ident!("{}FieldValue", name);
+
+  let idents: Vec<&Ident> = accessible.iter().map(|field| &field.ident).collect();
+  let variants: Vec<&Ident> = accessible.iter().map(|field| &field.variant).collect();
+  let types: Vec<&syn::Type> = accessible.iter().map(|field| &field.ty).collect();
+  let docs: Vec<&Vec<Attribute>> = accessible.iter().map(|field| &field.docs).collect();
+
+  let field_doc = format!("The accessible fields of [{}]", name);
+  let value_doc = format!("A wrapped value for each accessible field of [{}]", name);
+
+  Ok(quote! {
+    #[doc = #field_doc]
+    #[derive(Clone, Debug)]
+    #vis enum #field_enum {
+      #( #(#docs)* #variants, )*
+    }
+
+    #[doc = #value_doc]
+    #[derive(Clone, Debug)]
+    #vis enum #value_enum {
+      #( #(#docs)* #variants(#types), )*
+    }
+
+    #[automatically_derived]
+    impl ::wrangler_common::model::Accessible for #name {
+      type Struct = #name;
+
+      type Field = #field_enum;
+
+      type FieldValue = #value_enum;
+
+      fn fields() -> ::std::vec::Vec<Self::Field> {
+        ::std::vec![ #( #field_enum::#variants, )* ]
+      }
+
+      fn get(&self, field: Self::Field) -> Self::FieldValue {
+        match field {
+          #( #field_enum::#variants => #value_enum::#variants(::core::clone::Clone::clone(&self.#idents)), )*
+        }
+      }
+
+      fn set(&mut self, value: Self::FieldValue) {
+        match value {
+          #( #value_enum::#variants(inner) => self.#idents = inner, )*
+        }
+      }
+    }
+  })
+}
+
+/// Read all the `#[accessible(...)]` attributes attached to a field
+fn parse_options(attrs: &[Attribute]) -> syn::Result<FieldOptions> {
+  let mut options = FieldOptions::default();
+
+  for attr in attrs.iter().filter(|attr| attr.path().is_ident("accessible")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("skip") {
+        options.skip = true;
+        Ok(())
+      } else if meta.path.is_ident("rename") {
+        options.rename = Some(meta.value()?.parse()?);
+        Ok(())
+      } else {
+        Err(meta.error("Unsupported accessible attribute. Expected 'skip' or 'rename'"))
+      }
+    })?;
+  }
+
+  Ok(options)
+}
+
+/// Convert a snake_case field name into the CamelCase used by the enum variants
+fn to_camel_case(field: &str) -> String {
+  field
+    .trim_start_matches("r#")
+    .split('_')
+    .filter(|part| !part.is_empty())
+    .map(|part| {
+      let mut chars = part.chars();
+      match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+      }
+    })
+    .collect()
+}
@@ -0,0 +1,130 @@
+//! Connections to a graph held in the memory of this process
+//!
+//! This allows the server to run without any external database, such as on a laptop without Docker
+//! or in tests. All connections made from the same driver share a single graph.
+//!
+//! When the config names a snapshot file, the graph is loaded from it on connect and written back by
+//! [GraphtConnection::save], letting the graph act as a standalone desktop database.
+
+use super::{GraphDbConnection, GraphDbDriver};
+use crate::local::*;
+use wrangler_common::{
+  configuration::apps::grapht::*,
+  grapht::{cypher, prelude::*, Graph},
+};
+
+use std::{
+  path::{Path, PathBuf},
+  sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// A shared handle to an in-memory graph. Clones point at the same graph.
+#[derive(Clone, Default)]
+pub struct GraphtConnection {
+  graph: Arc<RwLock<Graph>>,
+
+  /// Where the graph is persisted, if anywhere
+  snapshot: Option<PathBuf>,
+}
+
+#[track_caller]
+fn poisoned() -> AllWhat<WranglerErrorKind> {
+  let err: AllWhat<WranglerErrorKind> = GraphDbError.into();
+  err.set_context("The in-memory graph was poisoned by a thread that panicked while writing")
+}
+
+impl GraphtConnection {
+  pub fn new() -> GraphtConnection {
+    GraphtConnection::default()
+  }
+
+  /// Wrap an existing graph
+  pub fn from_graph(graph: Graph) -> GraphtConnection {
+    GraphtConnection {
+      graph: Arc::new(RwLock::new(graph)),
+      snapshot: None,
+    }
+  }
+
+  /// Open the graph stored in a snapshot, starting empty if the file doesn't exist yet
+  pub fn open(path: &Path) -> Result<GraphtConnection> {
+    let graph = match path.exists() {
+      true => Graph::load(path)?,
+      false => Graph::new(),
+    };
+
+    Ok(GraphtConnection {
+      snapshot: Some(path.to_path_buf()),
+      ..GraphtConnection::from_graph(graph)
+    })
+  }
+
+  /// The file the graph is persisted to
+  pub fn get_snapshot(&self) -> Option<&Path> {
+    self.snapshot.as_deref()
+  }
+
+  /// Write the graph to its snapshot file. This does nothing if there isn't one.
+  pub fn save(&self) -> Result<()> {
+    match &self.snapshot {
+      Some(path) => self.read()?.save(path),
+      None => Ok(()),
+    }
+  }
+
+  /// Lock the graph for reading, allowing direct lookups and traversals
+  pub fn read(&self) -> Result<RwLockReadGuard<'_, Graph>> {
+    self.graph.read().map_err(|_| poisoned())
+  }
+
+  /// Lock the graph for writing
+  pub fn write(&self) -> Result<RwLockWriteGuard<'_, Graph>> {
+    self.graph.write().map_err(|_| poisoned())
+  }
+}
+
+impl fmt::Debug for GraphtConnection {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.graph.read() {
+      Ok(graph) => write!(
+        f,
+        "GraphtConnection({} nodes, {} edges)",
+        graph.node_count(),
+        graph.edge_count()
+      ),
+      Err(_) => write!(f, "GraphtConnection(poisoned)"),
+    }
+  }
+}
+
+impl GraphDbConnection for GraphtConnection {
+  fn create(&self, node: Box<dyn GraphtNode>) -> Result<()> {
+    self.write()?.insert(node.as_ref())?;
+    Ok(())
+  }
+
+  fn relate(&self, edge: Box<dyn GraphtEdge>) -> Result<()> {
+    self.write()?.relate(edge.as_ref())?;
+    Ok(())
+  }
+
+  fn find(&self, query: &str, params: Properties) -> Result<Vec<Row>> {
+    let parsed = cypher::parse(query)?;
+    let rows = match parsed.is_read_only() {
+      true => self.read()?.run_read_only(&parsed, &params),
+      false => self.write()?.run(&parsed, &params),
+    };
+    rows.map_err(|err| err.set_dev_context(query))
+  }
+}
+
+impl GraphDbDriver for GraphtConfig {
+  type Connection = GraphtConnection;
+
+  fn connect(&self, _db_name: &str) -> Result<GraphtConnection> {
+    Ok(GraphtConnection {
+      graph: self.get_graph()?,
+      snapshot: self.get_snapshot().map(Path::to_path_buf),
+    })
+  }
+}
@@ -2,19 +2,28 @@
 
 use crate::local::*;
 
-use wrangler_common::{configuration::apps::neo4j::*, grapht::prelude::*};
+use wrangler_common::{
+  configuration::apps::{grapht::*, neo4j::*},
+  grapht::prelude::*,
+};
 
 pub mod neo4j;
 use neo4j::Neo4jConnection;
 
+pub mod grapht;
+use grapht::GraphtConnection;
+
 /// A common interface tha all Graph Databases are expected to implement. It is meant to grab a
 /// connection from a backend pool.
 pub trait GraphDbConnection {
+  /// Add a new node to the graph
   fn create(&self, node: Box<dyn GraphtNode>) -> Result<()>;
 
+  /// Link two existing nodes, failing if either of them cannot be found
   fn relate(&self, edge: Box<dyn GraphtEdge>) -> Result<()>;
 
-  fn find(&self, query: &str);
+  /// Run a query with the parameters bound by name, returning every row of the result
+  fn find(&self, query: &str, params: Properties) -> Result<Vec<Row>>;
 }
 
 /// How to create a specific connection value based on a config
@@ -22,24 +31,24 @@ pub trait GraphDbDriver {
   type Connection: GraphDbConnection;
 
   /// Initialize a connection pool and verify the driver settings
-  fn connect(&self, db_name: &str) -> Result<()>;
-
-  fn get_connection(&self) -> Result<Self::Connection>;
+  fn connect(&self, db_name: &str) -> Result<Self::Connection>;
 }
 
 /// An enumeration of all the implemented graph database drivers
 #[derive(Debug, Clone)]
 pub enum Driver {
   Neo4j(Neo4jConfig),
-  // TODO: This will eventually be an in memory cache of the database.
-  // Grapht(GraphtConfig),
+
+  /// A graph kept in memory, for running without a database server
+  Grapht(GraphtConfig),
 }
 
 impl Driver {
   /// Initialize a connection pool for the given driver, targeting the specific database name
-  fn connect(&self, db_name: &str) -> Result<Neo4jConnection> {
+  fn connect(&self, db_name: &str) -> Result<Connection> {
     match self {
-      Driver::Neo4j(driver) => driver.connect(db_name),
+      Driver::Neo4j(driver) => Ok(Connection::Neo4j(driver.connect(db_name)?)),
+      Driver::Grapht(driver) => Ok(Connection::Grapht(driver.connect(db_name)?)),
     }
   }
 }
@@ -50,41 +59,117 @@ impl Default for Driver {
   }
 }
 
+/// A connection pool for one of the implemented drivers
+#[derive(Clone, Debug)]
+pub enum Connection {
+  Neo4j(Neo4jConnection),
+  Grapht(GraphtConnection),
+}
+
+impl GraphDbConnection for Connection {
+  fn create(&self, node: Box<dyn GraphtNode>) -> Result<()> {
+    match self {
+      Connection::Neo4j(conn) => conn.create(node),
+      Connection::Grapht(conn) => conn.create(node),
+    }
+  }
+
+  fn relate(&self, edge: Box<dyn GraphtEdge>) -> Result<()> {
+    match self {
+      Connection::Neo4j(conn) => conn.relate(edge),
+      Connection::Grapht(conn) => conn.relate(edge),
+    }
+  }
+
+  fn find(&self, query: &str, params: Properties) -> Result<Vec<Row>> {
+    match self {
+      Connection::Neo4j(conn) => conn.find(query, params),
+      Connection::Grapht(conn) => conn.find(query, params),
+    }
+  }
+}
+
 /// A generic interface for interacting with a single graph.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct GraphDb {
   /// Configuration for the database
   driver: Driver,
 
   /// An connection pool for communicating with the defined driver.
-  ///
-  /// This should be a generic connection, but in the interest of getting the pathway working it is
-  /// going to assume a Neo4j database.
-  connection: Option<Neo4jConnection>,
+  connection: Option<Connection>,
 
   /// The name of the graph in the server, the equivalent of a single database in a relational DB.
   db_name: String,
 }
 
 impl GraphDb {
+  pub fn new(driver: Driver, db_name: &str) -> GraphDb {
+    GraphDb {
+      driver,
+      connection: None,
+      db_name: db_name.to_string(),
+    }
+  }
+
+  pub fn get_driver(&self) -> &Driver {
+    &self.driver
+  }
+
+  pub fn get_db_name(&self) -> &str {
+    &self.db_name
+  }
+
   /// Create a connection pool if it doesn't already exist
   pub fn init(&mut self) -> Result<()> {
-    self.connection = Some(self.driver.connect(&self.db_name)?);
+    if self.connection.is_none() {
+      self.connection = Some(self.driver.connect(&self.db_name)?);
+    }
     Ok(())
   }
 
-  pub fn query(&self) -> Result<()> {
+  /// Get a handle to the connection pool
+  pub fn get_connection(&self) -> Result<Connection> {
+    match &self.connection {
+      Some(conn) => Ok(conn.clone()),
+      None => {
+        let err: AllWhat<WranglerErrorKind> = GraphDbError.into();
+        Err(err.set_context(&format!(
+          "The graph database '{}' has not been initialized",
+          self.db_name
+        )))
+      }
+    }
+  }
+
+  /// Run a trivial query to make sure the database is reachable
+  pub fn verify(&self) -> Result<()> {
+    self
+      .query("RETURN 1 AS ok", Properties::new())
+      .set_context(&format!(
+        "The graph database '{}' did not respond",
+        self.db_name
+      ))?;
     Ok(())
   }
+
+  /// Run a query against the graph
+  pub fn query(&self, query: &str, params: Properties) -> Result<Vec<Row>> {
+    self.get_connection()?.find(query, params)
+  }
+
+  /// Write any data held in memory to disk. Database servers persist on their own, so this only
+  /// affects the in-memory graph.
+  pub fn save(&self) -> Result<()> {
+    match &self.connection {
+      Some(Connection::Grapht(conn)) => conn.save(),
+      _ => Ok(()),
+    }
+  }
 }
 
 /// Use a the bolt protocol on a local instance of Neo4j by default
 impl Default for GraphDb {
   fn default() -> GraphDb {
-    GraphDb {
-      driver: Driver::default(),
-      connection: None,
-      db_name: "DefaultGraph".to_string(),
-    }
+    GraphDb::new(Driver::default(), "neo4j")
   }
 }
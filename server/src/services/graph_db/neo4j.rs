@@ -1,70 +1,257 @@
 //! Connections to an instance of Neo4j
 //!
 //! Building this as a blocking client for now until I decide how to integrate Tokio
+//!
+//! Every value is passed to the server as a bound parameter. Labels and relationship types cannot be
+//! parameterized in Cypher, so they are checked to be plain identifiers before being written into a
+//! query.
 
 use super::{GraphDbConnection, GraphDbDriver};
 use crate::local::*;
-use wrangler_common::{configuration::apps::neo4j::*, prelude::Result as AWResult};
+use wrangler_common::{
+  configuration::{apps::neo4j::*, traits::Configuration},
+  grapht::{self, prelude::*, Edge, Node},
+  prelude::Result as AWResult,
+};
+
+use neo4rs::{
+  query, BoltBoolean, BoltFloat, BoltInteger, BoltList, BoltMap, BoltNull, BoltString, BoltType,
+  ConfigBuilder, Graph, Query,
+};
+use std::{collections::HashMap, sync::Arc};
 
-// use futures::stream::*;
-use neo4rs::*;
-// use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
-// use uuid::Uuid;
+/// Wrap an error raised by the neo4rs driver
+#[track_caller]
+fn graph_error(
+  ctx: &str,
+  err: impl Into<AllWhat<WranglerErrorKind>>,
+) -> AllWhat<WranglerErrorKind> {
+  err.into().set_context(ctx)
+}
+
+/// Ensure a label can be written directly into a query
+fn checked_label(label: &str) -> AWResult<&str> {
+  match grapht::is_identifier(label) {
+    true => Ok(label),
+    false => {
+      let err: AllWhat<WranglerErrorKind> = ValidationError.into();
+      Err(err.set_context(&format!("'{}' is not a valid label", label)))
+    }
+  }
+}
+
+/// Convert a graph value into a query parameter
+fn to_bolt(value: &Value) -> BoltType {
+  match value {
+    Value::Null => BoltType::Null(BoltNull),
+    Value::Bool(inner) => BoltType::Boolean(BoltBoolean::new(*inner)),
+    Value::Integer(inner) => BoltType::Integer(BoltInteger::new(*inner)),
+    Value::Float(inner) => BoltType::Float(BoltFloat::new(*inner)),
+    Value::String(inner) => BoltType::String(BoltString::new(inner)),
+    Value::List(items) => BoltType::List(BoltList {
+      value: items.iter().map(to_bolt).collect(),
+    }),
+    Value::Map(props)
+    | Value::Node(Node {
+      properties: props, ..
+    }) => BoltType::Map(to_bolt_map(props)),
+    Value::Edge(Edge { properties, .. }) => BoltType::Map(to_bolt_map(properties)),
+  }
+}
+
+fn to_bolt_map(props: &Properties) -> BoltMap {
+  BoltMap {
+    value: props
+      .iter()
+      .map(|(key, value)| (BoltString::new(key), to_bolt(value)))
+      .collect(),
+  }
+}
+
+/// Convert a value returned by the server into a graph value
+fn from_bolt(value: BoltType) -> Value {
+  match value {
+    BoltType::Null(_) => Value::Null,
+    BoltType::Boolean(inner) => Value::Bool(inner.value),
+    BoltType::Integer(inner) => Value::Integer(inner.value),
+    BoltType::Float(inner) => Value::Float(inner.value),
+    BoltType::String(inner) => Value::String(inner.value),
+    BoltType::List(inner) => Value::List(inner.value.into_iter().map(from_bolt).collect()),
+    BoltType::Map(inner) => Value::Map(from_bolt_map(inner)),
+    BoltType::Node(node) => Value::Node(Node {
+      labels: node
+        .labels
+        .value
+        .into_iter()
+        .map(|label| label.to_string())
+        .collect(),
+      properties: from_bolt_map(node.properties),
+    }),
+    BoltType::Relation(rel) => Value::Edge(Edge {
+      label: rel.typ.value,
+      properties: from_bolt_map(rel.properties),
+    }),
+    BoltType::UnboundedRelation(rel) => Value::Edge(Edge {
+      label: rel.typ.value,
+      properties: from_bolt_map(rel.properties),
+    }),
+    // Temporal and spatial values are passed along in their printed form
+    other => Value::String(other.to_string()),
+  }
+}
+
+fn from_bolt_map(map: BoltMap) -> Properties {
+  map
+    .value
+    .into_iter()
+    .map(|(key, value)| (key.value, from_bolt(value)))
+    .collect()
+}
 
+/// A pooled connection to a single Neo4j database. Clones share the same pool.
+#[derive(Clone)]
 pub struct Neo4jConnection {
   /// Tokio::Runtime - an async loop for temporarily making the connection synchronous.
-  rt: tokio::runtime::Runtime,
+  rt: Arc<tokio::runtime::Runtime>,
 
   graph: Arc<Graph>,
+
+  /// Where the connection points, for display purposes
+  uri: String,
+  db_name: String,
 }
 
 impl Neo4jConnection {
-  pub fn init(driver: &Neo4jConfig) -> AWResult<Self> {
+  pub fn init(driver: &Neo4jConfig, db_name: &str) -> AWResult<Self> {
     let uri = driver.get_uri();
-    let user = driver.get_username();
-    let pass = driver.get_password().to_string();
 
     let rt = tokio::runtime::Builder::new_current_thread()
       .enable_all()
       .build()?;
 
+    let config = ConfigBuilder::default()
+      .uri(uri.clone())
+      .user(driver.get_username())
+      .password(driver.get_password().value())
+      .db(db_name)
+      .build()
+      .map_err(|err| graph_error("Invalid Neo4j configuration", err))?;
+
     // Doing this manually because adding the neo4j library to common breaks
-    let graph = rt.block_on(Graph::new(uri, user, pass)).map_err(|err| {
-      let result: AllWhat<WranglerErrorKind> = WranglerErrorKind::GraphDbError.into();
-      result.set_context(&format!("From <std::io::Error>:\n{:#?}", err))
-    })?;
+    let graph = rt
+      .block_on(Graph::connect(config))
+      .map_err(|err| graph_error(&format!("Could not connect to Neo4j at {}", uri), err))?;
 
     Ok(Neo4jConnection {
-      rt,
+      rt: Arc::new(rt),
       graph: Arc::new(graph),
+      uri,
+      db_name: db_name.to_string(),
+    })
+  }
+
+  /// Run a single query and collect all of its rows
+  fn execute(&self, query: Query) -> AWResult<Vec<Row>> {
+    let graph = self.graph.clone();
+    self.rt.block_on(async move {
+      let mut stream = graph
+        .execute(query)
+        .await
+        .map_err(|err| graph_error("Query failed", err))?;
+
+      let mut rows = Vec::new();
+      while let Some(row) = stream
+        .next()
+        .await
+        .map_err(|err| graph_error("Failed reading the query results", err))?
+      {
+        let fields: HashMap<String, BoltType> = row
+          .to()
+          .map_err(|err| graph_error("Could not decode a result row", err))?;
+
+        rows.push(
+          fields
+            .into_iter()
+            .map(|(key, value)| (key, from_bolt(value)))
+            .collect(),
+        );
+      }
+      Ok(rows)
     })
   }
 }
 
+impl fmt::Debug for Neo4jConnection {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Neo4jConnection({}/{})", self.uri, self.db_name)
+  }
+}
+
 impl GraphDbConnection for Neo4jConnection {
-  fn create(&self, node: Box<dyn wrangler_common::grapht::prelude::GraphtNode>) -> AWResult<()> {
-    todo!()
+  fn create(&self, node: Box<dyn GraphtNode>) -> AWResult<()> {
+    let label = checked_label(node.label())?;
+    let create = query(&format!("CREATE (n:{}) SET n = $properties", label))
+      .param("properties", BoltType::Map(to_bolt_map(&node.properties())));
+
+    self
+      .execute(create)
+      .set_context(&format!("Could not create {} {}", label, node.guid()))?;
+    Ok(())
   }
 
-  fn relate(&self, edge: Box<dyn wrangler_common::grapht::prelude::GraphtEdge>) -> AWResult<()> {
-    todo!()
+  fn relate(&self, edge: Box<dyn GraphtEdge>) -> AWResult<()> {
+    let (source, target) = (edge.source(), edge.target());
+    let relate = query(&format!(
+      "MATCH (a:{} {{guid: $source}})
+       MATCH (b:{} {{guid: $target}})
+       MERGE (a)-[r:{}]->(b)
+       SET r = $properties
+       RETURN count(r) AS linked",
+      checked_label(source.label)?,
+      checked_label(target.label)?,
+      checked_label(edge.label())?,
+    ))
+    .param("source", source.guid.to_string())
+    .param("target", target.guid.to_string())
+    .param("properties", BoltType::Map(to_bolt_map(&edge.properties())));
+
+    let rows = self.execute(relate)?;
+    let linked = rows
+      .first()
+      .and_then(|row| row.get("linked"))
+      .and_then(|linked| linked.as_i64())
+      .unwrap_or(0);
+
+    match linked {
+      0 => {
+        let err: AllWhat<WranglerErrorKind> = GraphDbError.into();
+        Err(err.set_context(&format!(
+          "Could not link {} {} -[{}]-> {} {}: one of the nodes does not exist",
+          source.label,
+          source.guid,
+          edge.label(),
+          target.label,
+          target.guid
+        )))
+      }
+      _ => Ok(()),
+    }
   }
 
-  fn find(&self, query: &str) {
-    todo!()
+  fn find(&self, cypher: &str, params: Properties) -> AWResult<Vec<Row>> {
+    let find = query(cypher).params(
+      params
+        .iter()
+        .map(|(key, value)| (key.as_str(), to_bolt(value))),
+    );
+    self.execute(find)
   }
 }
 
 impl GraphDbDriver for Neo4jConfig {
   type Connection = Neo4jConnection;
 
-  fn connect(&self) -> AWResult<()> {
-    Neo4jConnection::init(self)?;
-    Ok(())
-  }
-
-  fn get_connection(&self) -> AWResult<Neo4jConnection> {
-    todo!("GraphDb Driver - get connection")
+  fn connect(&self, db_name: &str) -> AWResult<Neo4jConnection> {
+    Neo4jConnection::init(self, db_name)
   }
 }
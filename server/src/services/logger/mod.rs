@@ -0,0 +1,70 @@
+//! Capture tracing events to a file in the workspace's log directory
+
+use crate::local::*;
+
+use std::{
+  fs::{self, File, OpenOptions},
+  io::Write,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+/// The name of the file events are appended to
+pub const LOG_FILE: &str = "wrangler.log";
+
+/// A sink that appends tracing events to a log file. Clones share the same file.
+#[derive(Clone)]
+pub struct Logger {
+  path: PathBuf,
+  file: Arc<File>,
+
+  /// Whether this sink receives the events of the whole process. Only the first logger created in
+  /// a process can, so later ones only hold the file open.
+  global: bool,
+}
+
+impl Logger {
+  /// Open the log file in the given directory, creating it if needed, and send events to it
+  pub fn init(dir: &Path) -> Result<Logger> {
+    let path = dir.join(LOG_FILE);
+    let opened = fs::create_dir_all(dir)
+      .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+    let file = match opened {
+      Ok(file) => Arc::new(file),
+      Err(err) => {
+        let result: AllWhat<WranglerErrorKind> = err.into();
+        return Err(
+          result.set_context(&format!("Could not open the log file '{}'", path.display())),
+        );
+      }
+    };
+
+    let subscriber = tracing_subscriber::fmt()
+      .with_writer(file.clone())
+      .with_ansi(false)
+      .finish();
+    let global = tracing::subscriber::set_global_default(subscriber).is_ok();
+
+    Ok(Logger { path, file, global })
+  }
+
+  pub fn get_path(&self) -> &Path {
+    &self.path
+  }
+
+  pub fn is_global(&self) -> bool {
+    self.global
+  }
+
+  /// Make sure everything logged so far has been written to disk
+  pub fn flush(&self) -> Result<()> {
+    (&*self.file).flush()?;
+    Ok(())
+  }
+}
+
+impl fmt::Debug for Logger {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Logger({})", self.path.display())
+  }
+}
@@ -4,6 +4,8 @@ pub mod graph_db;
 
 pub mod docker;
 
+pub mod logger;
+
 pub enum Service {
   GraphDb(graph_db::GraphDb),
 }
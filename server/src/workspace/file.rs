@@ -0,0 +1,536 @@
+//! Store the workspace configuration in a local TOML file
+//!
+//! ```toml
+//! [locations]
+//! log = "./local/logs"
+//! secrets = "./local/secrets"
+//! secret_key = "/home/you/.config/wrangler/secrets.key"  # outside the workspace, for the store
+//!
+//! [services]
+//! logger = true
+//! docker = false
+//!
+//! [services.wrangler_db]
+//! driver = "neo4j"  # or "grapht" to keep the data in a local file
+//! db_name = "neo4j"
+//!
+//! [services.wrangler_db.neo4j]
+//! uri = "bolt://127.0.0.1:7687"
+//! user = "neo4j"
+//! password = { store = "neo4j" }  # or { env = "VAR" }, { file = "path" } or the password itself
+//!
+//! [services.wrangler_db.grapht]
+//! snapshot = "./local/wrangler.grapht"
+//! ```
+//!
+//! Every key is optional, falling back to the defaults above, and any of them listed in [OVERRIDES]
+//! can be replaced by an environment variable. Validation reports every bad key at once, naming the
+//! environment variable when that is where the value came from.
+//!
+//! A password is never written back to the file. It is saved as the place it was read from. One that
+//! was given directly is moved into the secret store by [WorkspaceConfig::save], and is otherwise
+//! shown as `*******`.
+
+use crate::local::*;
+
+use super::{
+  secrets::{self, SecretStore},
+  Locations, ServiceConfigs, WorkspaceConfig,
+};
+use crate::services::{docker::Docker, graph_db::*};
+use wrangler_common::configuration::{
+  apps::{grapht::GraphtConfig, neo4j::Neo4jConfig},
+  primitives::{
+    password::{Password, PasswordSource},
+    path::{PathConfig, PathConstraint},
+    uri::UriConfig,
+  },
+  traits::Configuration,
+};
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// The name a database password given directly is kept under in the secret store
+pub const NEO4J_SECRET: &str = "neo4j";
+
+/// Environment variables that replace a key of the file, and the key they replace
+pub const OVERRIDES: [(&str, &str); 10] = [
+  ("WRANGLER_LOG_DIR", "locations.log"),
+  ("WRANGLER_SECRETS_DIR", "locations.secrets"),
+  ("WRANGLER_SECRET_KEY_FILE", "locations.secret_key"),
+  ("WRANGLER_DB_DRIVER", "services.wrangler_db.driver"),
+  ("WRANGLER_DB_NAME", "services.wrangler_db.db_name"),
+  ("WRANGLER_NEO4J_URI", "services.wrangler_db.neo4j.uri"),
+  ("WRANGLER_NEO4J_USER", "services.wrangler_db.neo4j.user"),
+  (
+    "WRANGLER_NEO4J_PASSWORD",
+    "services.wrangler_db.neo4j.password",
+  ),
+  (
+    "WRANGLER_GRAPHT_SNAPSHOT",
+    "services.wrangler_db.grapht.snapshot",
+  ),
+  ("WRANGLER_DOCKER", "services.docker"),
+];
+
+/// The raw contents of a configuration file, before it has been validated
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+  pub locations: LocationsFile,
+  pub services: ServicesFile,
+
+  /// The environment variable each overridden key was read from
+  #[serde(skip)]
+  sources: BTreeMap<String, String>,
+
+  /// Overrides that could not be applied, with the reason why
+  #[serde(skip)]
+  rejected: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocationsFile {
+  pub log: String,
+  pub secrets: String,
+  /// The key of the secret store. Without it, the store can't be used.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub secret_key: Option<String>,
+}
+
+impl Default for LocationsFile {
+  fn default() -> LocationsFile {
+    LocationsFile {
+      log: "./local/logs".to_string(),
+      secrets: "./local/secrets".to_string(),
+      secret_key: None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServicesFile {
+  /// Capture tracing events in the log directory
+  pub logger: bool,
+  pub docker: bool,
+  pub wrangler_db: GraphDbFile,
+}
+
+impl Default for ServicesFile {
+  fn default() -> ServicesFile {
+    ServicesFile {
+      logger: true,
+      docker: false,
+      wrangler_db: GraphDbFile::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GraphDbFile {
+  /// Which of the sections below to connect with: "neo4j" or "grapht"
+  pub driver: String,
+  pub db_name: String,
+  pub neo4j: Neo4jFile,
+  pub grapht: GraphtFile,
+}
+
+impl Default for GraphDbFile {
+  fn default() -> GraphDbFile {
+    GraphDbFile {
+      driver: "neo4j".to_string(),
+      db_name: "neo4j".to_string(),
+      neo4j: Neo4jFile::default(),
+      grapht: GraphtFile::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Neo4jFile {
+  pub uri: String,
+  pub user: String,
+  pub password: PasswordFile,
+}
+
+impl Default for Neo4jFile {
+  fn default() -> Neo4jFile {
+    Neo4jFile {
+      uri: "bolt://127.0.0.1:7687".to_string(),
+      user: "neo4j".to_string(),
+      password: PasswordFile::Plain(Password::new("neo4j")),
+    }
+  }
+}
+
+/// Either where to read the password from, or the password itself
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PasswordFile {
+  Source(PasswordSource),
+  Plain(Password),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GraphtFile {
+  /// Without a snapshot, the graph is lost when the process exits
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub snapshot: Option<String>,
+}
+
+impl ConfigFile {
+  /// Read the contents of a configuration file
+  pub fn parse(text: &str) -> Result<ConfigFile> {
+    ConfigFile::parse_named(text, "the workspace configuration")
+  }
+
+  /// Parse the text, naming where it came from in any errors
+  fn parse_named(text: &str, name: &str) -> Result<ConfigFile> {
+    toml::from_str(text).map_err(|err| {
+      let result: AllWhat<WranglerErrorKind> = ConfigError.into();
+      result
+        .set_context(&format!("Could not read {}: {}", name, err.message()))
+        .set_dev_context(&format!("From <toml>:\n{}", err))
+    })
+  }
+
+  /// Replace the keys that have a matching environment variable
+  pub fn with_overrides(self, vars: impl IntoIterator<Item = (String, String)>) -> ConfigFile {
+    let vars: BTreeMap<String, String> = vars.into_iter().collect();
+    OVERRIDES
+      .iter()
+      .fold(self, |file, (var, key)| match vars.get(*var) {
+        Some(value) => file.set(key, var, value),
+        None => file,
+      })
+  }
+
+  fn set(mut self, key: &str, var: &str, value: &str) -> ConfigFile {
+    let db = &mut self.services.wrangler_db;
+    match key {
+      "locations.log" => self.locations.log = value.to_string(),
+      "locations.secrets" => self.locations.secrets = value.to_string(),
+      "locations.secret_key" => self.locations.secret_key = Some(value.to_string()),
+      "services.wrangler_db.driver" => db.driver = value.to_string(),
+      "services.wrangler_db.db_name" => db.db_name = value.to_string(),
+      "services.wrangler_db.neo4j.uri" => db.neo4j.uri = value.to_string(),
+      "services.wrangler_db.neo4j.user" => db.neo4j.user = value.to_string(),
+      "services.wrangler_db.neo4j.password" => {
+        db.neo4j.password =
+          PasswordFile::Plain(Password::new(value).set_source(PasswordSource::Env(var.to_string())))
+      }
+      "services.wrangler_db.grapht.snapshot" => db.grapht.snapshot = Some(value.to_string()),
+      "services.docker" => match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" => self.services.docker = true,
+        "false" | "0" | "no" | "" => self.services.docker = false,
+        _ => self
+          .rejected
+          .push((key.to_string(), format!("'{}' is not true or false", value))),
+      },
+      _ => return self,
+    }
+    self.sources.insert(key.to_string(), var.to_string());
+    self
+  }
+
+  /// An error pointing at a single key, and the environment variable it came from if overridden
+  #[track_caller]
+  fn key_error(&self, key: &str, msg: &str) -> AllWhat<WranglerErrorKind> {
+    let location = match self.sources.get(key) {
+      Some(var) => format!("{} (set by {})", key, var),
+      None => key.to_string(),
+    };
+    let err: AllWhat<WranglerErrorKind> = ConfigError.into();
+    err.set_context(&format!("{}: {}", location, msg))
+  }
+
+  /// Point each of the failed constraints in a validation error at the key
+  fn key_errors(&self, key: &str, err: &AllWhat<WranglerErrorKind>) -> Vec<Result<()>> {
+    match err.get_inner() {
+      [] => vec![Err(self.key_error(key, &message(err)))],
+      inner => inner
+        .iter()
+        .map(|failure| Err(self.key_error(key, &message(failure))))
+        .collect(),
+    }
+  }
+
+  fn not_empty(&self, key: &str, value: &str) -> Result<()> {
+    match value.trim().is_empty() {
+      true => Err(self.key_error(key, "must not be empty")),
+      false => Ok(()),
+    }
+  }
+
+  /// Read the password from wherever the file says it is
+  fn resolve_password(&self) -> Result<Password> {
+    let key = "services.wrangler_db.neo4j.password";
+    let password = match &self.services.wrangler_db.neo4j.password {
+      PasswordFile::Plain(password) if password.is_redacted() => {
+        return Err(self.key_error(
+          key,
+          "was redacted when the file was saved. Set it again, or read it from an env var, file or the secret store",
+        ))
+      }
+      PasswordFile::Plain(password) => Ok(password.clone()),
+      PasswordFile::Source(PasswordSource::Env(var)) => Password::from_env(var),
+      PasswordFile::Source(PasswordSource::File(path)) => Password::from_file(path),
+      PasswordFile::Source(PasswordSource::Store(name)) => SecretStore::open(
+        Path::new(&self.locations.secrets),
+        self.locations.secret_key.as_deref().map(Path::new),
+      )
+      .and_then(|store| store.get(name)),
+    };
+
+    password.map_err(|err| self.key_error(key, &message(&err)))
+  }
+
+  /// Check a path is usable, skipping the constraints when it is empty
+  fn check_path(
+    &self,
+    key: &str,
+    value: &str,
+    constraints: Vec<PathConstraint>,
+  ) -> Vec<Result<()>> {
+    if let Err(err) = self.not_empty(key, value) {
+      return vec![Err(err)];
+    }
+    match PathConfig::new(value).constrain(constraints) {
+      Ok(_) => Vec::new(),
+      Err(err) => self.key_errors(key, &err),
+    }
+  }
+
+  /// Check every key and build the configuration, reporting all of the problems found at once
+  pub fn validate(&self) -> Result<WorkspaceConfig> {
+    let db = &self.services.wrangler_db;
+    let mut checks: Vec<Result<()>> =
+      vec![self.not_empty("services.wrangler_db.db_name", &db.db_name)];
+    checks.extend(self.check_path(
+      "locations.log",
+      &self.locations.log,
+      vec![
+        PathConstraint::Not(Box::new(PathConstraint::IsFile)),
+        PathConstraint::Writable,
+      ],
+    ));
+    if let Some(key) = &self.locations.secret_key {
+      checks.extend(self.check_path(
+        "locations.secret_key",
+        key,
+        vec![
+          PathConstraint::Not(Box::new(PathConstraint::IsDirectory)),
+          PathConstraint::Writable,
+        ],
+      ));
+      if secrets::key_in_store(Path::new(key), Path::new(&self.locations.secrets)) {
+        checks.push(Err(self.key_error(
+          "locations.secret_key",
+          "must be outside the locations.secrets directory, or anyone who can read the store can read the key",
+        )));
+      }
+    }
+    checks.extend(
+      self
+        .rejected
+        .iter()
+        .map(|(key, msg)| Err(self.key_error(key, msg))),
+    );
+
+    // Only the settings of the chosen driver are checked, as the others are unused
+    let driver = match db.driver.to_lowercase().as_str() {
+      "neo4j" => {
+        let key = "services.wrangler_db.neo4j.uri";
+        checks.push(self.not_empty("services.wrangler_db.neo4j.user", &db.neo4j.user));
+        let password = self.resolve_password();
+        match (UriConfig::parse(&db.neo4j.uri), password) {
+          (Err(_), password) => {
+            checks.push(Err(
+              self.key_error(key, &format!("'{}' is not a valid uri", db.neo4j.uri)),
+            ));
+            checks.extend(password.err().map(Err));
+            None
+          }
+          (Ok(_), Err(err)) => {
+            checks.push(Err(err));
+            None
+          }
+          (Ok(_), Ok(password)) => {
+            match Neo4jConfig::new(&db.neo4j.uri, &db.neo4j.user, password) {
+              Ok(config) => Some(Driver::Neo4j(config)),
+              Err(err) => {
+                checks.extend(self.key_errors(key, &err));
+                None
+              }
+            }
+          }
+        }
+      }
+      "grapht" => {
+        let config = GraphtConfig::new();
+        match &db.grapht.snapshot {
+          Some(path) => {
+            checks.extend(self.check_path(
+              "services.wrangler_db.grapht.snapshot",
+              path,
+              vec![
+                PathConstraint::Not(Box::new(PathConstraint::IsDirectory)),
+                PathConstraint::Writable,
+              ],
+            ));
+            Some(Driver::Grapht(config.with_snapshot(path)))
+          }
+          None => Some(Driver::Grapht(config)),
+        }
+      }
+      other => {
+        checks.push(Err(self.key_error(
+          "services.wrangler_db.driver",
+          &format!(
+            "'{}' is not a known driver. Expected 'neo4j' or 'grapht'",
+            other
+          ),
+        )));
+        None
+      }
+    };
+
+    AllWhat::flatten(ConfigError, checks).set_context("The workspace configuration is invalid")?;
+
+    Ok(WorkspaceConfig {
+      locations: Locations {
+        log: PathConfig::new(&self.locations.log),
+        secrets: PathConfig::new(&self.locations.secrets),
+        secret_key: self.locations.secret_key.as_deref().map(PathConfig::new),
+      },
+      services: ServiceConfigs {
+        wrangler_db: GraphDb::new(driver.unwrap_or_default(), &db.db_name),
+        logger: self.services.logger,
+        docker: self.services.docker.then_some(Docker {}),
+      },
+    })
+  }
+
+  /// Write the configuration out as TOML
+  pub fn to_toml(&self) -> Result<String> {
+    toml::to_string_pretty(self).map_err(|err| {
+      let result: AllWhat<WranglerErrorKind> = SerializationError.into();
+      result
+        .set_context("Could not write the workspace configuration")
+        .set_dev_context(&format!("From <toml>:\n{:#?}", err))
+    })
+  }
+}
+
+/// The user facing part of an error, leaving the developer context for the log
+fn message(err: &AllWhat<WranglerErrorKind>) -> String {
+  match err.get_context() {
+    Some(ctx) => ctx.to_string(),
+    None => err.render_context(),
+  }
+}
+
+impl From<&WorkspaceConfig> for ConfigFile {
+  fn from(config: &WorkspaceConfig) -> ConfigFile {
+    let db = &config.services.wrangler_db;
+    let mut wrangler_db = GraphDbFile {
+      db_name: db.get_db_name().to_string(),
+      ..GraphDbFile::default()
+    };
+
+    match db.get_driver() {
+      Driver::Neo4j(neo4j) => {
+        wrangler_db.neo4j = Neo4jFile {
+          uri: neo4j.get_uri(),
+          user: neo4j.get_username(),
+          password: PasswordFile::Plain(neo4j.get_password().clone()),
+        }
+      }
+      Driver::Grapht(grapht) => {
+        wrangler_db.driver = "grapht".to_string();
+        wrangler_db.grapht.snapshot = grapht
+          .get_snapshot()
+          .map(|path| path.to_string_lossy().to_string());
+      }
+    }
+
+    ConfigFile {
+      locations: LocationsFile {
+        log: config.locations.log.get_path().to_string(),
+        secrets: config.locations.secrets.get_path().to_string(),
+        secret_key: config
+          .locations
+          .secret_key
+          .as_ref()
+          .map(|key| key.get_path().to_string()),
+      },
+      services: ServicesFile {
+        logger: config.services.logger,
+        docker: config.services.docker.is_some(),
+        wrangler_db,
+      },
+      ..ConfigFile::default()
+    }
+  }
+}
+
+impl WorkspaceConfig {
+  /// Read and validate a configuration file, applying any overrides set in the environment
+  pub fn load(path: &Path) -> Result<WorkspaceConfig> {
+    let text = fs::read_to_string(path).map_err(|err| {
+      let result: AllWhat<WranglerErrorKind> = err.into();
+      result.set_context(&format!(
+        "Could not read the workspace configuration '{}'",
+        path.display()
+      ))
+    })?;
+
+    ConfigFile::parse_named(&text, &format!("'{}'", path.display()))?
+      .with_overrides(std::env::vars())
+      .validate()
+      .set_context(&format!(
+        "The workspace configuration '{}' is invalid",
+        path.display()
+      ))
+  }
+
+  /// Write the configuration to a file, creating its directory if needed
+  ///
+  /// A database password that wasn't read from anywhere would be redacted in the file, so it is put
+  /// in the secret store and the file points there instead.
+  pub fn save(&self, path: &Path) -> Result<()> {
+    let mut file = ConfigFile::from(self);
+    if let Driver::Neo4j(neo4j) = self.services.wrangler_db.get_driver() {
+      let password = neo4j.get_password();
+      if password.get_source().is_none() {
+        self
+          .secret_store()
+          .and_then(|mut store| store.set(NEO4J_SECRET, password))
+          .set_context("Could not move the database password into the secret store")?;
+        file.services.wrangler_db.neo4j.password =
+          PasswordFile::Source(PasswordSource::Store(NEO4J_SECRET.to_string()));
+      }
+    }
+
+    let text = file.to_toml()?;
+    if let Some(parent) = path
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+    {
+      fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text).map_err(|err| {
+      let result: AllWhat<WranglerErrorKind> = err.into();
+      result.set_context(&format!(
+        "Could not save the workspace configuration to '{}'",
+        path.display()
+      ))
+    })
+  }
+}
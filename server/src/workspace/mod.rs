@@ -1,17 +1,31 @@
 //! A workspace that aggregates all the configured connections the calls would need
 //!
-//! The configuration should be serializable so that it can be stored locally and run on startup.
+//! The configuration is stored locally as a TOML file so it can be run on startup. See [file] for the
+//! format.
 
 use crate::local::*;
 
-use super::services::docker::Docker;
+use super::services::{docker::Docker, logger::Logger};
 use crate::services::graph_db::*;
-use wrangler_common::configuration::primitives::path::*;
+use wrangler_common::configuration::{apps::grapht::GraphtConfig, primitives::path::*};
+
+use std::path::{Path, PathBuf};
+
+// Loading and saving the configuration
+pub mod file;
+
+// What happened while starting the services
+pub mod report;
+
+// Passwords kept out of the configuration file
+pub mod secrets;
+use report::{ServiceStatus, StartupReport};
+use secrets::SecretStore;
 
 #[derive(Debug, Clone)]
 pub struct ServiceConfigs {
-  /// A set of logger sinks for capturing tracing events
-  logger: Option<()>,
+  /// Capture tracing events to a file in the log directory
+  logger: bool,
 
   /// A graph database to store the submission data
   wrangler_db: GraphDb,
@@ -23,7 +37,7 @@ pub struct ServiceConfigs {
 impl Default for ServiceConfigs {
   fn default() -> ServiceConfigs {
     ServiceConfigs {
-      logger: None,
+      logger: true,
       wrangler_db: GraphDb::default(),
       docker: None,
     }
@@ -35,12 +49,20 @@ impl Default for ServiceConfigs {
 pub struct Locations {
   // A path to store logging data
   log: PathConfig,
+
+  // The encrypted secret store
+  secrets: PathConfig,
+
+  // The key of the secret store, which is kept outside the workspace
+  secret_key: Option<PathConfig>,
 }
 
 impl Default for Locations {
   fn default() -> Locations {
     Locations {
       log: PathConfig::new("./local/logs"),
+      secrets: PathConfig::new("./local/secrets"),
+      secret_key: None,
     }
   }
 }
@@ -54,37 +76,229 @@ pub struct WorkspaceConfig {
   services: ServiceConfigs,
 }
 
+impl WorkspaceConfig {
+  /// A workspace that keeps its data in a local file instead of a database server
+  pub fn standalone(snapshot: impl Into<PathBuf>) -> WorkspaceConfig {
+    let driver = Driver::Grapht(GraphtConfig::new().with_snapshot(snapshot));
+    WorkspaceConfig {
+      services: ServiceConfigs {
+        wrangler_db: GraphDb::new(driver, "wrangler"),
+        ..ServiceConfigs::default()
+      },
+      ..WorkspaceConfig::default()
+    }
+  }
+
+  /// Store the logs in a different directory
+  pub fn with_log_dir(self, dir: &str) -> WorkspaceConfig {
+    WorkspaceConfig {
+      locations: Locations {
+        log: PathConfig::new(dir),
+        ..self.locations
+      },
+      ..self
+    }
+  }
+
+  /// Keep the secret store in a different directory
+  pub fn with_secrets_dir(self, dir: &str) -> WorkspaceConfig {
+    WorkspaceConfig {
+      locations: Locations {
+        secrets: PathConfig::new(dir),
+        ..self.locations
+      },
+      ..self
+    }
+  }
+
+  /// Encrypt the secret store with the key in this file, created if it doesn't exist
+  pub fn with_secret_key(self, path: &str) -> WorkspaceConfig {
+    WorkspaceConfig {
+      locations: Locations {
+        secret_key: Some(PathConfig::new(path)),
+        ..self.locations
+      },
+      ..self
+    }
+  }
+
+  /// Open the store holding the passwords of this workspace
+  pub fn secret_store(&self) -> Result<SecretStore> {
+    SecretStore::open(
+      Path::new(self.locations.secrets.get_path()),
+      self
+        .locations
+        .secret_key
+        .as_ref()
+        .map(|key| Path::new(key.get_path())),
+    )
+  }
+}
+
+/// Print the configuration as it would be saved, which never includes the password
+impl fmt::Display for WorkspaceConfig {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match file::ConfigFile::from(self).to_toml() {
+      Ok(text) => write!(f, "{}", text),
+      Err(_) => write!(f, "{:?}", self),
+    }
+  }
+}
+
 /// A singleton designed to give context for all the available tools to a given application
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct Workspace {
   /// Standard OS level configurations, such as logging.
   config: WorkspaceConfig,
 
+  /// Where tracing events are being written
+  logger: Option<Logger>,
+
   /// A graph database to store the submission data
   wrangler_db: Option<GraphDb>,
 
   /// Where to run docker based commands
   docker: Option<Docker>,
+
+  /// Which services started and which failed
+  report: StartupReport,
 }
 
 impl Workspace {
-  // Starts all the services up using the internal configuration
-  pub fn init(config: WorkspaceConfig) -> Result<()> {
+  /// Starts all the services up using the internal configuration
+  ///
+  /// Optional services that fail are recorded in the startup report and left out of the workspace.
+  /// The graph database is required, so this fails if it cannot be reached.
+  pub fn init(config: WorkspaceConfig) -> Result<Workspace> {
+    let mut report = StartupReport::default();
+
     // Start the logger
-    println!("Logging to {:?}", config.locations.log);
+    let log_dir = Path::new(config.locations.log.get_path());
+    let logger = match config.services.logger {
+      false => {
+        report.add("logger", ServiceStatus::Skipped("Disabled".to_string()));
+        None
+      }
+      true => match Logger::init(log_dir) {
+        Ok(logger) => {
+          let detail = match logger.is_global() {
+            true => format!("Logging to {}", logger.get_path().display()),
+            false => format!(
+              "Logging to {}, but events are still captured by an earlier logger",
+              logger.get_path().display()
+            ),
+          };
+          report.add("logger", ServiceStatus::Started(detail));
+          Some(logger)
+        }
+        Err(err) => {
+          report.add("logger", ServiceStatus::Failed(err));
+          None
+        }
+      },
+    };
 
     // Connect to to the graph database
-    println!(
-      "Initializing connection to Neo4j: {:?}",
-      config.services.wrangler_db
-    );
+    let mut graph_db = config.services.wrangler_db.clone();
+    let wrangler_db = match graph_db.init().and_then(|_| graph_db.verify()) {
+      Ok(()) => {
+        report.add(
+          "wrangler_db",
+          ServiceStatus::Started(describe_db(&graph_db)),
+        );
+        Some(graph_db)
+      }
+      Err(err) => {
+        report.add("wrangler_db", ServiceStatus::Failed(err));
+        None
+      }
+    };
 
+    let docker = config.services.docker.clone();
+    match docker {
+      Some(_) => report.add("docker", ServiceStatus::Started("Enabled".to_string())),
+      None => report.add(
+        "docker",
+        ServiceStatus::Skipped("Not configured".to_string()),
+      ),
+    }
+
+    report.log();
+    if wrangler_db.is_none() {
+      let err: AllWhat<WranglerErrorKind> = GraphDbError.into();
+      return Err(err.set_context(&format!(
+        "The workspace could not start the graph database:\n{}",
+        report
+      )));
+    }
+
+    Ok(Workspace {
+      config,
+      logger,
+      wrangler_db,
+      docker,
+      report,
+    })
+  }
+
+  pub fn get_config(&self) -> &WorkspaceConfig {
+    &self.config
+  }
+
+  pub fn get_logger(&self) -> Option<&Logger> {
+    self.logger.as_ref()
+  }
+
+  /// The database holding the submission data
+  pub fn get_graph_db(&self) -> Result<&GraphDb> {
+    self.wrangler_db.as_ref().ok_or_else(|| {
+      let err: AllWhat<WranglerErrorKind> = GraphDbError.into();
+      err.set_context("The graph database is not running in this workspace")
+    })
+  }
+
+  pub fn get_docker(&self) -> Option<&Docker> {
+    self.docker.as_ref()
+  }
+
+  pub fn get_report(&self) -> &StartupReport {
+    &self.report
+  }
+
+  /// Stop the services, saving any data held in memory and flushing the logs
+  pub fn shutdown(self) -> Result<()> {
+    tracing::info!("Shutting down the workspace");
+
+    let results = vec![
+      match &self.wrangler_db {
+        Some(db) => db.save().set_context("Could not save the graph database"),
+        None => Ok(()),
+      },
+      match &self.logger {
+        Some(logger) => logger.flush().set_context("Could not flush the logs"),
+        None => Ok(()),
+      },
+    ];
+
+    AllWhat::flatten(UnrecognizedError, results)
+      .set_context("The workspace did not shut down cleanly")?;
     Ok(())
   }
 }
 
+/// A short description of where the graph database is
+fn describe_db(db: &GraphDb) -> String {
+  match db.get_driver() {
+    Driver::Neo4j(config) => format!("Neo4j at {}/{}", config.get_uri(), db.get_db_name()),
+    Driver::Grapht(config) => match config.get_snapshot() {
+      Some(path) => format!("In-memory graph saved to {}", path.display()),
+      None => "In-memory graph (not saved)".to_string(),
+    },
+  }
+}
+
 impl fmt::Display for Workspace {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{:?}", self)
+    write!(f, "Workspace:\n{}", self.report)
   }
 }
@@ -0,0 +1,71 @@
+//! A summary of which services came up when the workspace started
+
+use crate::local::*;
+
+#[derive(Debug, Clone)]
+pub enum ServiceStatus {
+  Started(String),
+
+  /// The service was not configured to run
+  Skipped(String),
+  Failed(AllWhat<WranglerErrorKind>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StartupReport {
+  services: Vec<(String, ServiceStatus)>,
+}
+
+impl StartupReport {
+  pub fn add(&mut self, name: &str, status: ServiceStatus) {
+    self.services.push((name.to_string(), status));
+  }
+
+  pub fn get(&self, name: &str) -> Option<&ServiceStatus> {
+    self
+      .services
+      .iter()
+      .find(|(service, _)| service == name)
+      .map(|(_, status)| status)
+  }
+
+  pub fn services(&self) -> &[(String, ServiceStatus)] {
+    &self.services
+  }
+
+  /// The services that failed to start
+  pub fn failures(&self) -> Vec<&str> {
+    self
+      .services
+      .iter()
+      .filter(|(_, status)| matches!(status, ServiceStatus::Failed(_)))
+      .map(|(name, _)| name.as_str())
+      .collect()
+  }
+
+  /// Write the report to the tracing logs
+  pub fn log(&self) {
+    for (name, status) in self.services.iter() {
+      match status {
+        ServiceStatus::Started(detail) => tracing::info!("Started {}: {}", name, detail),
+        ServiceStatus::Skipped(detail) => tracing::info!("Skipped {}: {}", name, detail),
+        ServiceStatus::Failed(err) => tracing::error!("Failed to start {}: {}", name, err),
+      }
+    }
+  }
+}
+
+impl fmt::Display for StartupReport {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (name, status) in self.services.iter() {
+      match status {
+        ServiceStatus::Started(detail) => writeln!(f, "  [started] {}: {}", name, detail)?,
+        ServiceStatus::Skipped(detail) => writeln!(f, "  [skipped] {}: {}", name, detail)?,
+        ServiceStatus::Failed(err) => {
+          writeln!(f, "  [failed]  {}: {}", name, err.render_context())?
+        }
+      }
+    }
+    Ok(())
+  }
+}
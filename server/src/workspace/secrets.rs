@@ -0,0 +1,294 @@
+//! A local store for passwords, so they don't have to be written into the configuration
+//!
+//! Each secret is sealed with ChaCha20-Poly1305 using its name as the associated data, so entries
+//! can't be swapped between names. The key is a file the user chooses, which is generated on first
+//! use and readable only by its owner on unix. It has to live outside the store's directory, such as
+//! in the home directory, so copying the workspace doesn't hand out the key with the secrets.
+
+use crate::local::*;
+
+use ring::{
+  aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+  rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+use wrangler_common::configuration::{
+  primitives::password::{Password, PasswordSource},
+  traits::Configuration,
+};
+use zeroize::Zeroizing;
+
+/// The encrypted secrets
+pub const STORE_FILE: &str = "secrets.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Sealed {
+  nonce: String,
+  data: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct StoreFile {
+  #[serde(default)]
+  secrets: BTreeMap<String, Sealed>,
+}
+
+pub struct SecretStore {
+  dir: PathBuf,
+  key: LessSafeKey,
+  secrets: BTreeMap<String, Sealed>,
+}
+
+#[track_caller]
+fn store_error(kind: WranglerErrorKind, msg: &str) -> AllWhat<WranglerErrorKind> {
+  let err: AllWhat<WranglerErrorKind> = kind.into();
+  err.set_context(msg)
+}
+
+#[track_caller]
+fn io_error(err: std::io::Error, msg: &str) -> AllWhat<WranglerErrorKind> {
+  let result: AllWhat<WranglerErrorKind> = err.into();
+  result.set_context(msg)
+}
+
+/// Whether the key would be kept in the store's own directory
+pub fn key_in_store(key: &Path, dir: &Path) -> bool {
+  let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+  absolute(key).starts_with(absolute(dir))
+}
+
+impl SecretStore {
+  /// Open the store in a directory, creating it and its key if they don't exist yet
+  ///
+  /// The key has to be given, and be outside the directory.
+  pub fn open(dir: &Path, key: Option<&Path>) -> Result<SecretStore> {
+    let key = key.ok_or_else(|| {
+      store_error(
+        ConfigError,
+        "The secret store has no key. Set locations.secret_key or WRANGLER_SECRET_KEY_FILE to a file outside the workspace",
+      )
+    })?;
+    if key_in_store(key, dir) {
+      return Err(store_error(
+        ConfigError,
+        &format!(
+          "The secret key '{}' is inside the secret store '{}', where anyone who can read the store can read it",
+          key.display(),
+          dir.display()
+        ),
+      ));
+    }
+
+    fs::create_dir_all(dir).map_err(|err| {
+      io_error(
+        err,
+        &format!("Could not create the secret store '{}'", dir.display()),
+      )
+    })?;
+
+    let key = SecretStore::load_key(key)?;
+    let store_path = dir.join(STORE_FILE);
+    let secrets = match store_path.exists() {
+      false => BTreeMap::new(),
+      true => {
+        let text = fs::read_to_string(&store_path).map_err(|err| {
+          io_error(
+            err,
+            &format!("Could not read the secret store '{}'", store_path.display()),
+          )
+        })?;
+        let file: StoreFile = toml::from_str(&text).map_err(|err| {
+          store_error(
+            SerializationError,
+            &format!("The secret store '{}' is corrupt", store_path.display()),
+          )
+          .set_dev_context(&format!("From <toml>:\n{}", err))
+        })?;
+        file.secrets
+      }
+    };
+
+    Ok(SecretStore {
+      dir: dir.to_path_buf(),
+      key,
+      secrets,
+    })
+  }
+
+  fn load_key(path: &Path) -> Result<LessSafeKey> {
+    let bytes = match path.exists() {
+      true => Zeroizing::new(fs::read(path).map_err(|err| {
+        io_error(
+          err,
+          &format!("Could not read the secret key '{}'", path.display()),
+        )
+      })?),
+      false => {
+        if let Some(parent) = path
+          .parent()
+          .filter(|parent| !parent.as_os_str().is_empty())
+        {
+          fs::create_dir_all(parent).map_err(|err| {
+            io_error(
+              err,
+              &format!("Could not create the directory of '{}'", path.display()),
+            )
+          })?;
+        }
+        let mut bytes = Zeroizing::new(vec![0u8; CHACHA20_POLY1305.key_len()]);
+        SystemRandom::new()
+          .fill(&mut bytes)
+          .map_err(|_| store_error(UnrecognizedError, "Could not generate a secret key"))?;
+        write_private(path, &bytes)?;
+        bytes
+      }
+    };
+
+    let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes).map_err(|_| {
+      store_error(
+        ConfigError,
+        &format!("The secret key '{}' is not a valid key", path.display()),
+      )
+    })?;
+    Ok(LessSafeKey::new(key))
+  }
+
+  pub fn get_dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// The names of the stored secrets
+  pub fn names(&self) -> Vec<&str> {
+    self.secrets.keys().map(|name| name.as_str()).collect()
+  }
+
+  /// Decrypt a secret
+  pub fn get(&self, name: &str) -> Result<Password> {
+    let sealed = self.secrets.get(name).ok_or_else(|| {
+      store_error(
+        NotFound,
+        &format!(
+          "There is no secret named '{}' in '{}'",
+          name,
+          self.dir.display()
+        ),
+      )
+    })?;
+
+    let unreadable = || {
+      store_error(
+        ConfigError,
+        &format!(
+          "The secret '{}' could not be decrypted. The store or its key has been changed",
+          name
+        ),
+      )
+    };
+    let nonce: [u8; NONCE_LEN] = hex::decode(&sealed.nonce)
+      .ok()
+      .and_then(|nonce| nonce.try_into().ok())
+      .ok_or_else(unreadable)?;
+    let mut data = Zeroizing::new(hex::decode(&sealed.data).map_err(|_| unreadable())?);
+
+    let plain = self
+      .key
+      .open_in_place(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(name.as_bytes()),
+        &mut data,
+      )
+      .map_err(|_| unreadable())?;
+    let value = std::str::from_utf8(plain).map_err(|_| unreadable())?;
+
+    Ok(Password::new(value).set_source(PasswordSource::Store(name.to_string())))
+  }
+
+  /// Encrypt a secret and save the store, replacing any with the same name
+  pub fn set(&mut self, name: &str, password: &Password) -> Result<()> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+      .fill(&mut nonce)
+      .map_err(|_| store_error(UnrecognizedError, "Could not generate a nonce"))?;
+
+    let mut data = Zeroizing::new(password.value().as_bytes().to_vec());
+    self
+      .key
+      .seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(name.as_bytes()),
+        &mut *data,
+      )
+      .map_err(|_| store_error(UnrecognizedError, &format!("Could not encrypt '{}'", name)))?;
+
+    self.secrets.insert(
+      name.to_string(),
+      Sealed {
+        nonce: hex::encode(nonce),
+        data: hex::encode(&*data),
+      },
+    );
+    self.save()
+  }
+
+  /// Delete a secret, returning whether it existed
+  pub fn remove(&mut self, name: &str) -> Result<bool> {
+    match self.secrets.remove(name) {
+      Some(_) => self.save().map(|_| true),
+      None => Ok(false),
+    }
+  }
+
+  fn save(&self) -> Result<()> {
+    let file = StoreFile {
+      secrets: self.secrets.clone(),
+    };
+    let text = toml::to_string_pretty(&file).map_err(|err| {
+      store_error(SerializationError, "Could not write the secret store")
+        .set_dev_context(&format!("From <toml>:\n{:#?}", err))
+    })?;
+    write_private(&self.dir.join(STORE_FILE), text.as_bytes())
+  }
+}
+
+/// Counts the writes made by this process, so two threads writing at once use different files
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// Replace a file with one only the current user can read
+fn write_private(path: &Path, bytes: &[u8]) -> Result<()> {
+  // Named after the whole target, as the key and the store may only differ by extension
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(format!(
+    ".{}.{}.tmp",
+    std::process::id(),
+    WRITES.fetch_add(1, Ordering::Relaxed)
+  ));
+  let tmp = path.with_file_name(name);
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+  let written = options.open(&tmp).and_then(|mut file| {
+    std::io::Write::write_all(&mut file, bytes)?;
+    file.sync_all()
+  });
+  written.and_then(|_| fs::rename(&tmp, path)).map_err(|err| {
+    let _ = fs::remove_file(&tmp);
+    io_error(err, &format!("Could not write '{}'", path.display()))
+  })
+}
+
+impl fmt::Debug for SecretStore {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SecretStore")
+      .field("dir", &self.dir)
+      .field("names", &self.names())
+      .finish()
+  }
+}
@@ -0,0 +1,113 @@
+//! Load, validate and save the workspace configuration file
+
+use wrangler_server::workspace::{file::ConfigFile, WorkspaceConfig};
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+  pairs
+    .iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect()
+}
+
+#[test]
+fn empty_file_uses_defaults() {
+  let file = ConfigFile::parse("").unwrap();
+  assert_eq!(file.services.wrangler_db.driver, "neo4j");
+  assert_eq!(file.locations.log, "./local/logs");
+  file.validate().unwrap();
+}
+
+#[test]
+fn save_and_load_round_trip() {
+  let path = std::env::temp_dir()
+    .join(format!("wrangler-{}", uuid::Uuid::new_v4()))
+    .join("workspace.toml");
+
+  let config = ConfigFile::parse(
+    r#"
+    [locations]
+    log = "/tmp/wrangler/logs"
+
+    [services.wrangler_db]
+    driver = "grapht"
+    db_name = "wrangler"
+    grapht = { snapshot = "/tmp/wrangler/data.grapht" }
+    "#,
+  )
+  .unwrap()
+  .validate()
+  .unwrap();
+
+  config.save(&path).unwrap();
+  let loaded = WorkspaceConfig::load(&path).unwrap();
+  std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+  let (saved, loaded) = (ConfigFile::from(&config), ConfigFile::from(&loaded));
+  assert_eq!(saved.to_toml().unwrap(), loaded.to_toml().unwrap());
+  assert_eq!(
+    loaded.services.wrangler_db.grapht.snapshot.as_deref(),
+    Some("/tmp/wrangler/data.grapht")
+  );
+}
+
+#[test]
+fn environment_overrides_the_file() {
+  let file = ConfigFile::parse("[services.wrangler_db.neo4j]\nuri = \"bolt://127.0.0.1:7687\"")
+    .unwrap()
+    .with_overrides(vars(&[
+      ("WRANGLER_NEO4J_URI", "neo4j+s://db.example.com:7687"),
+      ("WRANGLER_NEO4J_USER", "wrangler"),
+      ("UNRELATED", "ignored"),
+    ]));
+
+  assert_eq!(
+    file.services.wrangler_db.neo4j.uri,
+    "neo4j+s://db.example.com:7687"
+  );
+  assert_eq!(file.services.wrangler_db.neo4j.user, "wrangler");
+  file.validate().unwrap();
+}
+
+#[test]
+fn errors_point_at_the_bad_keys() {
+  let file = ConfigFile::parse(
+    r#"
+    [locations]
+    log = ""
+
+    [services.wrangler_db]
+    db_name = "neo4j"
+    "#,
+  )
+  .unwrap()
+  .with_overrides(vars(&[
+    ("WRANGLER_NEO4J_URI", "not a uri"),
+    ("WRANGLER_DOCKER", "maybe"),
+  ]));
+
+  let report = format!("{:?}", file.validate().unwrap_err());
+  assert!(report.contains("locations.log: must not be empty"));
+  assert!(report.contains("services.wrangler_db.neo4j.uri (set by WRANGLER_NEO4J_URI)"));
+  assert!(report.contains("services.docker (set by WRANGLER_DOCKER)"));
+
+  let driver = ConfigFile::parse("[services.wrangler_db]\ndriver = \"mysql\"").unwrap();
+  let report = format!("{:?}", driver.validate().unwrap_err());
+  assert!(report.contains("services.wrangler_db.driver"));
+
+  // Misspelled keys are rejected instead of silently ignored
+  let unknown = ConfigFile::parse("[services.wrangler_db]\ndb_nmae = \"neo4j\"").unwrap_err();
+  assert!(format!("{:?}", unknown).contains("db_nmae"));
+}
+
+#[test]
+fn each_failed_constraint_is_reported() {
+  let file =
+    ConfigFile::parse("[services.wrangler_db.neo4j]\nuri = \"https://db.example.com\"").unwrap();
+
+  let report = format!("{:?}", file.validate().unwrap_err());
+  assert!(report.contains(
+    "services.wrangler_db.neo4j.uri: 'https://db.example.com/' must use one of the schemes"
+  ));
+  assert!(report
+    .contains("services.wrangler_db.neo4j.uri: 'https://db.example.com/' must include a port"));
+}
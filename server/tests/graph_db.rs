@@ -0,0 +1,32 @@
+//! Connections to the in-memory graph
+
+use wrangler_common::{
+  configuration::apps::grapht::GraphtConfig, grapht::prelude::*, model::organization::Organization,
+};
+use wrangler_server::services::graph_db::{GraphDbConnection, GraphDbDriver};
+
+fn count_orgs(conn: &impl GraphDbConnection) -> Value {
+  let rows = conn
+    .find(
+      "MATCH (o:Organization) RETURN count(o) AS orgs",
+      Properties::new(),
+    )
+    .unwrap();
+  rows[0]["orgs"].clone()
+}
+
+#[test]
+fn connections_share_one_graph() {
+  let config = GraphtConfig::new();
+  let first = config.connect("neo4j").unwrap();
+  let second = config.clone().connect("neo4j").unwrap();
+
+  first
+    .create(Box::new(Organization::sample("Shared Vet")))
+    .unwrap();
+  assert_eq!(count_orgs(&second), Value::Integer(1));
+
+  // A new config starts its own graph
+  let other = GraphtConfig::new().connect("neo4j").unwrap();
+  assert_eq!(count_orgs(&other), Value::Integer(0));
+}
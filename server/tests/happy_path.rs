@@ -3,30 +3,63 @@
 //! This integration test emulates the calls that a GUI might make as a user enters a submission
 //! from a clean install
 
-use wrangler_common::configuration::apps::neo4j::*;
-use wrangler_server::{
-  prelude::*,
-  services::graph_db::{neo4j::*, Driver},
-};
+use std::path::{Path, PathBuf};
 
-fn workspace_init() {
+use wrangler_common::{grapht::prelude::*, model::organization::Organization};
+use wrangler_server::{prelude::*, services::graph_db::GraphDbConnection};
+
+/// A scratch directory for the workspace's files
+fn scratch() -> PathBuf {
+  std::env::temp_dir().join(format!("wrangler-happy-path-{}", uuid::Uuid::new_v4()))
+}
+
+fn workspace_init(dir: &Path) -> Workspace {
   println!("Initializing the workspace");
-  let config = WorkspaceConfig::default();
+  let config = WorkspaceConfig::standalone(dir.join("wrangler.grapht"))
+    .with_log_dir(&dir.join("logs").to_string_lossy());
   println!("{config}");
 
-  let workspaces = Workspace::init(config).unwrap();
-  println!("{workspace}")
+  let workspace = Workspace::init(config).unwrap();
+  println!("{workspace}");
+  workspace
+}
+
+fn count_orgs(workspace: &Workspace) -> Value {
+  let rows = workspace
+    .get_graph_db()
+    .unwrap()
+    .query(
+      "MATCH (o:Organization) RETURN count(o) AS orgs",
+      Properties::new(),
+    )
+    .unwrap();
+  rows[0]["orgs"].clone()
 }
 
 #[test]
 fn happy_path() {
-  let config = Neo4jConfig.new("127.0.0.1:7687", "neo4j", "neo4j");
+  let dir = scratch();
 
   // Make the workspace
-  // workspace_init();
+  let workspace = workspace_init(&dir);
+  assert!(workspace.get_report().failures().is_empty());
+  assert!(workspace.get_logger().is_some());
 
   // Query the organizations
+  assert_eq!(count_orgs(&workspace), Value::Integer(0));
+
+  // Add one
+  let conn = workspace.get_graph_db().unwrap().get_connection().unwrap();
+  conn
+    .create(Box::new(Organization::sample("Happy Vet")))
+    .unwrap();
+  assert_eq!(count_orgs(&workspace), Value::Integer(1));
+
+  // Restart, and the organization should still be there
+  workspace.shutdown().unwrap();
+  let workspace = workspace_init(&dir);
+  assert_eq!(count_orgs(&workspace), Value::Integer(1));
+  workspace.shutdown().unwrap();
 
-  // Call import
-  // Re-query the organizations
+  std::fs::remove_dir_all(&dir).unwrap();
 }
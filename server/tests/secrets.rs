@@ -0,0 +1,135 @@
+//! Keep the database password in the encrypted store instead of the configuration file
+
+use wrangler_common::configuration::{
+  primitives::password::{Password, PasswordSource},
+  traits::Configuration,
+};
+use wrangler_server::workspace::{
+  file::{ConfigFile, NEO4J_SECRET},
+  secrets::{SecretStore, STORE_FILE},
+  WorkspaceConfig,
+};
+
+fn scratch_dir() -> std::path::PathBuf {
+  std::env::temp_dir().join(format!("wrangler-{}", uuid::Uuid::new_v4()))
+}
+
+#[test]
+fn store_round_trip() {
+  let (dir, keys) = (scratch_dir(), scratch_dir());
+  let key = keys.join("secrets.key");
+  let mut store = SecretStore::open(&dir, Some(&key)).unwrap();
+  store.set("neo4j", &Password::new("hunter2")).unwrap();
+
+  // The value is only readable with the key, which isn't kept with the store
+  let text = std::fs::read_to_string(dir.join(STORE_FILE)).unwrap();
+  assert!(!text.contains("hunter2"));
+  let files: Vec<String> = std::fs::read_dir(&dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+    .collect();
+  assert_eq!(files, vec![STORE_FILE]);
+  assert!(key.is_file());
+
+  let store = SecretStore::open(&dir, Some(&key)).unwrap();
+  assert_eq!(store.names(), vec!["neo4j"]);
+  let password = store.get("neo4j").unwrap();
+  assert_eq!(password.value(), "hunter2");
+  assert_eq!(
+    password.get_source(),
+    Some(&PasswordSource::Store("neo4j".to_string()))
+  );
+  assert!(store.get("missing").is_err());
+
+  // Moving a secret to another name is caught
+  std::fs::write(
+    dir.join(STORE_FILE),
+    text.replace("[secrets.neo4j]", "[secrets.other]"),
+  )
+  .unwrap();
+  let err = SecretStore::open(&dir, Some(&key))
+    .unwrap()
+    .get("other")
+    .unwrap_err();
+  assert!(format!("{}", err).contains("could not be decrypted"));
+
+  std::fs::remove_dir_all(&dir).unwrap();
+  std::fs::remove_dir_all(&keys).unwrap();
+}
+
+#[test]
+fn the_key_is_kept_apart_from_the_store() {
+  let dir = scratch_dir();
+  let err = SecretStore::open(&dir, None).unwrap_err();
+  assert!(format!("{}", err).contains("has no key"));
+  let err = SecretStore::open(&dir, Some(&dir.join("secrets.key"))).unwrap_err();
+  assert!(format!("{}", err).contains("is inside the secret store"));
+  assert!(!dir.exists(), "nothing is created");
+
+  let text = format!(
+    "[locations]\nsecrets = \"{}\"\nsecret_key = \"{}\"",
+    dir.display(),
+    dir.join("keys").join("secrets.key").display()
+  );
+  let err = ConfigFile::parse(&text).unwrap().validate().unwrap_err();
+  assert!(format!("{:?}", err).contains("locations.secret_key: must be outside"));
+}
+
+#[test]
+fn configuration_reads_the_store_and_never_saves_the_password() {
+  let dir = scratch_dir();
+  let (secrets, key) = (dir.join("secrets"), dir.join("secrets.key"));
+  SecretStore::open(&secrets, Some(&key))
+    .unwrap()
+    .set("neo4j", &Password::new("hunter2"))
+    .unwrap();
+
+  let text = format!(
+    "[locations]\nsecrets = \"{}\"\nsecret_key = \"{}\"\n\n[services.wrangler_db.neo4j]\npassword = {{ store = \"neo4j\" }}",
+    secrets.display(),
+    key.display()
+  );
+  let config = ConfigFile::parse(&text).unwrap().validate().unwrap();
+  let saved = ConfigFile::from(&config).to_toml().unwrap();
+  assert!(saved.contains("[services.wrangler_db.neo4j.password]\nstore = \"neo4j\""));
+  ConfigFile::parse(&saved).unwrap().validate().unwrap();
+  assert!(!config.to_string().contains("hunter2"));
+
+  // A password typed into the file is never written back out as it is
+  let plain = ConfigFile::parse("[services.wrangler_db.neo4j]\npassword = \"hunter2\"")
+    .unwrap()
+    .validate()
+    .unwrap();
+  let saved = ConfigFile::from(&plain).to_toml().unwrap();
+  assert!(!saved.contains("hunter2"));
+  assert!(saved.contains("password = \"*******\""));
+  let err = ConfigFile::parse(&saved).unwrap().validate().unwrap_err();
+  assert!(format!("{:?}", err).contains("services.wrangler_db.neo4j.password: was redacted"));
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saving_moves_a_typed_password_into_the_store() {
+  let dir = scratch_dir();
+  let (secrets, key) = (dir.join("secrets"), dir.join("secrets.key"));
+  let text = format!(
+    "[locations]\nsecrets = \"{}\"\nsecret_key = \"{}\"\n\n[services.wrangler_db.neo4j]\npassword = \"hunter2\"",
+    secrets.display(),
+    key.display()
+  );
+  let config = ConfigFile::parse(&text).unwrap().validate().unwrap();
+
+  let path = dir.join("wrangler.toml");
+  config.save(&path).unwrap();
+  let saved = std::fs::read_to_string(&path).unwrap();
+  assert!(!saved.contains("hunter2"));
+  assert!(saved.contains("[services.wrangler_db.neo4j.password]\nstore = \"neo4j\""));
+
+  // The saved file loads again, with the same password
+  let loaded = WorkspaceConfig::load(&path).unwrap();
+  let password = loaded.secret_store().unwrap().get(NEO4J_SECRET).unwrap();
+  assert_eq!(password.value(), "hunter2");
+
+  std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,57 @@
+//! Starting and stopping the workspace services
+
+use wrangler_server::workspace::{file::ConfigFile, report::ServiceStatus, Workspace};
+
+#[test]
+fn unreachable_database_fails_with_a_report() {
+  let dir = std::env::temp_dir().join(format!("wrangler-{}", uuid::Uuid::new_v4()));
+  let config = ConfigFile::parse(&format!(
+    r#"
+    [locations]
+    log = "{}"
+
+    [services.wrangler_db.neo4j]
+    uri = "bolt://127.0.0.1:1"
+    "#,
+    dir.join("logs").display()
+  ))
+  .unwrap()
+  .validate()
+  .unwrap();
+
+  let err = format!("{}", Workspace::init(config).unwrap_err());
+  let _ = std::fs::remove_dir_all(&dir);
+
+  assert!(err.contains("[failed]  wrangler_db"));
+  assert!(err.contains("[started] logger"));
+  assert!(err.contains("[skipped] docker"));
+}
+
+#[test]
+fn disabled_services_are_skipped() {
+  let config = ConfigFile::parse(
+    r#"
+    [services]
+    logger = false
+
+    [services.wrangler_db]
+    driver = "grapht"
+    "#,
+  )
+  .unwrap()
+  .validate()
+  .unwrap();
+
+  let workspace = Workspace::init(config).unwrap();
+  assert!(workspace.get_logger().is_none());
+  assert!(matches!(
+    workspace.get_report().get("logger"),
+    Some(ServiceStatus::Skipped(_))
+  ));
+  assert!(matches!(
+    workspace.get_report().get("wrangler_db"),
+    Some(ServiceStatus::Started(_))
+  ));
+
+  workspace.shutdown().unwrap();
+}
@@ -6,6 +6,7 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use neo4rs::*;
+use wrangler_common::configuration::{apps::neo4j::Neo4jConfig, traits::Configuration};
 
 pub mod model {
   pub struct Organization {
@@ -15,13 +16,6 @@ pub mod model {
   }
 }
 
-#[derive(Clone, Debug)]
-pub struct Neo4jConfig {
-  pub uri: String,
-  pub username: String,
-  pub password: String,
-}
-
 #[derive(Clone)]
 pub struct Neo4jConnection {
   // /// Tokio::Runtime - an async loop for temporarily making the connection synchronous.
@@ -32,9 +26,13 @@ pub struct Neo4jConnection {
 impl Neo4jConnection {
   pub async fn connect(config: Neo4jConfig) -> core::result::Result<Neo4jConnection, String> {
     println!("Connecting to db with config: {:#?}", config);
-    let graph = Graph::new(&config.uri, config.username, config.password)
-      .await
-      .unwrap();
+    let graph = Graph::new(
+      &config.get_uri(),
+      config.get_username(),
+      config.get_password().value(),
+    )
+    .await
+    .unwrap();
 
     Ok(Neo4jConnection {
       graph: Arc::new(graph),
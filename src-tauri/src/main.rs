@@ -11,42 +11,67 @@ use tokio::sync::Mutex;
 use tracing::info;
 
 mod graph_db;
-use graph_db::{Neo4jConfig, Neo4jConnection};
+use graph_db::Neo4jConnection;
+
+use wrangler_common::{
+  calls::{Call, Reply, Request, Response},
+  configuration::{apps::neo4j::Neo4jConfig, primitives::password::Password},
+  errors::{
+    allwhat::ResultPlus,
+    AllWhat,
+    WranglerErrorKind::{self, *},
+  },
+  tools::json,
+};
 
 // Create a connection to the Neo4j server
 
 struct AsyncProcInputTx {
-  inner: Mutex<mpsc::Sender<String>>,
+  inner: Mutex<mpsc::Sender<Request>>,
 }
 
-fn rs2js<R: tauri::Runtime>(message: String, manager: &impl Manager<R>) {
-  info!(?message, "Replying using rs2js:");
-  manager
-    .emit_all("rs2js", format!("rs: {}", message))
-    .unwrap();
+/// Send a reply to the client, which pairs it with its request by id
+fn rs2js<R: tauri::Runtime>(reply: Reply, manager: &impl Manager<R>) {
+  info!(?reply, "Replying using rs2js:");
+  manager.emit_all("rs2js", reply).unwrap();
 }
 
 /// Receive a message from the client and forwards it along to the server side
+///
+/// Failures are returned as an [AllWhat] tree so the client can render them
 #[tauri::command]
 async fn call_server(
   message: String,
   state: tauri::State<'_, AsyncProcInputTx>,
-) -> Result<(), String> {
+) -> Result<(), AllWhat<WranglerErrorKind>> {
   info!(?message, "Received tauri::command: call_server");
+  let request: Request = json::from_str(&message)
+    .set_context("The call sent to the server could not be read")
+    .map_err(|err| err.for_client())?;
+
   let async_proc_input_tx = state.inner.lock().await;
-  async_proc_input_tx
-    .send(message)
-    .await
-    .map_err(|e| e.to_string())
+  async_proc_input_tx.send(request).await.map_err(|err| {
+    let result: AllWhat<WranglerErrorKind> = TokioError.into();
+    result
+      .set_context("The server is no longer accepting calls")
+      .set_dev_context(&format!("From <tokio::sync::mpsc>:\n{:#?}", err))
+      .for_client()
+  })
 }
 
 async fn async_process_model(
-  mut input_rx: mpsc::Receiver<String>,
-  output_tx: mpsc::Sender<String>,
+  mut input_rx: mpsc::Receiver<Request>,
+  output_tx: mpsc::Sender<Reply>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-  while let Some(input) = input_rx.recv().await {
-    let output = input;
-    output_tx.send(output).await?;
+  while let Some(request) = input_rx.recv().await {
+    let response = match &request.call {
+      Call::Heartbeat => Response::Heartbeat,
+      call => {
+        let err: AllWhat<WranglerErrorKind> = UnrecognizedError.into();
+        Response::Error(err.set_context(&format!("The server can't handle {:?} yet", call)))
+      }
+    };
+    output_tx.send(request.reply(response)).await?;
   }
 
   Ok(())
@@ -56,11 +81,16 @@ async fn async_process_model(
 async fn db_connect() -> core::result::Result<Neo4jConnection, String> {
   // A singleton workspace shared by the entire Tauri App
   // let workspace = Workspace::init(WorkspaceConfig::Default());
-  let graph_config = Neo4jConfig {
-    uri: "localhost:7687".to_string(),
-    username: "neo4j".to_string(),
-    password: "neo_pass".to_string(),
+  // Keep the credentials out of the source
+  let password = match std::env::var("WRANGLER_NEO4J_PASSWORD_FILE") {
+    Ok(path) => Password::from_file(&path),
+    Err(_) => Password::from_env("WRANGLER_NEO4J_PASSWORD").set_context(
+      "Set WRANGLER_NEO4J_PASSWORD to the Neo4j password, or WRANGLER_NEO4J_PASSWORD_FILE to a file holding it",
+    ),
   };
+  let graph_config = password
+    .and_then(|password| Neo4jConfig::new("bolt://127.0.0.1:7687", "neo4j", password))
+    .map_err(|err| err.to_string())?;
 
   let conn = Neo4jConnection::connect(graph_config).await?;
   conn.ping().await?;